@@ -0,0 +1,178 @@
+use crate::{
+    bitboard::Bitboard,
+    board::{Color, Position, Square, EMPTY, WHITE},
+    magic::{bishop_attacks, rook_attacks},
+    masks::{b_pawn_capture_mask, w_pawn_capture_mask, KING_MASK, KNIGHT_MASK},
+    piece::{Piece, PieceKind},
+};
+
+//all squares attacked by a piece on the square, sliders stop at the first occupied square
+//the attacked squares can be empty or occupied by pieces of either color
+pub fn attacks_from(piece: Piece, square: Square, occupancy: Bitboard) -> Bitboard {
+    match piece.kind() {
+        PieceKind::Pawn => pawn_attacks(piece.color(), square.bitboard()),
+        PieceKind::Knight => KNIGHT_MASK[square],
+        PieceKind::Bishop => bishop_attacks(square, occupancy),
+        PieceKind::Rook => rook_attacks(square, occupancy),
+        PieceKind::Queen => bishop_attacks(square, occupancy) | rook_attacks(square, occupancy),
+        PieceKind::King => KING_MASK[square],
+    }
+}
+
+#[inline]
+fn pawn_attacks(color: Color, pawns: Bitboard) -> Bitboard {
+    if color == WHITE {
+        w_pawn_capture_mask(&pawns)
+    } else {
+        b_pawn_capture_mask(&pawns)
+    }
+}
+
+impl Position {
+    pub fn piece_boards(&self, color: Color) -> &[Bitboard; 6] {
+        if color == WHITE {
+            &self.w_piece_board
+        } else {
+            &self.b_piece_board
+        }
+    }
+
+    pub fn color_board(&self, color: Color) -> Bitboard {
+        if color == WHITE {
+            self.w_board
+        } else {
+            self.b_board
+        }
+    }
+
+    pub fn king_square(&self, color: Color) -> Square {
+        self.piece_boards(color)[PieceKind::King].lsb()
+    }
+
+    //all pieces of the color which attack the square
+    pub fn attackers_to(&self, square: Square, color: Color) -> Bitboard {
+        self.attackers_to_with_occupancy(square, color, self.blocker_board)
+    }
+
+    //all pieces of the color which would attack the square if the occupied squares were different,
+    //used to look at the position after a move without making it
+    pub fn attackers_to_with_occupancy(
+        &self,
+        square: Square,
+        color: Color,
+        occupancy: Bitboard,
+    ) -> Bitboard {
+        let pieces = self.piece_boards(color);
+        //a pawn attacks the square if a pawn of the other color on the square would attack the pawn
+        (pawn_attacks(!color, square.bitboard()) & pieces[PieceKind::Pawn])
+            | (KNIGHT_MASK[square] & pieces[PieceKind::Knight])
+            | (KING_MASK[square] & pieces[PieceKind::King])
+            | (bishop_attacks(square, occupancy)
+                & (pieces[PieceKind::Bishop] | pieces[PieceKind::Queen]))
+            | (rook_attacks(square, occupancy)
+                & (pieces[PieceKind::Rook] | pieces[PieceKind::Queen]))
+    }
+
+    pub fn is_attacked(&self, square: Square, by: Color) -> bool {
+        self.attackers_to(square, by) != EMPTY
+    }
+
+    //the enemy pieces checking the king of the side to move
+    pub fn checkers(&self) -> Bitboard {
+        self.attackers_to(self.king_square(self.color_to_move), !self.color_to_move)
+    }
+
+    //pieces of the color which can not leave the line between their king and an enemy slider
+    pub fn pinned_pieces(&self, color: Color) -> Bitboard {
+        let mut pinned_pieces = EMPTY;
+        self.for_each_pin(color, |pinned, _| pinned_pieces |= pinned.bitboard());
+        pinned_pieces
+    }
+
+    //the enemy slider which pins the piece on the square to its king
+    pub fn pinner_of(&self, square: Square) -> Option<Square> {
+        let color = self.piece_on(square)?.color();
+        let mut pinner_of_square = None;
+        self.for_each_pin(color, |pinned, pinner| {
+            if pinned == square {
+                pinner_of_square = Some(pinner);
+            }
+        });
+        pinner_of_square
+    }
+
+    //calls the closure with every pinned piece of the color and the slider pinning it
+    fn for_each_pin(&self, color: Color, mut pin: impl FnMut(Square, Square)) {
+        let king = self.king_square(color);
+        let enemy_pieces = self.piece_boards(!color);
+        let king_rook_rays = rook_attacks(king, EMPTY);
+        let king_bishop_rays = bishop_attacks(king, EMPTY);
+        //enemy sliders which would attack the king if the board was empty
+        let snipers = (king_rook_rays
+            & (enemy_pieces[PieceKind::Rook] | enemy_pieces[PieceKind::Queen]))
+            | (king_bishop_rays
+                & (enemy_pieces[PieceKind::Bishop] | enemy_pieces[PieceKind::Queen]));
+        for sniper in snipers {
+            let between = if king_rook_rays.contains(sniper) {
+                rook_attacks(king, sniper.bitboard()) & rook_attacks(sniper, king.bitboard())
+            } else {
+                bishop_attacks(king, sniper.bitboard()) & bishop_attacks(sniper, king.bitboard())
+            };
+            let blockers = between & self.blocker_board;
+            if blockers.popcount() == 1 && blockers & self.color_board(color) != EMPTY {
+                pin(blockers.lsb(), sniper);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BLACK;
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    fn squares(names: &[&str]) -> Bitboard {
+        names.iter().map(|name| square(name)).collect()
+    }
+
+    #[test]
+    fn attacks_and_pins() {
+        //the queen, rook and bishop each pin a white piece while the knight gives check
+        let pos = Position::from_fen("4k3/4r3/8/q7/8/5n2/3NP3/4K1Br w - - 0 1");
+
+        assert_eq!(squares(&["f3"]), pos.checkers());
+        assert_eq!(squares(&["d2", "e2", "g1"]), pos.pinned_pieces(WHITE));
+        assert_eq!(EMPTY, pos.pinned_pieces(BLACK));
+        assert_eq!(Some(square("a5")), pos.pinner_of(square("d2")));
+        assert_eq!(Some(square("e7")), pos.pinner_of(square("e2")));
+        assert_eq!(Some(square("h1")), pos.pinner_of(square("g1")));
+        assert_eq!(None, pos.pinner_of(square("e1")));
+        assert_eq!(None, pos.pinner_of(square("e4")));
+
+        assert_eq!(
+            squares(&["a5", "f3"]),
+            pos.attackers_to(square("d2"), BLACK)
+        );
+        assert_eq!(squares(&["e1"]), pos.attackers_to(square("d2"), WHITE));
+        assert_eq!(squares(&["e7"]), pos.attackers_to(square("e2"), BLACK));
+        assert!(pos.is_attacked(square("f1"), WHITE));
+        assert!(!pos.is_attacked(square("f1"), BLACK));
+
+        let white_pawn = Piece::new(PieceKind::Pawn, WHITE);
+        let black_queen = Piece::new(PieceKind::Queen, BLACK);
+        assert_eq!(
+            squares(&["d3", "f3"]),
+            attacks_from(white_pawn, square("e2"), pos.blocker_board)
+        );
+        let queen_attacks = attacks_from(black_queen, square("a5"), pos.blocker_board);
+        assert!(queen_attacks.contains(square("d2")) && !queen_attacks.contains(square("e1")));
+        assert_eq!(
+            14 + 7,
+            attacks_from(black_queen, square("a5"), EMPTY).popcount()
+        );
+    }
+}
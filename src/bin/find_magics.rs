@@ -0,0 +1,22 @@
+use chessengine::magic::{find_magics, MagicNumber};
+
+//searches for magic numbers at runtime and prints them as the constants used in magic.rs
+//cargo run --release --bin find_magics
+fn main() {
+    let (rook_magic, bishop_magic) = find_magics();
+    print_constant("ROOK_MAGIC", &rook_magic);
+    print_constant("BISHOP_MAGIC", &bishop_magic);
+}
+
+fn print_constant(name: &str, magic: &[MagicNumber; 64]) {
+    println!("#[rustfmt::skip]");
+    println!("pub const {}: [MagicNumber; 64] = [", name);
+    for row in magic.chunks(4) {
+        let row: Vec<String> = row
+            .iter()
+            .map(|magic| format!("0x{:016X}", magic))
+            .collect();
+        println!("    {},", row.join(", "));
+    }
+    println!("];");
+}
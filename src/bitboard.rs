@@ -0,0 +1,297 @@
+use crate::board::{Square, FILE, NOT_ON_A_FILE, NOT_ON_H_FILE, RANK};
+use std::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, ShlAssign, Shr,
+    ShrAssign,
+};
+
+//a set of squares with one bit per square, h1 is the lowest bit and a8 the highest
+//the wrapper is transparent and every method is inlined, so it compiles to the same
+//instructions as working on the u64 directly
+#[repr(transparent)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    //file 0 is the a-file
+    #[inline]
+    pub const fn file(file: u8) -> Bitboard {
+        FILE[file as usize]
+    }
+
+    //rank 0 is the first rank
+    #[inline]
+    pub const fn rank(rank: u8) -> Bitboard {
+        RANK[rank as usize]
+    }
+
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline]
+    pub const fn popcount(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    #[inline]
+    pub const fn contains(self, square: Square) -> bool {
+        self.0 & square.bitboard().0 != 0
+    }
+
+    //the square of the lowest bit, the bitboard must not be empty
+    #[inline]
+    pub const fn lsb(self) -> Square {
+        debug_assert!(self.0 != 0);
+        Square::new(self.0.trailing_zeros() as u8)
+    }
+
+    //removes the lowest bit and returns its square
+    #[inline]
+    pub fn pop_lsb(&mut self) -> Square {
+        let square = self.lsb();
+        self.0 &= self.0 - 1;
+        square
+    }
+
+    //the next subset of the set after this subset, all subsets are enumerated when starting
+    //from the empty set until the empty set is returned again
+    //https://www.chessprogramming.org/Traversing_Subsets_of_a_Set#All_Subsets_of_any_Set
+    #[inline]
+    pub const fn next_subset_of(self, set: Bitboard) -> Bitboard {
+        Bitboard(self.0.wrapping_sub(set.0) & set.0)
+    }
+
+    //shifts by one square in a direction, squares shifted off the board are removed
+    #[inline]
+    pub const fn north(self) -> Bitboard {
+        Bitboard(self.0 << 8)
+    }
+
+    #[inline]
+    pub const fn south(self) -> Bitboard {
+        Bitboard(self.0 >> 8)
+    }
+
+    #[inline]
+    pub const fn east(self) -> Bitboard {
+        Bitboard((self.0 & NOT_ON_H_FILE.0) >> 1)
+    }
+
+    #[inline]
+    pub const fn west(self) -> Bitboard {
+        Bitboard((self.0 & NOT_ON_A_FILE.0) << 1)
+    }
+
+    #[inline]
+    pub const fn north_east(self) -> Bitboard {
+        Bitboard((self.0 & NOT_ON_H_FILE.0) << 7)
+    }
+
+    #[inline]
+    pub const fn north_west(self) -> Bitboard {
+        Bitboard((self.0 & NOT_ON_A_FILE.0) << 9)
+    }
+
+    #[inline]
+    pub const fn south_east(self) -> Bitboard {
+        Bitboard((self.0 & NOT_ON_H_FILE.0) >> 9)
+    }
+
+    #[inline]
+    pub const fn south_west(self) -> Bitboard {
+        Bitboard((self.0 & NOT_ON_A_FILE.0) >> 7)
+    }
+}
+
+//iterates over the squares from the lowest to the highest bit
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    #[inline]
+    fn next(&mut self) -> Option<Square> {
+        if self.0.is_empty() {
+            None
+        } else {
+            Some(self.0.pop_lsb())
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.popcount() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Squares {}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = Squares;
+
+    #[inline]
+    fn into_iter(self) -> Squares {
+        Squares(self)
+    }
+}
+
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Square>>(squares: I) -> Bitboard {
+        let mut bitboard = Bitboard(0);
+        for square in squares {
+            bitboard |= square.bitboard();
+        }
+        bitboard
+    }
+}
+
+macro_rules! bit_operator {
+    ($trait:ident, $fn:ident, $assign_trait:ident, $assign_fn:ident, $op:tt) => {
+        impl $trait for Bitboard {
+            type Output = Bitboard;
+
+            #[inline]
+            fn $fn(self, rhs: Bitboard) -> Bitboard {
+                Bitboard(self.0 $op rhs.0)
+            }
+        }
+
+        impl $assign_trait for Bitboard {
+            #[inline]
+            fn $assign_fn(&mut self, rhs: Bitboard) {
+                self.0 = self.0 $op rhs.0;
+            }
+        }
+    };
+}
+
+bit_operator!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+bit_operator!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+bit_operator!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    #[inline]
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+impl Shl<u32> for Bitboard {
+    type Output = Bitboard;
+
+    #[inline]
+    fn shl(self, rhs: u32) -> Bitboard {
+        Bitboard(self.0 << rhs)
+    }
+}
+
+impl Shr<u32> for Bitboard {
+    type Output = Bitboard;
+
+    #[inline]
+    fn shr(self, rhs: u32) -> Bitboard {
+        Bitboard(self.0 >> rhs)
+    }
+}
+
+impl ShlAssign<u32> for Bitboard {
+    #[inline]
+    fn shl_assign(&mut self, rhs: u32) {
+        self.0 <<= rhs;
+    }
+}
+
+impl ShrAssign<u32> for Bitboard {
+    #[inline]
+    fn shr_assign(&mut self, rhs: u32) {
+        self.0 >>= rhs;
+    }
+}
+
+//the board from white's perspective with the rank and file names around it
+impl std::fmt::Display for Bitboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for rank in (0..8).rev() {
+            write!(f, "{} ", rank + 1)?;
+            for file in 0..8 {
+                if self.contains(Square::from_file_rank(file, rank)) {
+                    write!(f, " x")?;
+                } else {
+                    write!(f, " .")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        write!(f, "   a b c d e f g h")
+    }
+}
+
+impl std::fmt::Debug for Bitboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Bitboard({:#018x})", self.0)?;
+        write!(f, "{}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_and_shifts() {
+        let square = |name| Square::from_algebraic(name).unwrap();
+        let bitboard: Bitboard = [square("a1"), square("e4"), square("h8")]
+            .into_iter()
+            .collect();
+        assert_eq!(3, bitboard.popcount());
+        assert!(bitboard.contains(square("e4")) && !bitboard.contains(square("e5")));
+        assert_eq!(
+            vec![square("a1"), square("e4"), square("h8")],
+            bitboard.into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Bitboard::file(4) & Bitboard::rank(3),
+            square("e4").bitboard()
+        );
+
+        //pieces on the edge are not shifted around the board
+        assert_eq!(
+            square("b2").bitboard(),
+            bitboard.north_east() & Bitboard::rank(1)
+        );
+        assert!(Bitboard::file(0).west().is_empty());
+        assert!(Bitboard::file(7).east().is_empty());
+        assert!(Bitboard::rank(7).north().is_empty());
+        assert_eq!(Bitboard::file(1), Bitboard::file(0).east());
+        assert_eq!(Bitboard::rank(2), Bitboard::rank(3).south());
+
+        let e4 = square("e4").bitboard();
+        let neighbours = e4.north()
+            | e4.south()
+            | e4.east()
+            | e4.west()
+            | e4.north_east()
+            | e4.north_west()
+            | e4.south_east()
+            | e4.south_west();
+        assert_eq!(crate::masks::KING_MASK[square("e4")], neighbours);
+
+        assert_eq!(
+            "8  . . . . . . . x\n\
+             7  . . . . . . . .\n\
+             6  . . . . . . . .\n\
+             5  . . . . . . . .\n\
+             4  . . . . x . . .\n\
+             3  . . . . . . . .\n\
+             2  . . . . . . . .\n\
+             1  x . . . . . . .\n   \
+             a b c d e f g h",
+            bitboard.to_string()
+        );
+    }
+}
@@ -1,13 +1,138 @@
-use std::{fmt::format, u8};
+use std::fmt::format;
 
 use crate::{
-    mve::{algebraic_to_move, Move},
-    piece::{BISHOP, KING, KNIGHT, NONE, PAWN, QUEEN, ROOK},
+    bitboard::Bitboard,
+    mve::{algebraic_to_move, Move, KING_CASTLE, QUEEN_CASTLE},
+    piece::{Piece, PieceKind},
 };
 
-pub type Bitboard = u64;
-pub type Square = u8;
-pub type Color = bool;
+//squares are numbered from h1 = 0 to a8 = 63, so the a-file is the highest bit of every rank
+//this differs from the common a1 = 0 numbering, always use file and rank to convert
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Square(u8);
+
+impl Square {
+    #[inline]
+    pub const fn new(index: u8) -> Square {
+        debug_assert!(index < 64);
+        Square(index)
+    }
+
+    //file 0 is the a-file and rank 0 is the first rank
+    #[inline]
+    pub const fn from_file_rank(file: u8, rank: u8) -> Square {
+        Square::new(rank * 8 + 7 - file)
+    }
+
+    //parses squares like "e4"
+    pub fn from_algebraic(algebraic: &str) -> Option<Square> {
+        match algebraic.as_bytes() {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Some(Square::from_file_rank(file - b'a', rank - b'1'))
+            }
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    #[inline]
+    pub const fn file(self) -> u8 {
+        7 - self.0 % 8
+    }
+
+    #[inline]
+    pub const fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    #[inline]
+    pub const fn bitboard(self) -> Bitboard {
+        Bitboard(0b1u64 << self.0)
+    }
+
+    //the square one rank higher, the square must not be on the eighth rank
+    #[inline]
+    pub const fn up(self) -> Square {
+        Square::new(self.0 + 8)
+    }
+
+    //the square one rank lower, the square must not be on the first rank
+    #[inline]
+    pub const fn down(self) -> Square {
+        Square::new(self.0 - 8)
+    }
+}
+
+//the corners, where the castling rooks start in standard chess
+pub const A1: Square = Square::from_file_rank(0, 0);
+pub const H1: Square = Square::from_file_rank(7, 0);
+pub const A8: Square = Square::from_file_rank(0, 7);
+pub const H8: Square = Square::from_file_rank(7, 7);
+
+impl std::fmt::Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            (b'a' + self.file()) as char,
+            (b'1' + self.rank()) as char
+        )
+    }
+}
+
+impl std::fmt::Debug for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+//tables with one entry per square can be indexed by squares without bounds checks,
+//the mask is a no-op for valid squares but lets the compiler prove the index is in bounds
+impl<T> std::ops::Index<Square> for [T; 64] {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, square: Square) -> &T {
+        &self[square.index() & 63]
+    }
+}
+
+impl<T> std::ops::IndexMut<Square> for [T; 64] {
+    #[inline]
+    fn index_mut(&mut self, square: Square) -> &mut T {
+        &mut self[square.index() & 63]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    //index into tables with one entry per color
+    #[inline]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl std::ops::Not for Color {
+    type Output = Color;
+
+    #[inline]
+    fn not(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
 
 #[derive(Clone, PartialEq)]
 pub enum Result {
@@ -16,10 +141,10 @@ pub enum Result {
     None,
 }
 
-pub const WHITE: Color = true;
-pub const BLACK: Color = false;
+pub const WHITE: Color = Color::White;
+pub const BLACK: Color = Color::Black;
 
-pub const EMPTY: Bitboard = 0;
+pub const EMPTY: Bitboard = Bitboard(0);
 
 #[derive(Clone)]
 pub struct Position {
@@ -27,60 +152,80 @@ pub struct Position {
     pub b_piece_board: [Bitboard; 6],
     pub w_board: Bitboard,
     pub b_board: Bitboard,
-    pub pieces: [u8; 64],
+    pub pieces: [Option<PieceKind>; 64],
     pub blocker_board: Bitboard,
     pub checked_squares: Bitboard,
     pub orthogonal_pin: Bitboard,
     pub diagonal_pin: Bitboard,
     pub en_passant_target_square: Bitboard,
     pub castling_rights: [bool; 4],
+    //the start squares of the castling rooks, indexed like castling_rights, these are only
+    //different from the corners in chess960
+    pub castling_rooks: [Square; 4],
+    //the destinations of the castling king and rook, the squares which must be empty and the
+    //squares the king crosses for each castling right, computed from the FEN as they never change
+    pub castling_king_to: [Square; 4],
+    pub castling_rook_to: [Square; 4],
+    pub castling_empty: [Bitboard; 4],
+    pub castling_king_path: [Bitboard; 4],
     pub color_to_move: Color,
     pub half_move_clock: u32,
     pub full_moves: u32,
     pub result: Result,
+    //zobrist hash, updated by make_move
+    pub hash: u64,
+    //the material and heatmap scores of all pieces from white's perspective, updated by make_move
+    pub midgame_score: i32,
+    pub end_game_score: i32,
 }
 
 #[inline]
 pub fn square_to_bitboard(square: Square) -> Bitboard {
-    0b1u64 << square
+    square.bitboard()
 }
 
 #[inline]
 pub fn bitboard_to_square(bit_square: Bitboard) -> Square {
-    bit_square.trailing_zeros() as Square
+    bit_square.lsb()
 }
 
 pub const FILE: [Bitboard; 8] = [
-    0b1000000010000000100000001000000010000000100000001000000010000000u64,
-    0b0100000001000000010000000100000001000000010000000100000001000000u64,
-    0b0010000000100000001000000010000000100000001000000010000000100000u64,
-    0b0001000000010000000100000001000000010000000100000001000000010000u64,
-    0b0000100000001000000010000000100000001000000010000000100000001000u64,
-    0b0000010000000100000001000000010000000100000001000000010000000100u64,
-    0b0000001000000010000000100000001000000010000000100000001000000010u64,
-    0b0000000100000001000000010000000100000001000000010000000100000001u64,
+    Bitboard(0b1000000010000000100000001000000010000000100000001000000010000000u64),
+    Bitboard(0b0100000001000000010000000100000001000000010000000100000001000000u64),
+    Bitboard(0b0010000000100000001000000010000000100000001000000010000000100000u64),
+    Bitboard(0b0001000000010000000100000001000000010000000100000001000000010000u64),
+    Bitboard(0b0000100000001000000010000000100000001000000010000000100000001000u64),
+    Bitboard(0b0000010000000100000001000000010000000100000001000000010000000100u64),
+    Bitboard(0b0000001000000010000000100000001000000010000000100000001000000010u64),
+    Bitboard(0b0000000100000001000000010000000100000001000000010000000100000001u64),
 ];
 
 pub const RANK: [Bitboard; 8] = [
-    0b11111111u64,
-    0b11111111u64 << 8,
-    0b11111111u64 << 16,
-    0b11111111u64 << 24,
-    0b11111111u64 << 32,
-    0b11111111u64 << 40,
-    0b11111111u64 << 48,
-    0b11111111u64 << 56,
+    Bitboard(0b11111111u64),
+    Bitboard(0b11111111u64 << 8),
+    Bitboard(0b11111111u64 << 16),
+    Bitboard(0b11111111u64 << 24),
+    Bitboard(0b11111111u64 << 32),
+    Bitboard(0b11111111u64 << 40),
+    Bitboard(0b11111111u64 << 48),
+    Bitboard(0b11111111u64 << 56),
 ];
 
 pub const NOT_ON_H_FILE: Bitboard =
-    0b1111111011111110111111101111111011111110111111101111111011111110u64;
+    Bitboard(0b1111111011111110111111101111111011111110111111101111111011111110u64);
 pub const NOT_ON_A_FILE: Bitboard =
-    0b0111111101111111011111110111111101111111011111110111111101111111u64;
+    Bitboard(0b0111111101111111011111110111111101111111011111110111111101111111u64);
 
 pub const NOT_ON_GH_FILE: Bitboard =
-    0b1111110011111100111111001111110011111100111111001111110011111100u64;
+    Bitboard(0b1111110011111100111111001111110011111100111111001111110011111100u64);
 pub const NOT_ON_AB_FILE: Bitboard =
-    0b0011111100111111001111110011111100111111001111110011111100111111u64;
+    Bitboard(0b0011111100111111001111110011111100111111001111110011111100111111u64);
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Position {
     pub fn new() -> Position {
@@ -89,17 +234,25 @@ impl Position {
             b_piece_board: [EMPTY; 6],
             w_board: EMPTY,
             b_board: EMPTY,
-            pieces: [NONE; 64],
+            pieces: [None; 64],
             blocker_board: EMPTY,
             checked_squares: EMPTY,
             orthogonal_pin: EMPTY,
             diagonal_pin: EMPTY,
             en_passant_target_square: EMPTY,
             castling_rights: [false; 4],
+            castling_rooks: [H1, A1, H8, A8],
+            castling_king_to: [H1, A1, H8, A8],
+            castling_rook_to: [H1, A1, H8, A8],
+            castling_empty: [EMPTY; 4],
+            castling_king_path: [EMPTY; 4],
             color_to_move: WHITE,
             half_move_clock: 0,
             full_moves: 0,
             result: Result::None,
+            hash: 0,
+            midgame_score: 0,
+            end_game_score: 0,
         }
     }
 
@@ -112,7 +265,7 @@ impl Position {
         let mut moves = move_string.split_whitespace();
 
         for algebraic_mve in moves {
-            let mve = algebraic_to_move(&position, algebraic_mve);
+            let mve = algebraic_to_move(&position, algebraic_mve).unwrap();
             position.make_move(mve);
         }
 
@@ -124,7 +277,7 @@ impl Position {
         let mut moves = move_string.split_whitespace();
 
         for algebraic_mve in moves {
-            let mve = algebraic_to_move(&position, algebraic_mve);
+            let mve = algebraic_to_move(&position, algebraic_mve).unwrap();
             position.make_move(mve);
         }
         position
@@ -136,9 +289,114 @@ impl Position {
         position
     }
 
+    //checks the FEN before parsing it, for FEN strings from files and other programs
+    //the move counters are optional like in EPD, one king of each color is required, pieces beyond
+    //the starting material need missing pawns to have promoted and castling rights must match the
+    //kings and rooks on the board, positions the side to move could not have reached are rejected
+    //as the move generator would capture the enemy king or move pawns off the board in them
+    pub fn try_from_fen(fen: &str) -> Option<Position> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if !(4..=6).contains(&fields.len()) {
+            return None;
+        }
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        let valid_rank = |rank: &&str| {
+            let mut squares = 0;
+            for letter in rank.chars() {
+                match letter {
+                    '1'..='8' => squares += letter as u32 - '0' as u32,
+                    _ if Piece::from_char(letter).is_some() => squares += 1,
+                    _ => return false,
+                }
+            }
+            squares == 8
+        };
+        if ranks.len() != 8 || !ranks.iter().all(valid_rank) {
+            return None;
+        }
+        if fields[0].matches('K').count() != 1 || fields[0].matches('k').count() != 1 {
+            return None;
+        }
+        //every piece beyond the starting material must be a promoted pawn of the same color
+        let possible_material = |pieces: [char; 5]| {
+            let [pawns, knights, bishops, rooks, queens] =
+                pieces.map(|piece| fields[0].matches(piece).count());
+            let promoted = knights.saturating_sub(2)
+                + bishops.saturating_sub(2)
+                + rooks.saturating_sub(2)
+                + queens.saturating_sub(1);
+            pawns + promoted <= 8
+        };
+        if !possible_material(['P', 'N', 'B', 'R', 'Q'])
+            || !possible_material(['p', 'n', 'b', 'r', 'q'])
+        {
+            return None;
+        }
+        let valid_castling = fields[2] == "-"
+            || fields[2]
+                .chars()
+                .all(|c| "KQkqABCDEFGHabcdefgh".contains(c));
+        //the en passant square is behind a pawn which was just pushed by the side not to move
+        let en_passant_rank = if fields[1] == "w" { "6" } else { "3" };
+        let valid_en_passant = fields[3] == "-"
+            || Square::from_algebraic(fields[3]).is_some() && fields[3].ends_with(en_passant_rank);
+        let valid_counters = fields[4..].iter().all(|field| field.parse::<u32>().is_ok());
+        if !matches!(fields[1], "w" | "b")
+            || !valid_castling
+            || !valid_en_passant
+            || !valid_counters
+        {
+            return None;
+        }
+        let half_move_clock = fields.get(4).unwrap_or(&"0");
+        let full_moves = fields.get(5).unwrap_or(&"1");
+        let position = Position::from_fen(&format!(
+            "{} {} {} {} {} {}",
+            fields[0], fields[1], fields[2], fields[3], half_move_clock, full_moves
+        ));
+
+        //every castling letter must name a different rook on the back rank of its king,
+        //letters without such a rook are not parsed as a castling right
+        let castling_letters = fields[2].trim_start_matches('-').len();
+        let castling_rights = position
+            .castling_rights
+            .iter()
+            .filter(|right| **right)
+            .count();
+        if castling_rights != castling_letters {
+            return None;
+        }
+        //pawns can not stand on the first or last rank and the side not to move can not be in check
+        let pawns =
+            position.w_piece_board[PieceKind::Pawn] | position.b_piece_board[PieceKind::Pawn];
+        let opponent_king = position.king_square(!position.color_to_move);
+        if pawns & (RANK[0] | RANK[7]) != EMPTY
+            || position.attackers_to(opponent_king, position.color_to_move) != EMPTY
+        {
+            return None;
+        }
+        //the pawn which was just pushed must be in front of the en passant square, which is empty
+        //like the square the pawn came from
+        if position.en_passant_target_square != EMPTY {
+            let en_passant = position.en_passant_target_square.lsb();
+            let (pushed, origin) = if position.color_to_move == WHITE {
+                (en_passant.down(), en_passant.up())
+            } else {
+                (en_passant.up(), en_passant.down())
+            };
+            let enemy_pawns = position.piece_boards(!position.color_to_move)[PieceKind::Pawn];
+            if !enemy_pawns.contains(pushed)
+                || position.blocker_board & (en_passant.bitboard() | origin.bitboard()) != EMPTY
+            {
+                return None;
+            }
+        }
+        Some(position)
+    }
+
     //this function is only safe for correct FEN strings
     pub fn parse_fen(&mut self, fen: &str) {
-        let mut ptr = 1 << 63;
+        let mut ptr = Square::new(63).bitboard();
         let mut fen_iter = fen.as_bytes().iter();
 
         //parsing the board
@@ -148,76 +406,32 @@ impl Position {
             if byte == &b' ' {
                 break;
             } else if (&b'1'..=&b'9').contains(&byte) {
-                ptr >>= byte - b'0';
+                ptr >>= (byte - b'0') as u32;
                 continue;
             }
-            match byte {
-                b'r' => {
-                    self.b_piece_board[ROOK] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = ROOK as u8;
-                }
-                b'b' => {
-                    self.b_piece_board[BISHOP] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = BISHOP as u8;
-                }
-                b'n' => {
-                    self.b_piece_board[KNIGHT] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = KNIGHT as u8;
-                }
-                b'q' => {
-                    self.b_piece_board[QUEEN] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = QUEEN as u8;
-                }
-                b'k' => {
-                    self.b_piece_board[KING] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = KING as u8;
-                }
-                b'p' => {
-                    self.b_piece_board[PAWN] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = PAWN as u8;
-                }
-                b'R' => {
-                    self.w_piece_board[ROOK] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = ROOK as u8;
-                }
-                b'B' => {
-                    self.w_piece_board[BISHOP] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = BISHOP as u8;
-                }
-                b'N' => {
-                    self.w_piece_board[KNIGHT] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = KNIGHT as u8;
-                }
-                b'Q' => {
-                    self.w_piece_board[QUEEN] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = QUEEN as u8;
-                }
-                b'K' => {
-                    self.w_piece_board[KING] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = KING as u8;
-                }
-                b'P' => {
-                    self.w_piece_board[PAWN] |= ptr;
-                    self.pieces[bitboard_to_square(ptr) as usize] = PAWN as u8;
-                }
-                _ => {
-                    continue;
-                }
+            let Some(piece) = Piece::from_char(*byte as char) else {
+                continue;
+            };
+            if piece.color() == WHITE {
+                self.w_piece_board[piece.kind().index()] |= ptr;
+            } else {
+                self.b_piece_board[piece.kind().index()] |= ptr;
             }
+            self.pieces[bitboard_to_square(ptr)] = Some(piece.kind());
             ptr >>= 1;
         }
-        self.w_board = self.w_piece_board[ROOK]
-            | self.w_piece_board[BISHOP]
-            | self.w_piece_board[KNIGHT]
-            | self.w_piece_board[QUEEN]
-            | self.w_piece_board[KING]
-            | self.w_piece_board[PAWN];
-        self.b_board = self.b_piece_board[ROOK]
-            | self.b_piece_board[BISHOP]
-            | self.b_piece_board[KNIGHT]
-            | self.b_piece_board[QUEEN]
-            | self.b_piece_board[KING]
-            | self.b_piece_board[PAWN];
+        self.w_board = self.w_piece_board[PieceKind::Rook]
+            | self.w_piece_board[PieceKind::Bishop]
+            | self.w_piece_board[PieceKind::Knight]
+            | self.w_piece_board[PieceKind::Queen]
+            | self.w_piece_board[PieceKind::King]
+            | self.w_piece_board[PieceKind::Pawn];
+        self.b_board = self.b_piece_board[PieceKind::Rook]
+            | self.b_piece_board[PieceKind::Bishop]
+            | self.b_piece_board[PieceKind::Knight]
+            | self.b_piece_board[PieceKind::Queen]
+            | self.b_piece_board[PieceKind::King]
+            | self.b_piece_board[PieceKind::Pawn];
         self.blocker_board = self.w_board | self.b_board;
 
         //parse color to move
@@ -234,15 +448,13 @@ impl Position {
 
             if byte == &b'-' {
                 continue;
+            } else if byte == &b' ' {
+                break;
             }
-            match byte {
-                b'K' => self.castling_rights[0] = true,
-                b'Q' => self.castling_rights[1] = true,
-                b'k' => self.castling_rights[2] = true,
-                b'q' => self.castling_rights[3] = true,
-                _ => {
-                    break;
-                }
+            if let Some((right, rook)) = self.castling_rook(*byte) {
+                self.castling_rights[right] = true;
+                self.castling_rooks[right] = rook;
+                self.set_castling_squares(right);
             }
         }
         //parse en passant target square
@@ -255,7 +467,7 @@ impl Position {
             let row = fen_iter.next().unwrap() - b'1';
 
             let square = 8 * row + 7 - col;
-            self.en_passant_target_square = 0b1u64 << square;
+            self.en_passant_target_square = Square::new(square).bitboard();
             fen_iter.next();
         }
         //parse halfmove and fullmove
@@ -281,98 +493,100 @@ impl Position {
                 break;
             }
         }
+        self.hash = self.compute_hash();
+        (self.midgame_score, self.end_game_score) = self.compute_scores();
+    }
+
+    //the castling right and rook of a castling letter, K and Q stand for the outermost rook on that
+    //side of the king like in X-FEN and the files A to H for the rook on that file like in
+    //Shredder-FEN, so chess960 positions can be read in both formats
+    fn castling_rook(&self, letter: u8) -> Option<(usize, Square)> {
+        let (first_right, rank, pieces) = if letter.is_ascii_uppercase() {
+            (0, 0, &self.w_piece_board)
+        } else {
+            (2, 7, &self.b_piece_board)
+        };
+        let king = pieces[PieceKind::King] & RANK[rank];
+        if king == EMPTY {
+            return None;
+        }
+        let king = king.lsb();
+        let mut rooks = (pieces[PieceKind::Rook] & RANK[rank]).into_iter();
+        let rook = match letter.to_ascii_lowercase() {
+            b'k' => rooks
+                .filter(|rook| rook.file() > king.file())
+                .max_by_key(|rook| rook.file()),
+            b'q' => rooks
+                .filter(|rook| rook.file() < king.file())
+                .min_by_key(|rook| rook.file()),
+            file @ b'a'..=b'h' => rooks.find(|rook| rook.file() == file - b'a'),
+            _ => None,
+        }?;
+        let queen_side = rook.file() < king.file();
+        Some((first_right + queen_side as usize, rook))
+    }
+
+    //the castling move of a right only depends on the start squares of the king and the rook, so
+    //the squares the move generator needs are only computed once
+    fn set_castling_squares(&mut self, right: usize) {
+        let pieces = if right < 2 {
+            &self.w_piece_board
+        } else {
+            &self.b_piece_board
+        };
+        let flag = [KING_CASTLE, QUEEN_CASTLE][right % 2];
+        let mve = Move::new(
+            pieces[PieceKind::King].lsb(),
+            self.castling_rooks[right],
+            flag,
+        );
+        self.castling_king_to[right] = mve.castling_king_to();
+        self.castling_rook_to[right] = mve.castling_rook_to();
+        self.castling_empty[right] = mve.castling_empty_squares();
+        self.castling_king_path[right] = mve.castling_king_path();
+    }
+
+    //the FEN letter of a castling right, K, Q, k or q unless another rook is further out on that
+    //side of the king, which needs the file of the castling rook
+    fn castling_letter(&self, right: usize) -> char {
+        let letter = b"KQkq"[right];
+        let rook = self.castling_rooks[right];
+        if self.castling_rook(letter) == Some((right, rook)) {
+            letter as char
+        } else if right < 2 {
+            (b'A' + rook.file()) as char
+        } else {
+            (b'a' + rook.file()) as char
+        }
     }
 
     pub fn fen(&self) -> String {
         let mut fen = String::new();
         let mut empty_count = 0;
         //board
-        for mut square in 0..64 {
-            square = 63 - square;
+        for index in 0..64 {
+            let square = Square::new(63 - index);
 
-            match self.pieces[square] as usize {
-                PAWN => {
-                    if empty_count != 0 {
-                        fen += &format!("{}", empty_count);
-                        empty_count = 0;
-                    }
-                    let bit_square = square_to_bitboard(square as u8);
-                    if bit_square & self.w_board != 0 {
-                        fen += "P";
-                    } else {
-                        fen += "p";
-                    }
-                }
-                KNIGHT => {
+            match self.piece_on(square) {
+                Some(piece) => {
                     if empty_count != 0 {
                         fen += &format!("{}", empty_count);
                         empty_count = 0;
                     }
-                    let bit_square = square_to_bitboard(square as u8);
-                    if bit_square & self.w_board != 0 {
-                        fen += "N";
-                    } else {
-                        fen += "n";
-                    }
+                    fen.push(piece.to_char());
                 }
-                BISHOP => {
-                    if empty_count != 0 {
-                        fen += &format!("{}", empty_count);
-                        empty_count = 0;
-                    }
-                    let bit_square = square_to_bitboard(square as u8);
-                    if bit_square & self.w_board != 0 {
-                        fen += "B";
-                    } else {
-                        fen += "b";
-                    }
-                }
-                ROOK => {
-                    if empty_count != 0 {
-                        fen += &format!("{}", empty_count);
-                        empty_count = 0;
-                    }
-                    let bit_square = square_to_bitboard(square as u8);
-                    if bit_square & self.w_board != 0 {
-                        fen += "R";
-                    } else {
-                        fen += "r";
-                    }
-                }
-                KING => {
-                    if empty_count != 0 {
-                        fen += &format!("{}", empty_count);
-                        empty_count = 0;
-                    }
-                    let bit_square = square_to_bitboard(square as u8);
-                    if bit_square & self.w_board != 0 {
-                        fen += "K";
-                    } else {
-                        fen += "k";
-                    }
-                }
-                QUEEN => {
-                    if empty_count != 0 {
-                        fen += &format!("{}", empty_count);
-                        empty_count = 0;
-                    }
-                    let bit_square = square_to_bitboard(square as u8);
-                    if bit_square & self.w_board != 0 {
-                        fen += "Q";
-                    } else {
-                        fen += "q";
-                    }
-                }
-                _ => {
+                None => {
                     empty_count += 1;
                 }
             }
-            if square % 8 == 0 && square != 0 {
+            if square.file() == 7 {
                 if empty_count != 0 {
                     fen += &format!("{}", empty_count);
                     empty_count = 0;
                 }
-                fen += "/";
+                if square.index() != 0 {
+                    fen += "/";
+                }
             }
         }
         fen += " ";
@@ -383,17 +597,13 @@ impl Position {
         }
         fen += " ";
         //castling rights
-        if self.castling_rights[0] {
-            fen += "K";
-        }
-        if self.castling_rights[1] {
-            fen += "Q";
-        }
-        if self.castling_rights[2] {
-            fen += "k";
+        for right in 0..4 {
+            if self.castling_rights[right] {
+                fen.push(self.castling_letter(right));
+            }
         }
-        if self.castling_rights[3] {
-            fen += "q";
+        if self.castling_rights == [false; 4] {
+            fen += "-";
         }
         fen += " ";
         //en passant target square
@@ -401,9 +611,7 @@ impl Position {
             fen += "- "
         } else {
             let square = bitboard_to_square(self.en_passant_target_square);
-            let row = ((square / 8) as u8 + b'1') as char;
-            let col = (7 - (square % 8) as u8 + b'a') as char;
-            fen += &format!("{}{} ", col, row);
+            fen += &format!("{} ", square);
         }
 
         //Halfmove clock
@@ -421,59 +629,14 @@ impl Position {
                 rank -= 1;
             }
             square = 63 - square;
-            let ptr = 0b1u64 << square;
-            let mut no_piece_count = 0;
-            for piece in 0..6 {
-                if ptr & self.w_piece_board[piece] != 0 {
-                    match piece {
-                        QUEEN => {
-                            print!("♕");
-                        }
-                        ROOK => {
-                            print!("♖");
-                        }
-                        BISHOP => {
-                            print!("♗");
-                        }
-                        KNIGHT => {
-                            print!("♘");
-                        }
-                        PAWN => {
-                            print!("♙");
-                        }
-                        KING => {
-                            print!("♔");
-                        }
-                        _ => {}
-                    };
-                } else if ptr & self.b_piece_board[piece] != 0 {
-                    match piece {
-                        QUEEN => {
-                            print!("♛");
-                        }
-                        ROOK => {
-                            print!("♜");
-                        }
-                        BISHOP => {
-                            print!("♝");
-                        }
-                        KNIGHT => {
-                            print!("♞");
-                        }
-                        PAWN => {
-                            print!("♟︎");
-                        }
-                        KING => {
-                            print!("♚");
-                        }
-                        _ => {}
-                    };
-                } else {
-                    no_piece_count += 1;
+            let ptr = Square::new(square).bitboard();
+            //the glyphs are ordered like the piece kinds
+            match self.piece_on(Square::new(square)) {
+                Some(piece) if piece.color() == WHITE => {
+                    print!("{}", ["♘", "♗", "♖", "♕", "♙", "♔"][piece.kind()])
                 }
-            }
-            if no_piece_count == 6 {
-                print!("_")
+                Some(piece) => print!("{}", ["♞", "♝", "♜", "♛", "♟︎", "♚"][piece.kind()]),
+                None => print!("_"),
             }
         }
         println!("\n  ABCDEFGH");
@@ -486,17 +649,10 @@ impl Position {
             println!("En passant target square: None")
         }
         print!("Castling rights: ");
-        if self.castling_rights[0] {
-            print!("K")
-        }
-        if self.castling_rights[1] {
-            print!("Q")
-        }
-        if self.castling_rights[2] {
-            print!("k")
-        }
-        if self.castling_rights[3] {
-            print!("q")
+        for right in 0..4 {
+            if self.castling_rights[right] {
+                print!("{}", self.castling_letter(right));
+            }
         }
         println!();
         println!(
@@ -510,20 +666,3 @@ impl Position {
         }
     }
 }
-
-pub fn print_bitboard(board: Bitboard) {
-    for mut square in 0..64 {
-        if square % 8 == 0 {
-            println!();
-        }
-        square = 63 - square;
-
-        let ptr = 0b1u64 << square;
-        if board & ptr == ptr {
-            print!("1")
-        } else {
-            print!(".")
-        }
-    }
-    println!();
-}
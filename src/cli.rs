@@ -0,0 +1,337 @@
+use std::{process::ExitCode, time::Duration};
+
+use crate::{
+    board::{Position, WHITE},
+    eval::evaluate,
+    options::{EngineConfig, HASH, THREADS},
+    perft::{bench, DEFAULT_BENCH_DEPTH},
+    search::{search_best_move, search_fixed_depth},
+    searchbench::{run_search_bench, DEFAULT_SEARCH_BENCH_DEPTH},
+    uci::uci,
+    ucigo::go_perft,
+};
+
+pub const USAGE: &str = "usage: chessengine [command] [options]
+
+commands:
+  uci                                          interactive uci mode, the default
+  perft [--fen <fen>] --depth <n> [--hash <mb>] [--threads <n>]
+                                               leaf node count after every legal move
+  bench [--fen <fen>] [--depth <n>] [--hash <mb>] [--threads <n>]
+                                               perft speed benchmark like the uci bench
+  searchbench [--depth <n>]                    fixed depth search benchmark
+  search [--fen <fen>] [--movetime <ms> | --depth <n>]
+                                               best move of the position
+  eval [--fen <fen>]                           static evaluation in centipawns for white
+  help                                         this message
+
+the position is the start position if no fen is given";
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const DEFAULT_MOVETIME_MS: u64 = 1000;
+
+//the wrong usage of a command, like an unknown option or a missing value
+const EXIT_USAGE: u8 = 2;
+
+pub enum SearchLimit {
+    MoveTime(Duration),
+    Depth(u8),
+}
+
+pub enum Command {
+    Uci,
+    Perft {
+        pos: Position,
+        depth: u8,
+        config: EngineConfig,
+    },
+    Bench {
+        pos: Position,
+        depth: u8,
+        config: EngineConfig,
+    },
+    SearchBench {
+        depth: u8,
+    },
+    Search {
+        pos: Position,
+        limit: SearchLimit,
+    },
+    Eval {
+        pos: Position,
+    },
+    Help,
+}
+
+//the "--name value" pairs after the command, only the given names are allowed
+fn parse_options<'a>(
+    args: &'a [String],
+    allowed: &[&str],
+) -> Result<Vec<(&'a str, &'a str)>, String> {
+    let mut options = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let name = arg
+            .strip_prefix("--")
+            .filter(|name| allowed.contains(name))
+            .ok_or_else(|| format!("unexpected argument {}", arg))?;
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for --{}", name))?;
+        options.push((name, value.as_str()));
+    }
+    Ok(options)
+}
+
+fn option<'a>(options: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    options
+        .iter()
+        .rev()
+        .find(|(option, _)| *option == name)
+        .map(|(_, value)| *value)
+}
+
+fn number<T: std::str::FromStr + PartialOrd + Default>(
+    options: &[(&str, &str)],
+    name: &str,
+) -> Result<Option<T>, String> {
+    let Some(value) = option(options, name) else {
+        return Ok(None);
+    };
+    match value.parse() {
+        Ok(number) if number > T::default() => Ok(Some(number)),
+        _ => Err(format!(
+            "--{} must be a positive number, found {}",
+            name, value
+        )),
+    }
+}
+
+//the hash and threads options have the same bounds as the uci options
+fn engine_config(options: &[(&str, &str)]) -> Result<EngineConfig, String> {
+    let mut config = EngineConfig::new();
+    for (flag, name) in [("hash", HASH), ("threads", THREADS)] {
+        if let Some(value) = option(options, flag) {
+            config
+                .set_option(&format!("name {} value {}", name, value))
+                .map_err(|error| format!("--{}: {}", flag, error))?;
+        }
+    }
+    Ok(config)
+}
+
+fn position(options: &[(&str, &str)]) -> Result<Position, String> {
+    let fen = option(options, "fen").unwrap_or(START_FEN);
+    Position::try_from_fen(fen).ok_or_else(|| format!("invalid fen {}", fen))
+}
+
+//parses the command line arguments without the program name
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some((command, args)) = args.split_first() else {
+        return Ok(Command::Uci);
+    };
+    match command.as_str() {
+        "uci" => {
+            parse_options(args, &[])?;
+            Ok(Command::Uci)
+        }
+        "perft" => {
+            let options = parse_options(args, &["fen", "depth", "hash", "threads"])?;
+            let depth = number(&options, "depth")?.ok_or("missing --depth")?;
+            Ok(Command::Perft {
+                pos: position(&options)?,
+                depth,
+                config: engine_config(&options)?,
+            })
+        }
+        "bench" => {
+            let options = parse_options(args, &["fen", "depth", "hash", "threads"])?;
+            let depth = number(&options, "depth")?.unwrap_or(DEFAULT_BENCH_DEPTH);
+            Ok(Command::Bench {
+                pos: position(&options)?,
+                depth,
+                config: engine_config(&options)?,
+            })
+        }
+        "searchbench" => {
+            let options = parse_options(args, &["depth"])?;
+            Ok(Command::SearchBench {
+                depth: number(&options, "depth")?.unwrap_or(DEFAULT_SEARCH_BENCH_DEPTH),
+            })
+        }
+        "search" => {
+            let options = parse_options(args, &["fen", "movetime", "depth"])?;
+            let limit = match (number(&options, "movetime")?, number(&options, "depth")?) {
+                (Some(_), Some(_)) => return Err("use either --movetime or --depth".to_string()),
+                (None, Some(depth)) => SearchLimit::Depth(depth),
+                (movetime, None) => SearchLimit::MoveTime(Duration::from_millis(
+                    movetime.unwrap_or(DEFAULT_MOVETIME_MS),
+                )),
+            };
+            Ok(Command::Search {
+                pos: position(&options)?,
+                limit,
+            })
+        }
+        "eval" => {
+            let options = parse_options(args, &["fen"])?;
+            Ok(Command::Eval {
+                pos: position(&options)?,
+            })
+        }
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        _ => Err(format!("unknown command {}", command)),
+    }
+}
+
+//runs the command given on the command line, the output of the commands is meant to be read by
+//scripts, errors are written to stderr
+pub fn run(args: &[String]) -> ExitCode {
+    let command = match parse_args(args) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("{}", USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    match command {
+        Command::Uci => uci(),
+        Command::Perft { pos, depth, config } => {
+            if let Err(error) = go_perft(&pos, depth, &config) {
+                eprintln!("{}", error);
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::Bench {
+            mut pos,
+            depth,
+            config,
+        } => {
+            if let Err(error) = bench(&mut pos, depth, config.hash_mb, config.threads) {
+                eprintln!("{}", error);
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::SearchBench { depth } => {
+            run_search_bench(depth);
+        }
+        Command::Search { mut pos, limit } => {
+            if pos.legal_moves().is_empty() {
+                println!("bestmove (none)");
+                return ExitCode::SUCCESS;
+            }
+            let best_move = match limit {
+                SearchLimit::MoveTime(movetime) => search_best_move(&mut pos, movetime),
+                SearchLimit::Depth(depth) => search_fixed_depth(&mut pos, depth).0,
+            };
+            println!("bestmove {}", best_move);
+        }
+        Command::Eval { pos } => {
+            //evaluate is from the perspective of the side to move
+            let eval = match pos.color_to_move {
+                WHITE => evaluate(&pos),
+                _ => -evaluate(&pos),
+            };
+            println!("{}", eval);
+        }
+        Command::Help => println!("{}", USAGE),
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Command, String> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert!(matches!(parse(""), Ok(Command::Uci)));
+        assert!(matches!(
+            parse("perft --depth 6 --threads 4"),
+            Ok(Command::Perft { depth: 6, config, .. }) if config.threads == 4 && config.hash_mb == 16
+        ));
+        let args: Vec<String> = ["search", "--fen", "8/8/8/8/8/8/k7/7K b - - 0 1"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let Ok(Command::Search { pos, limit }) = parse_args(&args) else {
+            panic!("search was not parsed");
+        };
+        assert_eq!("8/8/8/8/8/8/k7/7K b - - 0 1", pos.fen());
+        assert!(matches!(limit, SearchLimit::MoveTime(time) if time.as_millis() == 1000));
+        assert!(matches!(
+            parse("searchbench"),
+            Ok(Command::SearchBench { depth: 6 })
+        ));
+        assert!(matches!(
+            parse("bench --depth 5 --hash 64"),
+            Ok(Command::Bench { depth: 5, config, .. }) if config.hash_mb == 64 && config.threads == 1
+        ));
+        assert!(matches!(
+            parse("search --depth 4"),
+            Ok(Command::Search {
+                limit: SearchLimit::Depth(4),
+                ..
+            })
+        ));
+
+        assert!(parse("perft").is_err());
+        assert!(parse("perft --depth 0").is_err());
+        assert!(parse("perft --depth").is_err());
+        assert!(matches!(parse("bench"), Ok(Command::Bench { .. })));
+        assert!(parse("bench --depth 5 --fen x").is_err());
+        assert!(parse("searchbench --fen x").is_err());
+        assert!(parse("perft --depth 6 --hash 65537").is_err());
+        assert!(parse("perft --depth 6 --threads 0").is_err());
+        assert!(parse("perft --depth 6 --threads x").is_err());
+        assert!(parse("eval --fen x").is_err());
+        assert!(parse("search --depth 3 --movetime 100").is_err());
+        assert!(parse("play").is_err());
+    }
+
+    //positions the side to move could not have reached are usage errors instead of searches which
+    //never end
+    #[test]
+    fn unreachable_positions_are_usage_errors() {
+        for fen in [
+            //the side not to move is in check
+            "4k3/8/8/8/8/8/8/4R1K1 w - - 0 1",
+            //pawns on the first and the last rank
+            "4k3/8/8/8/8/8/8/P3K3 w - - 0 1",
+            "3pk3/8/8/8/8/8/8/4K3 b - - 0 1",
+            //en passant squares on the wrong rank
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d5 0 1",
+            "4k3/8/8/8/3pP3/8/8/4K3 w - e3 0 1",
+            //en passant squares without the pushed pawn in front or with a piece on the square
+            //or the square the pawn came from
+            "4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1",
+            "4k3/8/4p3/3Pp3/8/8/8/4K3 w - e6 0 1",
+            "4k3/4n3/8/3Pp3/8/8/8/4K3 w - e6 0 1",
+            "4k3/8/8/8/3pP3/4N3/8/4K3 b - e3 0 1",
+        ] {
+            for command in [
+                &["search", "--depth", "3"][..],
+                &["eval"],
+                &["perft", "--depth", "3"],
+            ] {
+                let args: Vec<String> = [command, &["--fen", fen]]
+                    .concat()
+                    .iter()
+                    .map(|arg| arg.to_string())
+                    .collect();
+                assert_eq!(ExitCode::from(EXIT_USAGE), run(&args), "{:?}", args);
+            }
+        }
+        let args: Vec<String> = ["eval", "--fen", "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(ExitCode::SUCCESS, run(&args));
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::Position,
+    mve::{Move, NULL_MOVE},
+    search::search_best_move,
+};
+
+//a position in extended position description with its operations, like
+//2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";
+//the position has no move counters unless they are given by the hmvc and fmvn operations
+#[derive(Clone)]
+pub struct Epd {
+    pub pos: Position,
+    //the opcodes with their operands, quotes around string operands are removed
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+//splits the operations into opcodes and operands, operations end with a semicolon
+//and string operands in quotes can contain spaces and semicolons
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, String> {
+    let mut operations = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(first) = rest.chars().next() {
+        if first == ';' {
+            if !tokens.is_empty() {
+                let opcode = tokens.remove(0);
+                operations.push((opcode, std::mem::take(&mut tokens)));
+            }
+            rest = &rest[1..];
+        } else if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted
+                .find('"')
+                .ok_or_else(|| format!("unterminated string {}", rest))?;
+            tokens.push(quoted[..end].to_string());
+            rest = &quoted[end + 1..];
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == ';')
+                .unwrap_or(rest.len());
+            tokens.push(rest[..end].to_string());
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    if !tokens.is_empty() {
+        return Err(format!("operation {} does not end with ;", tokens[0]));
+    }
+    Ok(operations)
+}
+
+impl Epd {
+    pub fn parse(line: &str) -> Result<Epd, String> {
+        //the four fields of the position are followed by the operations
+        let mut rest = line.trim();
+        let mut fields = Vec::with_capacity(4);
+        for _ in 0..4 {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            fields.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        let operations = parse_operations(rest)?;
+
+        let operand = |opcode: &str| {
+            operations
+                .iter()
+                .find(|(name, _)| name == opcode)
+                .and_then(|(_, operands)| operands.first())
+                .map(String::as_str)
+        };
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            operand("hmvc").unwrap_or("0"),
+            operand("fmvn").unwrap_or("1")
+        );
+        let pos =
+            Position::try_from_fen(&fen).ok_or_else(|| format!("invalid position {}", fen))?;
+        Ok(Epd { pos, operations })
+    }
+
+    pub fn operands(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    fn operand(&self, opcode: &str) -> Option<&str> {
+        self.operands(opcode)?.first().map(String::as_str)
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operand("id")
+    }
+
+    //the comments c0 to c9
+    pub fn comment(&self, index: u8) -> Option<&str> {
+        self.operand(&format!("c{}", index))
+    }
+
+    //analysis count depth
+    pub fn analysis_depth(&self) -> Option<u32> {
+        self.operand("acd")?.parse().ok()
+    }
+
+    //the number of moves of the side to move until mate
+    pub fn direct_mate(&self) -> Option<u32> {
+        self.operand("dm")?.parse().ok()
+    }
+
+    pub fn best_moves(&self) -> Result<Vec<Move>, String> {
+        self.moves("bm")
+    }
+
+    pub fn avoid_moves(&self) -> Result<Vec<Move>, String> {
+        self.moves("am")
+    }
+
+    //the moves of the predicted variation, each move is played before the next one is parsed
+    pub fn principal_variation(&self) -> Result<Vec<Move>, String> {
+        let mut pos = self.pos.clone();
+        let mut moves = Vec::new();
+        for san in self.operands("pv").unwrap_or_default() {
+            let mve = pos.parse_san(san)?;
+            pos.make_move(mve);
+            moves.push(mve);
+        }
+        Ok(moves)
+    }
+
+    fn moves(&self, opcode: &str) -> Result<Vec<Move>, String> {
+        self.operands(opcode)
+            .unwrap_or_default()
+            .iter()
+            .map(|san| self.pos.parse_san(san))
+            .collect()
+    }
+
+    //a move solves the position if it is one of the best moves and none of the moves to avoid
+    pub fn is_solved_by(&self, mve: Move) -> Result<bool, String> {
+        let best_moves = self.best_moves()?;
+        let avoid_moves = self.avoid_moves()?;
+        if best_moves.is_empty() && avoid_moves.is_empty() {
+            return Err("neither bm nor am is given".to_string());
+        }
+        //a null move means that no move was found, which never solves a position
+        Ok(mve != NULL_MOVE
+            && (best_moves.is_empty() || best_moves.contains(&mve))
+            && !avoid_moves.contains(&mve))
+    }
+}
+
+//searches every position of an EPD file for a fixed time and checks the move against bm and am
+pub fn run_test_suite(path: &str, movetime: Duration) {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => {
+            println!("cannot open {}: {}", path, error);
+            return;
+        }
+    };
+    let start = Instant::now();
+    let mut passed = 0;
+    let mut total = 0;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                println!("cannot read {}: {}", path, error);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let epd = match Epd::parse(&line) {
+            Ok(epd) => epd,
+            Err(error) => {
+                println!("line {}: {}", index + 1, error);
+                continue;
+            }
+        };
+        let mve = search_best_move(&mut epd.pos.clone(), movetime);
+        let solved = match epd.is_solved_by(mve) {
+            Ok(solved) => solved,
+            Err(error) => {
+                println!("line {}: {}", index + 1, error);
+                continue;
+            }
+        };
+        total += 1;
+        if solved {
+            passed += 1;
+        }
+        let found = if mve == NULL_MOVE {
+            "none".to_string()
+        } else {
+            epd.pos.to_san(mve)
+        };
+        let expected: Vec<String> = ["bm", "am"]
+            .iter()
+            .filter_map(|opcode| Some(format!("{} {}", opcode, epd.operands(opcode)?.join(" "))))
+            .collect();
+        println!(
+            "{} {} {} found {} expected {}",
+            if solved { "pass" } else { "fail" },
+            total,
+            epd.id().unwrap_or("-"),
+            found,
+            expected.join(" ")
+        );
+    }
+    println!();
+    println!("Score: {}/{}", passed, total);
+    println!("Time Elapsed: {} ms", start.elapsed().as_millis());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_epd() {
+        let epd = Epd::parse(
+            r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";"#,
+        )
+        .unwrap();
+        assert_eq!(Some("WAC.001"), epd.id());
+        let best_move = epd.best_moves().unwrap()[0];
+        assert_eq!("g3g6", best_move.to_string());
+        assert_eq!(Ok(true), epd.is_solved_by(best_move));
+        assert_eq!(
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1",
+            epd.pos.fen()
+        );
+
+        let epd = Epd::parse(
+            "4k3/8/8/8/8/8/4P3/R3K3 w Q - am O-O-O e3;  acd 12; dm 3; pv e4 Kd7 e5; \
+             c0 \"avoid; castling\"; hmvc 7; fmvn 40;",
+        )
+        .unwrap();
+        assert_eq!(Some("avoid; castling"), epd.comment(0));
+        assert_eq!(None, epd.comment(1));
+        assert_eq!(Some(12), epd.analysis_depth());
+        assert_eq!(Some(3), epd.direct_mate());
+        assert_eq!(2, epd.avoid_moves().unwrap().len());
+        assert_eq!(3, epd.principal_variation().unwrap().len());
+        assert_eq!(Ok(false), epd.is_solved_by(epd.avoid_moves().unwrap()[0]));
+        assert_eq!(Ok(false), epd.is_solved_by(NULL_MOVE));
+        assert_eq!(
+            Ok(true),
+            epd.is_solved_by(epd.principal_variation().unwrap()[0])
+        );
+        assert_eq!("4k3/8/8/8/8/8/4P3/R3K3 w Q - 7 40", epd.pos.fen());
+
+        assert!(Epd::parse("4k3/8/8/8/8/8/8/4K3 w - - bm Kd2").is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/8/4K3 w - - bm Kd9;")
+            .unwrap()
+            .best_moves()
+            .is_err());
+        assert!(Epd::parse("4k3/8/8/8/8/8/8/8 w - - bm Kd2;").is_err());
+    }
+}
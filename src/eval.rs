@@ -1,10 +1,12 @@
 use crate::{
-    board::{bitboard_to_square, Position, Square, EMPTY, WHITE},
-    piece::{BISHOP, KING, KNIGHT, PAWN, QUEEN, ROOK},
+    board::{bitboard_to_square, Color, Position, Square, BLACK, EMPTY, WHITE},
+    piece::PieceKind,
 };
 
 //Values and heatmaps from
 //https://www.chessprogramming.org/Simplified_Evaluation_Function
+//every term has a midgame and an endgame value which are blended by the game phase
+//https://www.chessprogramming.org/Tapered_Eval
 
 const PAWN_VALUE: i32 = 100;
 const KNIGHT_VALUE: i32 = 320;
@@ -12,51 +14,124 @@ const BISHOP_VALUE: i32 = 330;
 const ROOK_VALUE: i32 = 500;
 const QUEEN_VALUE: i32 = 900;
 const KING_VALUE: i32 = 0;
-const EMPTY_VALUE: i32 = 0;
 
-pub const PIECE_VALUES: [i32; 7] = [
+//the midgame values, also used for move ordering
+pub const PIECE_VALUES: [i32; 6] = [
     KNIGHT_VALUE,
     BISHOP_VALUE,
     ROOK_VALUE,
     QUEEN_VALUE,
     PAWN_VALUE,
     KING_VALUE,
-    EMPTY_VALUE,
 ];
 
-pub static mut COUNT: i32 = 0;
+//pawns become more valuable as they can promote, rooks and queens gain open lines and
+//knights lose value without pawns to support them
+pub const END_GAME_PIECE_VALUES: [i32; 6] = [
+    KNIGHT_VALUE - 30,
+    BISHOP_VALUE - 10,
+    ROOK_VALUE + 30,
+    QUEEN_VALUE + 40,
+    PAWN_VALUE + 20,
+    KING_VALUE,
+];
 
-//returns the evaluation of the position from the current players perspective
-pub fn evaluate(pos: &Position) -> i32 {
-    let mut eval = 0;
-
-    let mut w_pieces = pos.w_board;
-    while w_pieces != 0 {
-        let square = (w_pieces & !(w_pieces - 1)).trailing_zeros() as usize;
-        eval += PIECE_VALUES[pos.pieces[square] as usize];
-        eval += PIECE_HEATMAP[pos.pieces[square] as usize][63 - square];
-        w_pieces &= w_pieces - 1; //removing the last bit
+//the phase is the sum of the phase values of the pieces on the board, it is MAX_PHASE with all
+//pieces on the board and 0 with only pawns and kings left
+pub const PHASE_VALUES: [i32; 6] = [1, 1, 2, 4, 0, 0];
+pub const MAX_PHASE: i32 = 24;
+
+//the phase of the position, promotions can increase it above MAX_PHASE
+pub fn game_phase(pos: &Position) -> i32 {
+    let phase: i32 = [
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+    ]
+    .into_iter()
+    .map(|kind| {
+        let count = pos.w_piece_board[kind].popcount() + pos.b_piece_board[kind].popcount();
+        PHASE_VALUES[kind] * count as i32
+    })
+    .sum();
+    phase.min(MAX_PHASE)
+}
+
+//blends the midgame and endgame scores, the midgame score is used with all pieces on the board
+//and the endgame score with only pawns and kings
+#[inline]
+pub fn taper(midgame: i32, end_game: i32, phase: i32) -> i32 {
+    (midgame * phase + end_game * (MAX_PHASE - phase)) / MAX_PHASE
+}
+
+//the material and heatmap score of every piece on every square from white's perspective,
+//indexed by color, piece kind and square
+pub static MIDGAME_PSQT: [[[i32; 64]; 6]; 2] = piece_square_table(&PIECE_VALUES, &PIECE_HEATMAP);
+pub static END_GAME_PSQT: [[[i32; 64]; 6]; 2] =
+    piece_square_table(&END_GAME_PIECE_VALUES, &PIECE_END_GAME_HEATMAP);
+
+//the heatmaps are written from white's perspective and mirrored for black
+const fn piece_square_table(values: &[i32; 6], heatmaps: &[[i32; 64]; 6]) -> [[[i32; 64]; 6]; 2] {
+    let mut table = [[[0; 64]; 6]; 2];
+    let mut kind = 0;
+    while kind < 6 {
+        let mut square = 0;
+        while square < 64 {
+            table[WHITE.index()][kind][square] = values[kind] + heatmaps[kind][63 - square];
+            table[BLACK.index()][kind][square] = -(values[kind] + heatmaps[kind][square]);
+            square += 1;
+        }
+        kind += 1;
     }
-    let mut b_pieces = pos.b_board;
-    while b_pieces != 0 {
-        let square = (b_pieces & !(b_pieces - 1)).trailing_zeros() as usize;
-        eval -= PIECE_VALUES[pos.pieces[square] as usize];
-        eval -= PIECE_HEATMAP[pos.pieces[square] as usize][square];
-        b_pieces &= b_pieces - 1; //removing the last bit
+    table
+}
+
+impl Position {
+    //the midgame and endgame scores calculated from scratch, make_move updates them incrementally
+    pub fn compute_scores(&self) -> (i32, i32) {
+        let mut midgame = 0;
+        let mut end_game = 0;
+        for (color, piece_board) in [(WHITE, &self.w_piece_board), (BLACK, &self.b_piece_board)] {
+            for kind in PieceKind::ALL {
+                for square in piece_board[kind] {
+                    midgame += MIDGAME_PSQT[color.index()][kind][square];
+                    end_game += END_GAME_PSQT[color.index()][kind][square];
+                }
+            }
+        }
+        (midgame, end_game)
     }
-    if pos.blocker_board.count_ones() < 18 {
-        //count position as endgame
-        let w_king_pos = pos.w_piece_board[KING].trailing_zeros() as usize;
-        let b_king_pos = pos.b_piece_board[KING].trailing_zeros() as usize;
-        //reverting king heatmap eval
-        eval -= PIECE_HEATMAP[KING][63 - w_king_pos];
-        eval += PIECE_HEATMAP[KING][b_king_pos];
-        //applying endgame king heatmap instead
-        eval += KING_END_GAME_HEATMAP[63 - w_king_pos];
-        eval -= KING_END_GAME_HEATMAP[b_king_pos];
+
+    #[inline]
+    pub fn add_piece_score(&mut self, color: Color, kind: PieceKind, square: Square) {
+        self.midgame_score += MIDGAME_PSQT[color.index()][kind][square];
+        self.end_game_score += END_GAME_PSQT[color.index()][kind][square];
+    }
+
+    #[inline]
+    pub fn remove_piece_score(&mut self, color: Color, kind: PieceKind, square: Square) {
+        self.midgame_score -= MIDGAME_PSQT[color.index()][kind][square];
+        self.end_game_score -= END_GAME_PSQT[color.index()][kind][square];
+    }
+}
+
+//returns the evaluation of the position from the current players perspective
+pub fn evaluate(pos: &Position) -> i32 {
+    debug_assert_eq!(
+        (pos.midgame_score, pos.end_game_score),
+        pos.compute_scores(),
+        "{}",
+        pos.fen()
+    );
+    let eval = taper(pos.midgame_score, pos.end_game_score, game_phase(pos));
+
+    //changing perspective for negamax
+    if pos.color_to_move == WHITE {
+        eval
+    } else {
+        -eval
     }
-    //converting color_to_move to 1 if WHITE and -1 if BLACK without branching to change perspective for negamax
-    eval * (2 * (pos.color_to_move as i8) - 1) as i32
 }
 
 //heatmaps for all pieces as pieces placed on better squares are worth more
@@ -69,6 +144,16 @@ const PIECE_HEATMAP: [[i32; 64]; 6] = [
     KING_HEATMAP,
 ];
 
+//minor pieces want to be centralized in every phase of the game
+const PIECE_END_GAME_HEATMAP: [[i32; 64]; 6] = [
+    KNIGHT_HEATMAP,
+    BISHOP_HEATMAP,
+    ROOK_END_GAME_HEATMAP,
+    QUEEN_END_GAME_HEATMAP,
+    PAWN_END_GAME_HEATMAP,
+    KING_END_GAME_HEATMAP,
+];
+
 #[rustfmt::skip]
 const PAWN_HEATMAP: [i32; 64] = [
      0,  0,  0,  0,  0,  0,  0,  0,
@@ -152,3 +237,89 @@ const KING_END_GAME_HEATMAP: [i32; 64] = [
     -30,-30,  0,  0,  0,  0,-30,-30,
     -50,-30,-30,-30,-30,-30,-30,-50,
 ];
+
+#[rustfmt::skip]
+const PAWN_END_GAME_HEATMAP: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    80, 80, 80, 80, 80, 80, 80, 80,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    30, 30, 30, 30, 30, 30, 30, 30,
+    15, 15, 15, 15, 15, 15, 15, 15,
+     5,  5,  5,  5,  5,  5,  5,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const ROOK_END_GAME_HEATMAP: [i32; 64] = [
+    0,  0,  0,  0,  0,  0,  0,  0,
+   10, 10, 10, 10, 10, 10, 10, 10,
+    0,  0,  0,  0,  0,  0,  0,  0,
+    0,  0,  0,  0,  0,  0,  0,  0,
+    0,  0,  0,  0,  0,  0,  0,  0,
+    0,  0,  0,  0,  0,  0,  0,  0,
+    0,  0,  0,  0,  0,  0,  0,  0,
+    0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_END_GAME_HEATMAP: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+    -10,  5, 10, 15, 15, 10,  5,-10,
+    -10,  5, 15, 20, 20, 15,  5,-10,
+    -10,  5, 15, 20, 20, 15,  5,-10,
+    -10,  5, 10, 15, 15, 10,  5,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perft::TEST_FENS;
+
+    #[test]
+    fn tapered_eval() {
+        assert_eq!(MAX_PHASE, game_phase(&Position::startpos()));
+        assert_eq!(0, evaluate(&Position::startpos()));
+
+        //only the endgame values are used without pieces
+        let pos = Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        assert_eq!(0, game_phase(&pos));
+        let end_game = END_GAME_PIECE_VALUES[PieceKind::Pawn]
+            + PAWN_END_GAME_HEATMAP[52]
+            + KING_END_GAME_HEATMAP[60]
+            - KING_END_GAME_HEATMAP[59];
+        assert_eq!(end_game, evaluate(&pos));
+
+        //the same position with colors flipped has the opposite evaluation
+        let pos = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        );
+        let flipped = Position::from_fen(
+            "r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1",
+        );
+        assert_eq!(evaluate(&pos), evaluate(&flipped));
+    }
+
+    #[test]
+    fn incremental_scores() {
+        //castling, en passant and promotions with and without captures
+        for fen in TEST_FENS {
+            let mut pos = Position::from_fen(fen);
+            for mve in &pos.legal_moves() {
+                let mut pos_clone = pos.clone();
+                pos_clone.make_move(*mve);
+                for reply in &pos_clone.legal_moves() {
+                    let mut reply_pos = pos_clone.clone();
+                    reply_pos.make_move(*reply);
+                    assert_eq!(
+                        reply_pos.compute_scores(),
+                        (reply_pos.midgame_score, reply_pos.end_game_score)
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,696 +1,475 @@
 use crate::{
+    bitboard::Bitboard,
     board::{
-        bitboard_to_square, print_bitboard, Bitboard, Position, Result, Square, EMPTY, FILE,
+        bitboard_to_square, square_to_bitboard, Position, Result, Square, EMPTY, FILE,
         NOT_ON_A_FILE, NOT_ON_H_FILE, RANK, WHITE,
     },
-    magic::MAGIC,
     masks::*,
+    movelist::MoveList,
     mve::{
         move_to_algebraic, Move, BISHOP_PROMOTION, BISHOP_PROMOTION_CAPTURE, CAPTURE,
         DOUBLE_PAWN_PUSH, EN_PASSANT_CAPTURE, KING_CASTLE, KNIGHT_PROMOTION,
         KNIGHT_PROMOTION_CAPTURE, QUEEN_CASTLE, QUEEN_PROMOTION, QUEEN_PROMOTION_CAPTURE,
-        ROOK_PROMOTION, ROOK_PROMOTION_CAPTURE,
+        QUIET_MOVE, ROOK_PROMOTION, ROOK_PROMOTION_CAPTURE,
     },
-    piece::{BISHOP, KING, KNIGHT, PAWN, QUEEN, ROOK},
+    piece::PieceKind,
+    side::{AllMoves, Black, Captures, Evasions, GenType, QuietChecks, Quiets, Side, White},
 };
 
-const W_K_CASTLE_MUST_BE_SAFE_SQUARES: Bitboard = 0b111u64 << 1;
-const W_Q_CASTLE_MUST_BE_SAFE_SQUARES: Bitboard = 0b111u64 << 3;
-const W_K_CASTLE_MUST_BE_EMPTY_SQUARES: Bitboard = 0b11u64 << 1;
-const W_Q_CASTLE_MUST_BE_EMPTY_SQUARES: Bitboard = 0b111u64 << 4;
-
-const B_K_CASTLE_MUST_BE_SAFE_SQUARES: Bitboard = 0b111u64 << 57;
-const B_Q_CASTLE_MUST_BE_SAFE_SQUARES: Bitboard = 0b111u64 << 59;
-const B_K_CASTLE_MUST_BE_EMPTY_SQUARES: Bitboard = 0b11u64 << 57;
-const B_Q_CASTLE_MUST_BE_EMPTY_SQUARES: Bitboard = 0b111u64 << 60;
-
-const MAX_NUMBER_OF_LEGAL_MOVES: usize = 218;
-
 impl Position {
     pub fn print_legal_moves(&mut self) {
-        let moves = self.legal_moves();
-        for i in 0..moves.1 {
-            let mve = moves.0[i];
-            print!("{} ", move_to_algebraic(mve));
+        for mve in &self.legal_moves() {
+            print!("{} ", move_to_algebraic(*mve));
         }
         println!();
     }
 
-    //returns array with legal moves and the amount of legal moves
-    pub fn legal_moves(&mut self) -> ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize) {
-        let mut legal_moves: ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize) =
-            ([0; MAX_NUMBER_OF_LEGAL_MOVES], 0);
+    //all legal moves of the side to move
+    pub fn legal_moves(&mut self) -> MoveList {
+        if self.color_to_move == WHITE {
+            self.generate::<White, AllMoves>()
+        } else {
+            self.generate::<Black, AllMoves>()
+        }
+    }
 
-        let num_checks = self.update_check_and_pinned();
+    //quiescence_search only looks at captures so lets generate only captures
+    pub fn legal_captures(&mut self) -> MoveList {
+        if self.color_to_move == WHITE {
+            self.generate::<White, Captures>()
+        } else {
+            self.generate::<Black, Captures>()
+        }
+    }
+
+    //all legal moves which are not captures, including castling and quiet promotions
+    pub fn legal_quiets(&mut self) -> MoveList {
+        if self.color_to_move == WHITE {
+            self.generate::<White, Quiets>()
+        } else {
+            self.generate::<Black, Quiets>()
+        }
+    }
+
+    //quiet moves which check the enemy king, directly or by uncovering a friendly slider
+    pub fn legal_checks(&mut self) -> MoveList {
+        if self.color_to_move == WHITE {
+            self.generate::<White, QuietChecks>()
+        } else {
+            self.generate::<Black, QuietChecks>()
+        }
+    }
+
+    //all legal moves if the side to move is in check, otherwise no moves
+    pub fn legal_evasions(&mut self) -> MoveList {
+        if self.color_to_move == WHITE {
+            self.generate::<White, Evasions>()
+        } else {
+            self.generate::<Black, Evasions>()
+        }
+    }
+
+    //legal move generator for one color and one kind of moves, both are known at compile time
+    //so every combination is compiled into its own branch free function
+    fn generate<S: Side, G: GenType>(&mut self) -> MoveList {
+        let mut legal_moves = MoveList::new();
+
+        let num_checks = self.update_check_and_pinned_for::<S>();
+        //when in check the check mask already limits the generator to evasions
+        if G::EVASIONS && num_checks == 0 {
+            return legal_moves;
+        }
+
+        let enemy_board = S::Opponent::board(self);
+        let discoverers = if G::CHECKS {
+            self.discoverers::<S>()
+        } else {
+            EMPTY
+        };
 
         //If the king is checked twice, only king moves can be legal, so let not check for anything else
         if num_checks == 2 {
-            if self.color_to_move == WHITE {
-                //To get legal king moves we must know which squares are seen by the opponent
-                //Calculating this at once is faster compared to looking for attackers for every square
-                let seen_by_opponent = self.seen_by_black();
-                let king_pos = bitboard_to_square(self.w_piece_board[KING]);
-                let king_moves = self.w_king_move(king_pos) & !seen_by_opponent;
-                add_moves_from_bitboard(king_pos, king_moves & !self.b_board, &mut legal_moves);
-                add_capture_moves_from_bitboard(
-                    king_pos,
-                    king_moves & self.b_board,
-                    &mut legal_moves,
-                );
-                if legal_moves.1 == 0 {
-                    self.result = Result::Checkmate;
-                }
-                legal_moves
-            } else {
-                //To get legal king moves we must know which squares are seen by the opponent
-                //Calculating this at once is faster compared to looking for attackers for every square
-                let seen_by_opponent = self.seen_by_white();
-                let king_pos = bitboard_to_square(self.b_piece_board[KING]);
-                let king_moves = self.b_king_move(king_pos) & !seen_by_opponent;
-                add_moves_from_bitboard(king_pos, king_moves & !self.w_board, &mut legal_moves);
+            //To get legal king moves we must know which squares are seen by the opponent
+            //Calculating this at once is faster compared to looking for attackers for every square
+            let seen_by_opponent = self.seen_by::<S::Opponent>();
+            let king_pos = bitboard_to_square(S::piece_board(self)[PieceKind::King]);
+            let king_moves = self.king_move::<S>(king_pos) & !seen_by_opponent;
+            if G::QUIETS {
+                let quiet_moves =
+                    king_moves & !enemy_board & self.quiet_targets::<S, G>(king_pos, discoverers);
+                add_moves_from_bitboard(king_pos, quiet_moves, &mut legal_moves);
+            }
+            if G::CAPTURES {
                 add_capture_moves_from_bitboard(
                     king_pos,
-                    king_moves & self.w_board,
+                    king_moves & enemy_board,
                     &mut legal_moves,
                 );
-                if legal_moves.1 == 0 {
-                    self.result = Result::Checkmate;
-                }
-                legal_moves
             }
-        } else {
-            //1 or 0 checks
-            if self.color_to_move == WHITE {
-                //Pinned knights can never move
-                let mut knights =
-                    self.w_piece_board[KNIGHT] & !(self.orthogonal_pin | self.diagonal_pin);
-                while knights != 0 {
-                    let knight_pos: Square = bitboard_to_square(knights & !(knights - 1)); //getting the last bit
-                    knights &= knights - 1; //removing the last bit
-                    let moves = self.w_knight_move(knight_pos);
-                    add_capture_moves_from_bitboard(
-                        knight_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(knight_pos, moves & !self.b_board, &mut legal_moves);
-                }
-                //Queens are handled like a rook and a bishop on the same square
-
-                //Diagonally pinned rooks can never move
-                let rooks =
-                    (self.w_piece_board[ROOK] | self.w_piece_board[QUEEN]) & !self.diagonal_pin;
-                //Orthogonally pinned bishops can never move
-                let bishops =
-                    (self.w_piece_board[BISHOP] | self.w_piece_board[QUEEN]) & !self.orthogonal_pin;
-
-                //Orthogonally pinned rook moves.
-                let mut pinned_rooks = rooks & self.orthogonal_pin;
-                while pinned_rooks != 0 {
-                    let rook_pos: Square = bitboard_to_square(pinned_rooks & !(pinned_rooks - 1)); //getting the last bit
-                    pinned_rooks &= pinned_rooks - 1; //removing the last bit
-                    let moves = self.w_pinned_rook_moves(rook_pos);
-                    add_capture_moves_from_bitboard(
-                        rook_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(rook_pos, moves & !self.b_board, &mut legal_moves);
-                }
-
-                //Free rook moves.
-                let mut free_rooks = rooks & !self.orthogonal_pin;
-                while free_rooks != 0 {
-                    let rook_pos: Square = bitboard_to_square(free_rooks & !(free_rooks - 1)); //getting the last bit
-                    free_rooks &= free_rooks - 1; //removing the last bit
-                    let moves = self.w_rook_moves(rook_pos);
-                    add_capture_moves_from_bitboard(
-                        rook_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(rook_pos, moves & !self.b_board, &mut legal_moves);
-                }
+            if G::QUIETS && G::CAPTURES && legal_moves.is_empty() {
+                self.result = Result::Checkmate;
+            }
+            return legal_moves;
+        }
 
-                //Diagonally pinned bishop moves.
-                let mut pinned_bishops = bishops & self.diagonal_pin;
-                while pinned_bishops != 0 {
-                    let bishop_pos: Square =
-                        bitboard_to_square(pinned_bishops & !(pinned_bishops - 1)); //getting the last bit
-                    pinned_bishops &= pinned_bishops - 1; //removing the last bit
-                    let moves = self.w_pinned_bishop_moves(bishop_pos);
-                    add_capture_moves_from_bitboard(
-                        bishop_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(bishop_pos, moves & !self.b_board, &mut legal_moves);
-                }
+        //1 or 0 checks
+        //Pinned knights can never move
+        let knights =
+            S::piece_board(self)[PieceKind::Knight] & !(self.orthogonal_pin | self.diagonal_pin);
+        for knight_pos in knights {
+            let moves = self.knight_move::<S>(knight_pos);
+            self.add_piece_moves::<S, G>(knight_pos, moves, discoverers, &mut legal_moves);
+        }
+        //Queens are handled like a rook and a bishop on the same square
+
+        //Diagonally pinned rooks can never move
+        let rooks = (S::piece_board(self)[PieceKind::Rook]
+            | S::piece_board(self)[PieceKind::Queen])
+            & !self.diagonal_pin;
+        //Orthogonally pinned bishops can never move
+        let bishops = (S::piece_board(self)[PieceKind::Bishop]
+            | S::piece_board(self)[PieceKind::Queen])
+            & !self.orthogonal_pin;
+
+        //Orthogonally pinned rook moves.
+        let pinned_rooks = rooks & self.orthogonal_pin;
+        for rook_pos in pinned_rooks {
+            let moves = self.pinned_rook_moves::<S>(rook_pos);
+            self.add_piece_moves::<S, G>(rook_pos, moves, discoverers, &mut legal_moves);
+        }
 
-                //Free bishop moves.
-                let mut free_bishops = bishops & !self.diagonal_pin;
-                while free_bishops != 0 {
-                    let bishop_pos: Square = bitboard_to_square(free_bishops & !(free_bishops - 1)); //getting the last bit
-                    free_bishops &= free_bishops - 1; //removing the last bit
-                    let moves = self.w_bishop_moves(bishop_pos);
-                    add_capture_moves_from_bitboard(
-                        bishop_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(bishop_pos, moves & !self.b_board, &mut legal_moves);
-                }
+        //Free rook moves.
+        let free_rooks = rooks & !self.orthogonal_pin;
+        for rook_pos in free_rooks {
+            let moves = self.rook_moves::<S>(rook_pos);
+            self.add_piece_moves::<S, G>(rook_pos, moves, discoverers, &mut legal_moves);
+        }
 
-                let pawns = self.w_piece_board[PAWN];
-                //Diagonally pinned pawn can never go forward
-                let marching_pawns = pawns & !self.diagonal_pin;
-                //Orthogonally pinned pawns can never capture
-                let capturing_pawns = pawns & !self.orthogonal_pin;
+        //Diagonally pinned bishop moves.
+        let pinned_bishops = bishops & self.diagonal_pin;
+        for bishop_pos in pinned_bishops {
+            let moves = self.pinned_bishop_moves::<S>(bishop_pos);
+            self.add_piece_moves::<S, G>(bishop_pos, moves, discoverers, &mut legal_moves);
+        }
 
-                let double_marchers = marching_pawns & RANK[1];
+        //Free bishop moves.
+        let free_bishops = bishops & !self.diagonal_pin;
+        for bishop_pos in free_bishops {
+            let moves = self.bishop_moves::<S>(bishop_pos);
+            self.add_piece_moves::<S, G>(bishop_pos, moves, discoverers, &mut legal_moves);
+        }
 
-                //Orthogonally pinned marchers
-                let mut pinned_marchers = marching_pawns & self.orthogonal_pin;
-                while pinned_marchers != 0 {
-                    let pawn_pos: Bitboard = pinned_marchers & !(pinned_marchers - 1); //getting the last bit
-                    pinned_marchers &= pinned_marchers - 1; //removing the last bit
-                    let moves = self.w_pinned_pawn_forward_mask(&pawn_pos);
-                    add_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[7],
-                        &mut legal_moves,
-                    );
-                    add_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[7],
-                        &mut legal_moves,
-                    );
-                }
-                //Free marchers
-                let mut marchers = marching_pawns & !self.orthogonal_pin;
-                while marchers != 0 {
-                    let pawn_pos: Bitboard = marchers & !(marchers - 1); //getting the last bit
-                    marchers &= marchers - 1; //removing the last bit
-                    let moves = self.w_pawn_forward_mask(&pawn_pos);
-                    add_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[7],
-                        &mut legal_moves,
-                    );
-                    add_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[7],
-                        &mut legal_moves,
-                    )
-                }
-                //Orthogonally pinned double marchers
-                let mut pinned_marchers = double_marchers & self.orthogonal_pin;
-                while pinned_marchers != 0 {
-                    let pawn_pos: Bitboard = pinned_marchers & !(pinned_marchers - 1); //getting the last bit
-                    pinned_marchers &= pinned_marchers - 1; //removing the last bit
-                    let moves = self.w_pinned_pawn_doubleforward_mask(&pawn_pos);
-                    add_double_forward_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves,
-                        &mut legal_moves,
-                    );
-                }
-                //Free double marchers
-                let mut marchers = double_marchers & !self.orthogonal_pin;
-                while marchers != 0 {
-                    let pawn_pos: Bitboard = marchers & !(marchers - 1); //getting the last bit
-                    marchers &= marchers - 1; //removing the last bit
-                    let moves = self.w_pawn_doubleforward_mask(&pawn_pos);
-                    add_double_forward_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves,
-                        &mut legal_moves,
-                    );
-                }
+        let pawns = S::piece_board(self)[PieceKind::Pawn];
 
-                //Diagonally pinned capturers
-                let mut pinned_capturers = capturing_pawns & self.diagonal_pin;
-                while pinned_capturers != 0 {
-                    let pawn_pos: Bitboard = pinned_capturers & !(pinned_capturers - 1); //getting the last bit
-                    pinned_capturers &= pinned_capturers - 1; //removing the last bit
-                    let moves = self.w_pinned_pawn_capture_mask(&pawn_pos);
-                    add_capture_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[7],
-                        &mut legal_moves,
-                    );
-                    add_capture_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[7],
-                        &mut legal_moves,
-                    );
-                }
+        if G::QUIETS {
+            //Diagonally pinned pawn can never go forward
+            let marching_pawns = pawns & !self.diagonal_pin;
+            let double_marchers = marching_pawns & S::DOUBLE_PUSH_RANK;
 
-                //Free capturers
-                let mut capturers = capturing_pawns & !self.diagonal_pin;
-                while capturers != 0 {
-                    let pawn_pos: Bitboard = capturers & !(capturers - 1); //getting the last bit
-                    capturers &= capturers - 1; //removing the last bit
-                    let moves = self.w_pawn_capture_mask(&pawn_pos);
-                    add_capture_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[7],
-                        &mut legal_moves,
-                    );
-                    add_capture_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[7],
-                        &mut legal_moves,
-                    );
-                }
-                //The best way to find the candiates is to use the enemy capture mask from the en passant target square
-                let en_passant_pawns =
-                    capturing_pawns & b_pawn_capture_mask(&self.en_passant_target_square);
+            //Orthogonally pinned marchers
+            let pinned_marchers = marching_pawns & self.orthogonal_pin;
+            for pawn_square in pinned_marchers {
+                let pawn_pos = pawn_square.bitboard();
+                let moves = self.pinned_pawn_forward_mask::<S>(&pawn_pos);
+                self.add_pawn_pushes::<S, G>(pawn_square, moves, discoverers, &mut legal_moves);
+            }
+            //Free marchers
+            let marchers = marching_pawns & !self.orthogonal_pin;
+            for pawn_square in marchers {
+                let pawn_pos = pawn_square.bitboard();
+                let moves = self.pawn_forward_mask::<S>(&pawn_pos);
+                self.add_pawn_pushes::<S, G>(pawn_square, moves, discoverers, &mut legal_moves);
+            }
+            //Orthogonally pinned double marchers
+            let pinned_marchers = double_marchers & self.orthogonal_pin;
+            for pawn_square in pinned_marchers {
+                let pawn_pos = pawn_square.bitboard();
+                let moves = self.pinned_pawn_doubleforward_mask::<S>(&pawn_pos)
+                    & self.quiet_targets::<S, G>(pawn_square, discoverers);
+                add_double_forward_moves_from_bitboard(pawn_square, moves, &mut legal_moves);
+            }
+            //Free double marchers
+            let marchers = double_marchers & !self.orthogonal_pin;
+            for pawn_square in marchers {
+                let pawn_pos = pawn_square.bitboard();
+                let moves = self.pawn_doubleforward_mask::<S>(&pawn_pos)
+                    & self.quiet_targets::<S, G>(pawn_square, discoverers);
+                add_double_forward_moves_from_bitboard(pawn_square, moves, &mut legal_moves);
+            }
+        }
 
-                //Diagonally pinned en passant
-                let mut pinned_en_passant = en_passant_pawns & self.diagonal_pin;
-                while pinned_en_passant != 0 {
-                    let pawn_pos: Bitboard = pinned_en_passant & !(pinned_en_passant - 1); //getting the last bit
-                    pinned_en_passant &= pinned_en_passant - 1; //removing the last bit
-                    let moves = self.w_pinned_pawn_en_passant_mask(&pawn_pos);
-                    self.add_w_en_passant_moves(pawn_pos, moves, &mut legal_moves);
-                }
+        if G::CAPTURES {
+            //Orthogonally pinned pawns can never capture
+            let capturing_pawns = pawns & !self.orthogonal_pin;
 
-                //En passant
-                let mut en_passant = en_passant_pawns & !self.diagonal_pin;
-                while en_passant != 0 {
-                    let pawn_pos: Bitboard = en_passant & !(en_passant - 1); //getting the last bit
-                    en_passant &= en_passant - 1; //removing the last bit
-                    let moves = self.w_pawn_en_passant_mask(&pawn_pos);
-                    self.add_w_en_passant_moves(pawn_pos, moves, &mut legal_moves);
-                }
+            //Diagonally pinned capturers
+            let pinned_capturers = capturing_pawns & self.diagonal_pin;
+            for pawn_square in pinned_capturers {
+                let pawn_pos = pawn_square.bitboard();
+                let moves = self.pinned_pawn_capture_mask::<S>(&pawn_pos);
+                add_capture_moves_from_bitboard(
+                    pawn_square,
+                    moves & !S::PROMOTION_RANK,
+                    &mut legal_moves,
+                );
+                add_capture_promotion_moves_from_bitboard(
+                    pawn_square,
+                    moves & S::PROMOTION_RANK,
+                    &mut legal_moves,
+                );
+            }
 
-                //To get legal king moves we must know which squares are seen by the opponent
-                //Calculating this at once is faster compared to looking for attackers for every square
-                let seen_by_opponent = self.seen_by_black();
-                let king_pos = bitboard_to_square(self.w_piece_board[KING]);
-                let king_moves = self.w_king_move(king_pos) & !seen_by_opponent;
-                add_moves_from_bitboard(king_pos, king_moves & !self.b_board, &mut legal_moves);
+            //Free capturers
+            let capturers = capturing_pawns & !self.diagonal_pin;
+            for pawn_square in capturers {
+                let pawn_pos = pawn_square.bitboard();
+                let moves = self.pawn_capture_mask::<S>(&pawn_pos);
                 add_capture_moves_from_bitboard(
-                    king_pos,
-                    king_moves & self.b_board,
+                    pawn_square,
+                    moves & !S::PROMOTION_RANK,
+                    &mut legal_moves,
+                );
+                add_capture_promotion_moves_from_bitboard(
+                    pawn_square,
+                    moves & S::PROMOTION_RANK,
                     &mut legal_moves,
                 );
+            }
+            //The best way to find the candiates is to use the enemy capture mask from the en passant target square
+            let en_passant_pawns =
+                capturing_pawns & S::Opponent::pawn_attacks(&self.en_passant_target_square);
+
+            //Diagonally pinned en passant
+            let pinned_en_passant = en_passant_pawns & self.diagonal_pin;
+            for pawn_square in pinned_en_passant {
+                let pawn_pos = pawn_square.bitboard();
+                let moves = self.pinned_pawn_en_passant_mask::<S>(&pawn_pos);
+                self.add_en_passant_moves::<S>(pawn_pos, moves, &mut legal_moves);
+            }
 
-                //castling moves
-                if self.castling_rights[0]
-                    && (W_K_CASTLE_MUST_BE_SAFE_SQUARES & seen_by_opponent)
-                        | (W_K_CASTLE_MUST_BE_EMPTY_SQUARES & self.blocker_board)
-                        == 0
-                {
-                    let mve = 3 | (1 << 6) | KING_CASTLE;
-                    unsafe {
-                        let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-                        *ptr = mve;
-                    }
-                    legal_moves.1 += 1;
-                }
+            //En passant
+            let en_passant = en_passant_pawns & !self.diagonal_pin;
+            for pawn_square in en_passant {
+                let pawn_pos = pawn_square.bitboard();
+                let moves = self.pawn_en_passant_mask::<S>(&pawn_pos);
+                self.add_en_passant_moves::<S>(pawn_pos, moves, &mut legal_moves);
+            }
+        }
+
+        //To get legal king moves we must know which squares are seen by the opponent
+        //Calculating this at once is faster compared to looking for attackers for every square
+        let seen_by_opponent = self.seen_by::<S::Opponent>();
+        let king_pos = bitboard_to_square(S::piece_board(self)[PieceKind::King]);
+        let king_moves = self.king_move::<S>(king_pos) & !seen_by_opponent;
+        if G::QUIETS {
+            let quiet_moves =
+                king_moves & !enemy_board & self.quiet_targets::<S, G>(king_pos, discoverers);
+            add_moves_from_bitboard(king_pos, quiet_moves, &mut legal_moves);
+        }
+        if G::CAPTURES {
+            add_capture_moves_from_bitboard(king_pos, king_moves & enemy_board, &mut legal_moves);
+        }
 
-                if self.castling_rights[1]
-                    && (W_Q_CASTLE_MUST_BE_SAFE_SQUARES & seen_by_opponent)
-                        | (W_Q_CASTLE_MUST_BE_EMPTY_SQUARES & self.blocker_board)
-                        == 0
+        //castling moves
+        if G::QUIETS {
+            for (right, flag) in [
+                (S::KING_CASTLE_RIGHT, KING_CASTLE),
+                (S::QUEEN_CASTLE_RIGHT, QUEEN_CASTLE),
+            ] {
+                if !self.castling_rights[right] {
+                    continue;
+                }
+                let rook = self.castling_rooks[right];
+                let mve = Move::new(king_pos, rook, flag);
+                if self.castling_empty[right] & self.blocker_board == EMPTY
+                    && self.castling_king_path[right] & seen_by_opponent == EMPTY
+                    && (matches!(rook.file(), 0 | 7)
+                        || !self.castling_uncovers_king::<S>(king_pos, right))
+                    && (!G::CHECKS || self.gives_check_for::<S>(mve))
                 {
-                    let mve = 3 | (5 << 6) | QUEEN_CASTLE;
-                    unsafe {
-                        let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-                        *ptr = mve;
-                    }
-                    legal_moves.1 += 1;
-                }
-                if legal_moves.1 == 0 {
-                    self.result = if num_checks == 0 {
-                        Result::Draw
-                    } else {
-                        Result::Checkmate
-                    }
+                    legal_moves.push(mve);
                 }
-                legal_moves
+            }
+        }
+        if G::QUIETS && G::CAPTURES && legal_moves.is_empty() {
+            self.result = if num_checks == 0 {
+                Result::Draw
             } else {
-                //Pinned knights can never move
-                let mut knights =
-                    self.b_piece_board[KNIGHT] & !(self.orthogonal_pin | self.diagonal_pin);
-                while knights != 0 {
-                    let knight_pos: Square = bitboard_to_square(knights & !(knights - 1)); //getting the last bit
-                    knights &= knights - 1; //removing the last bit
-                    let moves = self.b_knight_move(knight_pos);
-                    add_capture_moves_from_bitboard(
-                        knight_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(knight_pos, moves & !self.w_board, &mut legal_moves);
-                }
-                //Queens are handled like a rook and a bishop on the same square
-
-                //Diagonally pinned rooks can never move
-                let rooks =
-                    (self.b_piece_board[ROOK] | self.b_piece_board[QUEEN]) & !self.diagonal_pin;
-                //Orthogonally pinned bishops can never move
-                let bishops =
-                    (self.b_piece_board[BISHOP] | self.b_piece_board[QUEEN]) & !self.orthogonal_pin;
-
-                //Orthogonally pinned rook moves.
-                let mut pinned_rooks = rooks & self.orthogonal_pin;
-                while pinned_rooks != 0 {
-                    let rook_pos: Square = bitboard_to_square(pinned_rooks & !(pinned_rooks - 1)); //getting the last bit
-                    pinned_rooks &= pinned_rooks - 1; //removing the last bit
-                    let moves = self.b_pinned_rook_moves(rook_pos);
-                    add_capture_moves_from_bitboard(
-                        rook_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(rook_pos, moves & !self.w_board, &mut legal_moves);
-                }
-
-                //Free rook moves.
-                let mut free_rooks = rooks & !self.orthogonal_pin;
-                while free_rooks != 0 {
-                    let rook_pos: Square = bitboard_to_square(free_rooks & !(free_rooks - 1)); //getting the last bit
-                    free_rooks &= free_rooks - 1; //removing the last bit
-                    let moves = self.b_rook_moves(rook_pos);
-                    add_capture_moves_from_bitboard(
-                        rook_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(rook_pos, moves & !self.w_board, &mut legal_moves);
-                }
-
-                //Diagonally pinned bishop moves.
-                let mut pinned_bishops = bishops & self.diagonal_pin;
-                while pinned_bishops != 0 {
-                    let bishop_pos: Square =
-                        bitboard_to_square(pinned_bishops & !(pinned_bishops - 1)); //getting the last bit
-                    pinned_bishops &= pinned_bishops - 1; //removing the last bit
-                    let moves = self.b_pinned_bishop_moves(bishop_pos);
-                    add_capture_moves_from_bitboard(
-                        bishop_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(bishop_pos, moves & !self.w_board, &mut legal_moves);
-                }
-
-                //Free bishop moves.
-                let mut free_bishops = bishops & !self.diagonal_pin;
-                while free_bishops != 0 {
-                    let bishop_pos: Square = bitboard_to_square(free_bishops & !(free_bishops - 1)); //getting the last bit
-                    free_bishops &= free_bishops - 1; //removing the last bit
-                    let moves = self.b_bishop_moves(bishop_pos);
-                    add_capture_moves_from_bitboard(
-                        bishop_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                    add_moves_from_bitboard(bishop_pos, moves & !self.w_board, &mut legal_moves);
-                }
-
-                let pawns = self.b_piece_board[PAWN];
-                //Diagonally pinned pawn can never go forward
-                let marching_pawns = pawns & !self.diagonal_pin;
-                //Orthogonally pinned pawns can never capture
-                let capturing_pawns = pawns & !self.orthogonal_pin;
-
-                let double_marchers = marching_pawns & RANK[6];
-
-                //Orthogonally pinned marchers
-                let mut pinned_marchers = marching_pawns & self.orthogonal_pin;
-                while pinned_marchers != 0 {
-                    let pawn_pos: Bitboard = pinned_marchers & !(pinned_marchers - 1); //getting the last bit
-                    pinned_marchers &= pinned_marchers - 1; //removing the last bit
-                    let moves = self.b_pinned_pawn_forward_mask(&pawn_pos);
-                    add_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[0],
-                        &mut legal_moves,
-                    );
-                    add_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[0],
-                        &mut legal_moves,
-                    );
-                }
-                //Free marchers
-                let mut marchers = marching_pawns & !self.orthogonal_pin;
-                while marchers != 0 {
-                    let pawn_pos: Bitboard = marchers & !(marchers - 1); //getting the last bit
-                    marchers &= marchers - 1; //removing the last bit
-                    let moves = self.b_pawn_forward_mask(&pawn_pos);
-                    add_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[0],
-                        &mut legal_moves,
-                    );
-                    add_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[0],
-                        &mut legal_moves,
-                    )
-                }
-                //Orthogonally pinned double marchers
-                let mut pinned_marchers = double_marchers & self.orthogonal_pin;
-                while pinned_marchers != 0 {
-                    let pawn_pos: Bitboard = pinned_marchers & !(pinned_marchers - 1); //getting the last bit
-                    pinned_marchers &= pinned_marchers - 1; //removing the last bit
-                    let moves = self.b_pinned_pawn_doubleforward_mask(&pawn_pos);
-                    add_double_forward_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves,
-                        &mut legal_moves,
-                    );
-                }
-                //Free double marchers
-                let mut marchers = double_marchers & !self.orthogonal_pin;
-                while marchers != 0 {
-                    let pawn_pos: Bitboard = marchers & !(marchers - 1); //getting the last bit
-                    marchers &= marchers - 1; //removing the last bit
-                    let moves = self.b_pawn_doubleforward_mask(&pawn_pos);
-                    add_double_forward_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves,
-                        &mut legal_moves,
-                    );
-                }
-
-                //Diagonally pinned capturers
-                let mut pinned_capturers = capturing_pawns & self.diagonal_pin;
-                while pinned_capturers != 0 {
-                    let pawn_pos: Bitboard = pinned_capturers & !(pinned_capturers - 1); //getting the last bit
-                    pinned_capturers &= pinned_capturers - 1; //removing the last bit
-                    let moves = self.b_pinned_pawn_capture_mask(&pawn_pos);
-                    add_capture_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[0],
-                        &mut legal_moves,
-                    );
-                    add_capture_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[0],
-                        &mut legal_moves,
-                    );
-                }
-
-                //Free capturers
-                let mut capturers = capturing_pawns & !self.diagonal_pin;
-                while capturers != 0 {
-                    let pawn_pos: Bitboard = capturers & !(capturers - 1); //getting the last bit
-                    capturers &= capturers - 1; //removing the last bit
-                    let moves = self.b_pawn_capture_mask(&pawn_pos);
-                    add_capture_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[0],
-                        &mut legal_moves,
-                    );
-                    add_capture_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[0],
-                        &mut legal_moves,
-                    );
-                }
-                //The best way to find the candiates is to use the enemy capture mask from the en passant target square
-                let en_passant_pawns =
-                    capturing_pawns & w_pawn_capture_mask(&self.en_passant_target_square);
+                Result::Checkmate
+            }
+        }
+        legal_moves
+    }
 
-                //Diagonally pinned en passant
-                let mut pinned_en_passant = en_passant_pawns & self.diagonal_pin;
-                while pinned_en_passant != 0 {
-                    let pawn_pos: Bitboard = pinned_en_passant & !(pinned_en_passant - 1); //getting the last bit
-                    pinned_en_passant &= pinned_en_passant - 1; //removing the last bit
-                    let moves = self.b_pinned_pawn_en_passant_mask(&pawn_pos);
-                    self.add_b_en_passant_moves(pawn_pos, moves, &mut legal_moves);
-                }
+    //adds the captures and quiet moves of a knight, bishop, rook or queen
+    #[inline]
+    fn add_piece_moves<S: Side, G: GenType>(
+        &self,
+        from: Square,
+        moves: Bitboard,
+        discoverers: Bitboard,
+        legal_moves: &mut MoveList,
+    ) {
+        let enemy_board = S::Opponent::board(self);
+        if G::CAPTURES {
+            add_capture_moves_from_bitboard(from, moves & enemy_board, legal_moves);
+        }
+        if G::QUIETS {
+            let quiet_moves = moves & !enemy_board & self.quiet_targets::<S, G>(from, discoverers);
+            add_moves_from_bitboard(from, quiet_moves, legal_moves);
+        }
+    }
 
-                //En passant
-                let mut en_passant = en_passant_pawns & !self.diagonal_pin;
-                while en_passant != 0 {
-                    let pawn_pos: Bitboard = en_passant & !(en_passant - 1); //getting the last bit
-                    en_passant &= en_passant - 1; //removing the last bit
-                    let moves = self.b_pawn_en_passant_mask(&pawn_pos);
-                    self.add_b_en_passant_moves(pawn_pos, moves, &mut legal_moves);
-                }
+    //adds the single pawn pushes including the quiet promotions
+    #[inline]
+    fn add_pawn_pushes<S: Side, G: GenType>(
+        &self,
+        from: Square,
+        moves: Bitboard,
+        discoverers: Bitboard,
+        legal_moves: &mut MoveList,
+    ) {
+        let quiet_moves =
+            moves & !S::PROMOTION_RANK & self.quiet_targets::<S, G>(from, discoverers);
+        add_moves_from_bitboard(from, quiet_moves, legal_moves);
+        if !G::CHECKS {
+            add_promotion_moves_from_bitboard(from, moves & S::PROMOTION_RANK, legal_moves);
+            return;
+        }
+        //the promoted piece can check through the square the pawn leaves, so every promotion
+        //is checked on its own
+        let mut promotions = MoveList::new();
+        add_promotion_moves_from_bitboard(from, moves & S::PROMOTION_RANK, &mut promotions);
+        for &mve in &promotions {
+            if self.gives_check_for::<S>(mve) {
+                legal_moves.push(mve);
+            }
+        }
+    }
 
-                //To get legal king moves we must know which squares are seen by the opponent
-                //Calculating this at once is faster compared to looking for attackers for every square
-                let seen_by_opponent = self.seen_by_white();
-                let king_pos = bitboard_to_square(self.b_piece_board[KING]);
-                let king_moves = self.b_king_move(king_pos) & !seen_by_opponent;
-                add_moves_from_bitboard(king_pos, king_moves & !self.w_board, &mut legal_moves);
-                add_capture_moves_from_bitboard(
-                    king_pos,
-                    king_moves & self.w_board,
-                    &mut legal_moves,
-                );
+    //the squares a quiet move from the square has to land on, every square unless only checks
+    //are generated
+    #[inline]
+    fn quiet_targets<S: Side, G: GenType>(&self, from: Square, discoverers: Bitboard) -> Bitboard {
+        if !G::CHECKS {
+            return !EMPTY;
+        }
+        self.check_squares::<S>(from, discoverers)
+    }
 
-                //castling moves
-                if self.castling_rights[2]
-                    && (B_K_CASTLE_MUST_BE_SAFE_SQUARES & seen_by_opponent)
-                        | (B_K_CASTLE_MUST_BE_EMPTY_SQUARES & self.blocker_board)
-                        == 0
-                {
-                    let mve = 59 | (57 << 6) | KING_CASTLE;
-                    unsafe {
-                        let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-                        *ptr = mve;
-                    }
-                    legal_moves.1 += 1;
-                }
+    //the squares from which the piece on the square checks the enemy king after a quiet move
+    //a discoverer checks from every square which is not on its line to the enemy king
+    fn check_squares<S: Side>(&self, from: Square, discoverers: Bitboard) -> Bitboard {
+        let enemy_king = S::Opponent::piece_board(self)[PieceKind::King];
+        let king_square = bitboard_to_square(enemy_king);
+        let direct = match self.pieces[from] {
+            Some(PieceKind::Pawn) => S::Opponent::pawn_attacks(&enemy_king),
+            Some(PieceKind::Knight) => KNIGHT_MASK[king_square],
+            Some(PieceKind::Bishop) => {
+                self.seen_by_bishop_custom_blocker(king_square, self.blocker_board)
+            }
+            Some(PieceKind::Rook) => {
+                self.seen_by_rook_custom_blocker(king_square, self.blocker_board)
+            }
+            Some(PieceKind::Queen) => {
+                self.seen_by_bishop_custom_blocker(king_square, self.blocker_board)
+                    | self.seen_by_rook_custom_blocker(king_square, self.blocker_board)
+            }
+            _ => EMPTY,
+        };
+        if !discoverers.contains(from) {
+            return direct;
+        }
+        let king_lines = self.seen_by_rook_custom_blocker(king_square, EMPTY);
+        let line = if king_lines.contains(from) {
+            king_lines & self.seen_by_rook_custom_blocker(from, EMPTY)
+        } else {
+            self.seen_by_bishop_custom_blocker(king_square, EMPTY)
+                & self.seen_by_bishop_custom_blocker(from, EMPTY)
+        };
+        direct | !line
+    }
 
-                if self.castling_rights[3]
-                    && (B_Q_CASTLE_MUST_BE_SAFE_SQUARES & seen_by_opponent)
-                        | (B_Q_CASTLE_MUST_BE_EMPTY_SQUARES & self.blocker_board)
-                        == 0
-                {
-                    let mve = 59 | (61 << 6) | QUEEN_CASTLE;
-                    unsafe {
-                        let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-                        *ptr = mve;
-                    }
-                    legal_moves.1 += 1;
-                }
-                if legal_moves.1 == 0 {
-                    self.result = if num_checks == 0 {
-                        Result::Draw
-                    } else {
-                        Result::Checkmate
-                    }
-                }
-                legal_moves
+    //the friendly pieces which are the only piece between the enemy king and a friendly slider
+    fn discoverers<S: Side>(&self) -> Bitboard {
+        let king_square = bitboard_to_square(S::Opponent::piece_board(self)[PieceKind::King]);
+        let friendly_board = S::board(self);
+        let rooks = S::piece_board(self)[PieceKind::Rook] | S::piece_board(self)[PieceKind::Queen];
+        let bishops =
+            S::piece_board(self)[PieceKind::Bishop] | S::piece_board(self)[PieceKind::Queen];
+
+        let mut discoverers = EMPTY;
+        //removing a piece the king sees reveals the squares behind it
+        let rook_vision = self.seen_by_rook_custom_blocker(king_square, self.blocker_board);
+        for blocker in rook_vision & friendly_board {
+            let behind = self
+                .seen_by_rook_custom_blocker(king_square, self.blocker_board ^ blocker.bitboard())
+                & !rook_vision;
+            if behind & rooks != EMPTY {
+                discoverers |= blocker.bitboard();
+            }
+        }
+        let bishop_vision = self.seen_by_bishop_custom_blocker(king_square, self.blocker_board);
+        for blocker in bishop_vision & friendly_board {
+            let behind = self.seen_by_bishop_custom_blocker(
+                king_square,
+                self.blocker_board ^ blocker.bitboard(),
+            ) & !bishop_vision;
+            if behind & bishops != EMPTY {
+                discoverers |= blocker.bitboard();
             }
         }
+        discoverers
     }
 }
 
 //It is faster to have special case functions than using branching to add the move flags
-pub fn add_moves_from_bitboard(
-    from: Square,
-    mut to: Bitboard,
-    legal_moves: &mut ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize),
-) {
-    while to != 0 {
-        let last_bit: Bitboard = to & !(to - 1); //getting the last bit
-        to &= to - 1; //removing the last bit
-        let mve = from as Move | ((last_bit.trailing_zeros() as Move) << 6);
-        unsafe {
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve;
-        }
-        legal_moves.1 += 1;
+pub fn add_moves_from_bitboard(from: Square, to: Bitboard, legal_moves: &mut MoveList) {
+    for to_square in to {
+        legal_moves.push(Move::new(from, to_square, QUIET_MOVE));
     }
 }
 pub fn add_double_forward_moves_from_bitboard(
     from: Square,
-    mut to: Bitboard,
-    legal_moves: &mut ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize),
+    to: Bitboard,
+    legal_moves: &mut MoveList,
 ) {
-    if to != 0 {
-        let mve = from as Move | ((to.trailing_zeros() as Move) << 6);
-        unsafe {
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | DOUBLE_PAWN_PUSH;
-        }
-        legal_moves.1 += 1;
+    if to != EMPTY {
+        legal_moves.push(Move::new(from, bitboard_to_square(to), DOUBLE_PAWN_PUSH));
     }
 }
-pub fn add_capture_moves_from_bitboard(
-    from: Square,
-    mut to: Bitboard,
-    legal_moves: &mut ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize),
-) {
-    while to != 0 {
-        let last_bit: Bitboard = to & !(to - 1); //getting the last bit
-        to &= to - 1; //removing the last bit
-        let mve = from as Move | ((last_bit.trailing_zeros() as Move) << 6) | CAPTURE;
-        unsafe {
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve;
-        }
-        legal_moves.1 += 1;
+pub fn add_capture_moves_from_bitboard(from: Square, to: Bitboard, legal_moves: &mut MoveList) {
+    for to_square in to {
+        legal_moves.push(Move::new(from, to_square, CAPTURE));
     }
 }
-pub fn add_promotion_moves_from_bitboard(
-    from: Square,
-    mut to: Bitboard,
-    legal_moves: &mut ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize),
-) {
-    while to != 0 {
-        let last_bit: Bitboard = to & !(to - 1); //getting the last bit
-        to &= to - 1; //removing the last bit
-        let mve = from as Move | ((last_bit.trailing_zeros() as Move) << 6);
-        unsafe {
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | QUEEN_PROMOTION;
-            legal_moves.1 += 1;
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | KNIGHT_PROMOTION;
-            legal_moves.1 += 1;
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | ROOK_PROMOTION;
-            legal_moves.1 += 1;
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | BISHOP_PROMOTION;
-            legal_moves.1 += 1;
-        }
+pub fn add_promotion_moves_from_bitboard(from: Square, to: Bitboard, legal_moves: &mut MoveList) {
+    for to_square in to {
+        legal_moves.push(Move::new(from, to_square, QUEEN_PROMOTION));
+        legal_moves.push(Move::new(from, to_square, KNIGHT_PROMOTION));
+        legal_moves.push(Move::new(from, to_square, ROOK_PROMOTION));
+        legal_moves.push(Move::new(from, to_square, BISHOP_PROMOTION));
     }
 }
 pub fn add_capture_promotion_moves_from_bitboard(
     from: Square,
-    mut to: Bitboard,
-    legal_moves: &mut ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize),
+    to: Bitboard,
+    legal_moves: &mut MoveList,
 ) {
-    while to != 0 {
-        let last_bit: Bitboard = to & !(to - 1); //getting the last bit
-        to &= to - 1; //removing the last bit
-        let mve = from as Move | ((last_bit.trailing_zeros() as Move) << 6);
-        unsafe {
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | QUEEN_PROMOTION_CAPTURE;
-            legal_moves.1 += 1;
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | KNIGHT_PROMOTION_CAPTURE;
-            legal_moves.1 += 1;
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | ROOK_PROMOTION_CAPTURE;
-            legal_moves.1 += 1;
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | BISHOP_PROMOTION_CAPTURE;
-            legal_moves.1 += 1;
-        }
+    for to_square in to {
+        legal_moves.push(Move::new(from, to_square, QUEEN_PROMOTION_CAPTURE));
+        legal_moves.push(Move::new(from, to_square, KNIGHT_PROMOTION_CAPTURE));
+        legal_moves.push(Move::new(from, to_square, ROOK_PROMOTION_CAPTURE));
+        legal_moves.push(Move::new(from, to_square, BISHOP_PROMOTION_CAPTURE));
     }
 }
 
 impl Position {
-    pub fn add_w_en_passant_moves(
+    pub fn add_en_passant_moves<S: Side>(
         &mut self,
         from: Bitboard,
         to: Bitboard,
-        legal_moves: &mut ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize),
+        legal_moves: &mut MoveList,
     ) {
         if to == EMPTY {
             return;
@@ -698,789 +477,363 @@ impl Position {
         //the only edge case which the pinned bitboards does not work for is the orthogonal pin
         //on two pieces caused by enpassant being the only move which can remove pieces from two squares
 
-        //Optimization to only check for the edge case if the friendly king and an enemy orthogonal slider exist
-        //on the 5th rank
-        if (self.w_piece_board[KING] & RANK[4])
-            | ((self.b_piece_board[ROOK] | self.b_piece_board[QUEEN]) & RANK[4])
-            != 0
+        //Optimization to only check for the edge case if the friendly king or an enemy orthogonal slider is
+        //on the rank en passant is captured from
+        let enemy_orthogonal_sliders = S::Opponent::piece_board(self)[PieceKind::Rook]
+            | S::Opponent::piece_board(self)[PieceKind::Queen];
+        if (S::piece_board(self)[PieceKind::King] & S::EN_PASSANT_RANK)
+            | (enemy_orthogonal_sliders & S::EN_PASSANT_RANK)
+            != EMPTY
         {
-            let pawns_to_be_removed = from | (to >> 8);
+            let pawns_to_be_removed = from | S::Opponent::forward(to, 1);
             //We then do a rook attack from the friendly king and check if it attacks any
-            //enemy orthogonal slider along the 5th rank
+            //enemy orthogonal slider along the en passant rank
             let king_vision = self.seen_by_rook_custom_blocker(
-                bitboard_to_square(self.w_piece_board[KING]),
+                bitboard_to_square(S::piece_board(self)[PieceKind::King]),
                 self.blocker_board ^ pawns_to_be_removed,
-            ) & RANK[4];
-            if king_vision & (self.b_piece_board[ROOK] | self.b_piece_board[QUEEN]) != 0 {
+            ) & S::EN_PASSANT_RANK;
+            if king_vision & enemy_orthogonal_sliders != EMPTY {
                 //en passant is illegal
                 return;
             }
         }
-        let mve = from.trailing_zeros() as Move | ((to.trailing_zeros() as Move) << 6);
-        unsafe {
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | EN_PASSANT_CAPTURE;
-        }
-        legal_moves.1 += 1;
-    }
-
-    pub fn add_b_en_passant_moves(
-        &mut self,
-        from: Bitboard,
-        to: Bitboard,
-        legal_moves: &mut ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize),
-    ) {
-        if to == EMPTY {
-            return;
-        }
-        //the only edge case which the pinned bitboards does not work for is the orthogonal pin
-        //on two pieces caused by enpassant being the only move which can remove pieces from two squares
-
-        //Optimization to only check for the edge case if the friendly king and an enemy orthogonal slider exist
-        //on the 4th rank
-        if (self.b_piece_board[KING] & RANK[3])
-            | ((self.w_piece_board[ROOK] | self.w_piece_board[QUEEN]) & RANK[3])
-            != 0
-        {
-            let pawns_to_be_removed = from | (to << 8);
-            //We then do a rook attack from the friendly king and check if it attacks any
-            //enemy orthogonal slider along the 4th rank
-            let king_vision = self.seen_by_rook_custom_blocker(
-                bitboard_to_square(self.b_piece_board[KING]),
-                self.blocker_board ^ pawns_to_be_removed,
-            ) & RANK[3];
-            if king_vision & (self.w_piece_board[ROOK] | self.w_piece_board[QUEEN]) != 0 {
-                //en passant is illegal
-                return;
-            }
-        }
-        let mve = from.trailing_zeros() as Move | ((to.trailing_zeros() as Move) << 6);
-        unsafe {
-            let ptr = legal_moves.0.get_unchecked_mut(legal_moves.1);
-            *ptr = mve | EN_PASSANT_CAPTURE;
-        }
-        legal_moves.1 += 1;
+        legal_moves.push(Move::new(
+            bitboard_to_square(from),
+            bitboard_to_square(to),
+            EN_PASSANT_CAPTURE,
+        ));
     }
 
     //updates the checkmask and pinned masks on the position and returns the amount of checks
     //these masks can then be used to generate legal moves instead of pseudo legal moves
     //legal move generation is about 5 times faster than pseudo legal move generation
     pub fn update_check_and_pinned(&mut self) -> u8 {
-        self.orthogonal_pin = 0;
-        self.diagonal_pin = 0;
-
-        let mut check_mask: Bitboard = EMPTY;
-        let mut num_checks = 0;
-
-        let friendly_piece_board;
-        let enemy_piece_board;
-        let friendly_board;
-        let enemy_board;
-        let king_bit_pos;
-
         if self.color_to_move == WHITE {
-            friendly_piece_board = &self.w_piece_board;
-            enemy_piece_board = &self.b_piece_board;
-            friendly_board = &self.w_board;
-            enemy_board = &self.b_board;
-            king_bit_pos = friendly_piece_board[KING];
-            check_mask |= (w_pawn_capture_mask(&king_bit_pos) & enemy_piece_board[PAWN]);
+            self.update_check_and_pinned_for::<White>()
         } else {
-            friendly_piece_board = &self.b_piece_board;
-            enemy_piece_board = &self.w_piece_board;
-            friendly_board = &self.b_board;
-            enemy_board = &self.w_board;
-            king_bit_pos = friendly_piece_board[KING];
-            check_mask |= (b_pawn_capture_mask(&king_bit_pos) & enemy_piece_board[PAWN]);
+            self.update_check_and_pinned_for::<Black>()
         }
+    }
 
-        let king_pos = bitboard_to_square(king_bit_pos) as usize;
-
-        unsafe {
-            check_mask |= (KNIGHT_MASK[king_pos] & enemy_piece_board[KNIGHT]);
-        }
+    fn update_check_and_pinned_for<S: Side>(&mut self) -> u8 {
+        self.orthogonal_pin = EMPTY;
+        self.diagonal_pin = EMPTY;
+
+        let friendly_board = S::board(self);
+        let enemy_board = S::Opponent::board(self);
+        let enemy_piece_board = S::Opponent::piece_board(self);
+        let orthogonal_sliders =
+            enemy_piece_board[PieceKind::Rook] | enemy_piece_board[PieceKind::Queen];
+        let diagonal_sliders =
+            enemy_piece_board[PieceKind::Bishop] | enemy_piece_board[PieceKind::Queen];
+        let king_bit_pos = S::piece_board(self)[PieceKind::King];
+        let king_pos = bitboard_to_square(king_bit_pos);
+
+        let mut check_mask = (S::pawn_attacks(&king_bit_pos) & enemy_piece_board[PieceKind::Pawn])
+            | (KNIGHT_MASK[king_pos] & enemy_piece_board[PieceKind::Knight]);
+        let mut num_checks = 0;
 
         //there will never be both a pawn and knight check so we only need to check the combined mask
-        if check_mask != 0 {
+        if check_mask != EMPTY {
             num_checks += 1;
         }
 
-        let mut ptr = king_bit_pos;
-        let mut friendly_pieces = 0;
-        let mut maybe_sliding_check: Bitboard = EMPTY;
-        let mut maybe_pin: Bitboard = EMPTY;
-
-        //up
-        while ptr & RANK[7] == 0 {
-            ptr <<= 8;
-            if friendly_pieces == 0 {
-                maybe_sliding_check |= ptr;
-            }
-            maybe_pin |= ptr;
-
-            if ptr & friendly_board != 0 {
-                friendly_pieces += 1;
-                if friendly_pieces == 2 {
-                    break;
-                }
-            } else if ptr & enemy_board != 0 {
-                if ptr & (enemy_piece_board[ROOK] | enemy_piece_board[QUEEN]) != 0 {
-                    if friendly_pieces == 0 {
-                        check_mask |= maybe_sliding_check;
-                        num_checks += 1;
-                        break;
-                    } else if friendly_pieces == 1 {
-                        self.orthogonal_pin |= maybe_pin;
-                    }
-                } else {
-                    break;
-                }
+        //the same ray walk for every direction, each one is compiled with its own constant shift
+        let orthogonal_rays = [
+            walk_ray::<8>(
+                king_bit_pos,
+                RANK[7],
+                friendly_board,
+                enemy_board,
+                orthogonal_sliders,
+            ),
+            walk_ray::<-8>(
+                king_bit_pos,
+                RANK[0],
+                friendly_board,
+                enemy_board,
+                orthogonal_sliders,
+            ),
+            walk_ray::<1>(
+                king_bit_pos,
+                FILE[0],
+                friendly_board,
+                enemy_board,
+                orthogonal_sliders,
+            ),
+            walk_ray::<-1>(
+                king_bit_pos,
+                FILE[7],
+                friendly_board,
+                enemy_board,
+                orthogonal_sliders,
+            ),
+        ];
+        let diagonal_rays = [
+            walk_ray::<7>(
+                king_bit_pos,
+                RANK[7] | FILE[7],
+                friendly_board,
+                enemy_board,
+                diagonal_sliders,
+            ),
+            walk_ray::<9>(
+                king_bit_pos,
+                RANK[7] | FILE[0],
+                friendly_board,
+                enemy_board,
+                diagonal_sliders,
+            ),
+            walk_ray::<-9>(
+                king_bit_pos,
+                RANK[0] | FILE[7],
+                friendly_board,
+                enemy_board,
+                diagonal_sliders,
+            ),
+            walk_ray::<-7>(
+                king_bit_pos,
+                RANK[0] | FILE[0],
+                friendly_board,
+                enemy_board,
+                diagonal_sliders,
+            ),
+        ];
+        for (check, pin) in orthogonal_rays {
+            if check != EMPTY {
+                check_mask |= check;
+                num_checks += 1;
             }
+            self.orthogonal_pin |= pin;
         }
-        ptr = king_bit_pos;
-        friendly_pieces = 0;
-        maybe_sliding_check = EMPTY;
-        maybe_pin = EMPTY;
-        //down
-        while ptr & RANK[0] == 0 {
-            ptr >>= 8;
-            if friendly_pieces == 0 {
-                maybe_sliding_check |= ptr;
-            }
-            maybe_pin |= ptr;
-
-            if ptr & friendly_board != 0 {
-                friendly_pieces += 1;
-                if friendly_pieces == 2 {
-                    break;
-                }
-            } else if ptr & enemy_board != 0 {
-                if ptr & (enemy_piece_board[ROOK] | enemy_piece_board[QUEEN]) != 0 {
-                    if friendly_pieces == 0 {
-                        check_mask |= maybe_sliding_check;
-                        num_checks += 1;
-                        break;
-                    } else if friendly_pieces == 1 {
-                        self.orthogonal_pin |= maybe_pin;
-                    }
-                } else {
-                    break;
-                }
+        for (check, pin) in diagonal_rays {
+            if check != EMPTY {
+                check_mask |= check;
+                num_checks += 1;
             }
+            self.diagonal_pin |= pin;
         }
-        ptr = king_bit_pos;
-        friendly_pieces = 0;
-        maybe_sliding_check = EMPTY;
-        maybe_pin = EMPTY;
-        //left
-        while ptr & FILE[0] == 0 {
-            ptr <<= 1;
-            if friendly_pieces == 0 {
-                maybe_sliding_check |= ptr;
-            }
-            maybe_pin |= ptr;
-
-            if ptr & friendly_board != 0 {
-                friendly_pieces += 1;
-                if friendly_pieces == 2 {
-                    break;
-                }
-            } else if ptr & enemy_board != 0 {
-                if ptr & (enemy_piece_board[ROOK] | enemy_piece_board[QUEEN]) != 0 {
-                    if friendly_pieces == 0 {
-                        check_mask |= maybe_sliding_check;
-                        num_checks += 1;
-                        break;
-                    } else if friendly_pieces == 1 {
-                        self.orthogonal_pin |= maybe_pin;
-                    }
-                } else {
-                    break;
-                }
-            }
+        if check_mask == EMPTY {
+            self.checked_squares = !check_mask;
+        } else {
+            self.checked_squares = check_mask;
         }
-        ptr = king_bit_pos;
-        friendly_pieces = 0;
-        maybe_sliding_check = EMPTY;
-        maybe_pin = EMPTY;
-        //right
-        while ptr & FILE[7] == 0 {
-            ptr >>= 1;
-            if friendly_pieces == 0 {
-                maybe_sliding_check |= ptr;
-            }
-            maybe_pin |= ptr;
+        num_checks
+    }
 
-            if ptr & friendly_board != 0 {
-                friendly_pieces += 1;
-                if friendly_pieces == 2 {
-                    break;
-                }
-            } else if ptr & enemy_board != 0 {
-                if ptr & (enemy_piece_board[ROOK] | enemy_piece_board[QUEEN]) != 0 {
-                    if friendly_pieces == 0 {
-                        check_mask |= maybe_sliding_check;
-                        num_checks += 1;
-                        break;
-                    } else if friendly_pieces == 1 {
-                        self.orthogonal_pin |= maybe_pin;
-                    }
-                } else {
-                    break;
-                }
-            }
+    //whether a legal move of the side S checks the enemy king
+    //the board is updated as if the move was made and the king is checked if any friendly piece
+    //on its new square or any slider the move uncovered attacks it
+    pub fn gives_check_for<S: Side>(&self, mve: Move) -> bool {
+        let from = mve.from();
+        let to = mve.to();
+        let flag = mve.flag();
+        let enemy_king = S::Opponent::piece_board(self)[PieceKind::King];
+        let enemy_king_square = bitboard_to_square(enemy_king);
+
+        let mut blocker_board =
+            (self.blocker_board ^ square_to_bitboard(from)) | square_to_bitboard(to);
+        let mut rooks = (S::piece_board(self)[PieceKind::Rook]
+            | S::piece_board(self)[PieceKind::Queen])
+            & !square_to_bitboard(from);
+        let mut bishops = (S::piece_board(self)[PieceKind::Bishop]
+            | S::piece_board(self)[PieceKind::Queen])
+            & !square_to_bitboard(from);
+
+        if flag == EN_PASSANT_CAPTURE {
+            blocker_board ^= S::Opponent::forward(square_to_bitboard(to), 1);
+        } else if mve.is_castling() {
+            //the move goes to the square of the castling rook
+            let right = S::castling_right(mve);
+            let rook_to = square_to_bitboard(self.castling_rook_to[right]);
+            blocker_board =
+                (self.blocker_board & !square_to_bitboard(from) & !square_to_bitboard(to))
+                    | square_to_bitboard(self.castling_king_to[right])
+                    | rook_to;
+            rooks = (rooks & !square_to_bitboard(to)) | rook_to;
         }
-        ptr = king_bit_pos;
-        friendly_pieces = 0;
-        maybe_sliding_check = EMPTY;
-        maybe_pin = EMPTY;
-
-        //up right
-        while ptr & (RANK[7] | FILE[7]) == 0 {
-            ptr <<= 7;
-            if friendly_pieces == 0 {
-                maybe_sliding_check |= ptr;
-            }
-            maybe_pin |= ptr;
 
-            if ptr & friendly_board != 0 {
-                friendly_pieces += 1;
-                if friendly_pieces == 2 {
-                    break;
-                }
-            } else if ptr & enemy_board != 0 {
-                if ptr & (enemy_piece_board[BISHOP] | enemy_piece_board[QUEEN]) != 0 {
-                    if friendly_pieces == 0 {
-                        check_mask |= maybe_sliding_check;
-                        num_checks += 1;
-                        break;
-                    } else if friendly_pieces == 1 {
-                        self.diagonal_pin |= maybe_pin;
-                    }
-                } else {
-                    break;
-                }
-            }
-        }
-        ptr = king_bit_pos;
-        friendly_pieces = 0;
-        maybe_sliding_check = EMPTY;
-        maybe_pin = EMPTY;
-        //up left
-        while ptr & (RANK[7] | FILE[0]) == 0 {
-            ptr <<= 9;
-            if friendly_pieces == 0 {
-                maybe_sliding_check |= ptr;
+        let piece = mve.promotion().or(self.pieces[from]);
+        let direct_check = match piece {
+            Some(PieceKind::Pawn) => S::pawn_attacks(&square_to_bitboard(to)) & enemy_king != EMPTY,
+            Some(PieceKind::Knight) => KNIGHT_MASK[to] & enemy_king != EMPTY,
+            Some(PieceKind::Bishop) => {
+                bishops |= square_to_bitboard(to);
+                false
             }
-            maybe_pin |= ptr;
-
-            if ptr & friendly_board != 0 {
-                friendly_pieces += 1;
-                if friendly_pieces == 2 {
-                    break;
-                }
-            } else if ptr & enemy_board != 0 {
-                if ptr & (enemy_piece_board[BISHOP] | enemy_piece_board[QUEEN]) != 0 {
-                    if friendly_pieces == 0 {
-                        check_mask |= maybe_sliding_check;
-                        num_checks += 1;
-                        break;
-                    } else if friendly_pieces == 1 {
-                        self.diagonal_pin |= maybe_pin;
-                    }
-                } else {
-                    break;
-                }
+            Some(PieceKind::Rook) => {
+                rooks |= square_to_bitboard(to);
+                false
             }
-        }
-        ptr = king_bit_pos;
-        friendly_pieces = 0;
-        maybe_sliding_check = EMPTY;
-        maybe_pin = EMPTY;
-        //down right
-        while ptr & (RANK[0] | FILE[7]) == 0 {
-            ptr >>= 9;
-            if friendly_pieces == 0 {
-                maybe_sliding_check |= ptr;
+            Some(PieceKind::Queen) => {
+                bishops |= square_to_bitboard(to);
+                rooks |= square_to_bitboard(to);
+                false
             }
-            maybe_pin |= ptr;
+            _ => false,
+        };
+        direct_check
+            || self.seen_by_rook_custom_blocker(enemy_king_square, blocker_board) & rooks != EMPTY
+            || self.seen_by_bishop_custom_blocker(enemy_king_square, blocker_board) & bishops
+                != EMPTY
+    }
 
-            if ptr & friendly_board != 0 {
-                friendly_pieces += 1;
-                if friendly_pieces == 2 {
-                    break;
-                }
-            } else if ptr & enemy_board != 0 {
-                if ptr & (enemy_piece_board[BISHOP] | enemy_piece_board[QUEEN]) != 0 {
-                    if friendly_pieces == 0 {
-                        check_mask |= maybe_sliding_check;
-                        num_checks += 1;
-                        break;
-                    } else if friendly_pieces == 1 {
-                        self.diagonal_pin |= maybe_pin;
-                    }
-                } else {
-                    break;
-                }
-            }
+    //all squares seen by a color
+    fn seen_by<S: Side>(&self) -> Bitboard {
+        let mut seen = self.seen_by_pawns::<S>()
+            | KING_MASK[bitboard_to_square(S::piece_board(self)[PieceKind::King])];
+        for knight_pos in S::piece_board(self)[PieceKind::Knight] {
+            seen |= KNIGHT_MASK[knight_pos];
         }
-        ptr = king_bit_pos;
-        friendly_pieces = 0;
-        maybe_sliding_check = EMPTY;
-        maybe_pin = EMPTY;
-        //down left
-        while ptr & (RANK[0] | FILE[0]) == 0 {
-            ptr >>= 7;
-            if friendly_pieces == 0 {
-                maybe_sliding_check |= ptr;
-            }
-            maybe_pin |= ptr;
+        //sliding pieces can see through the friendly king
+        let blocker_without_king =
+            self.blocker_board ^ S::Opponent::piece_board(self)[PieceKind::King];
 
-            if ptr & friendly_board != 0 {
-                friendly_pieces += 1;
-                if friendly_pieces == 2 {
-                    break;
-                }
-            } else if ptr & enemy_board != 0 {
-                if ptr & (enemy_piece_board[BISHOP] | enemy_piece_board[QUEEN]) != 0 {
-                    if friendly_pieces == 0 {
-                        check_mask |= maybe_sliding_check;
-                        num_checks += 1;
-                        break;
-                    } else if friendly_pieces == 1 {
-                        self.diagonal_pin |= maybe_pin;
-                    }
-                } else {
-                    break;
-                }
-            }
+        let rooks = S::piece_board(self)[PieceKind::Rook] | S::piece_board(self)[PieceKind::Queen];
+        for rook_pos in rooks {
+            seen |= self.seen_by_rook_custom_blocker(rook_pos, blocker_without_king);
         }
-        if check_mask == EMPTY {
-            self.checked_squares = !check_mask;
-        } else {
-            self.checked_squares = check_mask;
+        let bishops =
+            S::piece_board(self)[PieceKind::Bishop] | S::piece_board(self)[PieceKind::Queen];
+        for bishop_pos in bishops {
+            seen |= self.seen_by_bishop_custom_blocker(bishop_pos, blocker_without_king);
         }
-        num_checks
+        seen
     }
+}
 
-    fn seen_by_white(&self) -> Bitboard {
-        unsafe {
-            let mut seen = self.seen_by_w_pawns()
-                | KING_MASK[self.w_piece_board[KING].trailing_zeros() as usize];
-            let mut knights = self.w_piece_board[KNIGHT];
-            while knights != 0 {
-                seen |= KNIGHT_MASK[bitboard_to_square(knights & !(knights - 1)) as usize];
-                knights &= knights - 1; //removing the last bit
-            }
-            //sliding pieces can see through the friendly king
-            let blocker_without_king = self.blocker_board ^ self.b_piece_board[KING];
-
-            let mut rooks = self.w_piece_board[ROOK] | self.w_piece_board[QUEEN];
-            while rooks != 0 {
-                seen |= self.seen_by_rook_custom_blocker(
-                    bitboard_to_square(rooks & !(rooks - 1)),
-                    blocker_without_king,
-                );
-                rooks &= rooks - 1; //removing the last bit
-            }
-            let mut bishops = self.w_piece_board[BISHOP] | self.w_piece_board[QUEEN];
-            while bishops != 0 {
-                seen |= self.seen_by_bishop_custom_blocker(
-                    bitboard_to_square(bishops & !(bishops - 1)),
-                    blocker_without_king,
-                );
-                bishops &= bishops - 1; //removing the last bit
-            }
-            seen
+//walks a ray from the king one SHIFT at a time, positive shifts go to the left, until it reaches the
+//edge or passes two friendly pieces. an enemy slider of the ray checks the king if no friendly piece
+//is in between and pins the friendly piece if there is exactly one
+//returns the squares up to the checking slider and the squares up to the pinning slider
+#[inline(always)]
+fn walk_ray<const SHIFT: i8>(
+    king: Bitboard,
+    edge: Bitboard,
+    friendly_board: Bitboard,
+    enemy_board: Bitboard,
+    sliders: Bitboard,
+) -> (Bitboard, Bitboard) {
+    let mut ptr = king;
+    let mut friendly_pieces = 0;
+    let mut maybe_sliding_check: Bitboard = EMPTY;
+    let mut maybe_pin: Bitboard = EMPTY;
+    let mut pin: Bitboard = EMPTY;
+
+    while ptr & edge == EMPTY {
+        ptr = if SHIFT > 0 {
+            ptr << SHIFT as u32
+        } else {
+            ptr >> -SHIFT as u32
+        };
+        if friendly_pieces == 0 {
+            maybe_sliding_check |= ptr;
         }
-    }
+        maybe_pin |= ptr;
 
-    fn seen_by_black(&self) -> Bitboard {
-        unsafe {
-            let mut seen = self.seen_by_b_pawns()
-                | KING_MASK[bitboard_to_square(self.b_piece_board[KING]) as usize];
-            let mut knights = self.b_piece_board[KNIGHT];
-            while knights != 0 {
-                seen |= KNIGHT_MASK[bitboard_to_square(knights & !(knights - 1)) as usize];
-                knights &= knights - 1; //removing the last bit
+        if ptr & friendly_board != EMPTY {
+            friendly_pieces += 1;
+            if friendly_pieces == 2 {
+                break;
             }
-            //sliding pieces can see through the friendly king
-            let blocker_without_king = self.blocker_board ^ self.w_piece_board[KING];
-            let mut rooks = self.b_piece_board[ROOK] | self.b_piece_board[QUEEN];
-            while rooks != 0 {
-                seen |= self.seen_by_rook_custom_blocker(
-                    bitboard_to_square(rooks & !(rooks - 1)),
-                    blocker_without_king,
-                );
-                rooks &= rooks - 1; //removing the last bit
+        } else if ptr & enemy_board != EMPTY {
+            if ptr & sliders == EMPTY {
+                break;
             }
-            let mut bishops = self.b_piece_board[BISHOP] | self.b_piece_board[QUEEN];
-            while bishops != 0 {
-                seen |= self.seen_by_bishop_custom_blocker(
-                    bitboard_to_square(bishops & !(bishops - 1)),
-                    blocker_without_king,
-                );
-                bishops &= bishops - 1; //removing the last bit
+            if friendly_pieces == 0 {
+                return (maybe_sliding_check, EMPTY);
             }
-            seen
+            pin |= maybe_pin;
         }
     }
+    (EMPTY, pin)
+}
 
-    //quiescence_search only looks at captures so lets make a modified legal_move function for only finding captures
-    //returns array with legal captures and the amount of legal moves
-    pub fn legal_captures(&mut self) -> ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize) {
-        let mut legal_moves: ([Move; MAX_NUMBER_OF_LEGAL_MOVES], usize) =
-            ([0; MAX_NUMBER_OF_LEGAL_MOVES], 0);
-
-        let num_checks = self.update_check_and_pinned();
-
-        //If the king is checked twice, only king moves can be legal, so let not check for anything else
-        if num_checks == 2 {
-            if self.color_to_move == WHITE {
-                //To get legal king moves we must know which squares are seen by the opponent
-                //Calculating this at once is faster compared to looking for attackers for every square
-                let seen_by_opponent = self.seen_by_black();
-                let king_pos = bitboard_to_square(self.w_piece_board[KING]);
-                let king_moves = self.w_king_move(king_pos) & !seen_by_opponent;
-                add_capture_moves_from_bitboard(
-                    king_pos,
-                    king_moves & self.b_board,
-                    &mut legal_moves,
-                );
-                if legal_moves.1 == 0 {
-                    self.result = Result::Checkmate;
-                }
-                legal_moves
-            } else {
-                //To get legal king moves we must know which squares are seen by the opponent
-                //Calculating this at once is faster compared to looking for attackers for every square
-                let seen_by_opponent = self.seen_by_white();
-                let king_pos = bitboard_to_square(self.b_piece_board[KING]);
-                let king_moves = self.b_king_move(king_pos) & !seen_by_opponent;
-                add_capture_moves_from_bitboard(
-                    king_pos,
-                    king_moves & self.w_board,
-                    &mut legal_moves,
-                );
-                if legal_moves.1 == 0 {
-                    self.result = Result::Checkmate;
-                }
-                legal_moves
-            }
-        } else {
-            //1 or 0 checks
-            if self.color_to_move == WHITE {
-                //Pinned knights can never move
-                let mut knights =
-                    self.w_piece_board[KNIGHT] & !(self.orthogonal_pin | self.diagonal_pin);
-                while knights != 0 {
-                    let knight_pos: Square = bitboard_to_square(knights & !(knights - 1)); //getting the last bit
-                    knights &= knights - 1; //removing the last bit
-                    let moves = self.w_knight_move(knight_pos);
-                    add_capture_moves_from_bitboard(
-                        knight_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                }
-                //Queens are handled like a rook and a bishop on the same square
-
-                //Diagonally pinned rooks can never move
-                let rooks =
-                    (self.w_piece_board[ROOK] | self.w_piece_board[QUEEN]) & !self.diagonal_pin;
-                //Orthogonally pinned bishops can never move
-                let bishops =
-                    (self.w_piece_board[BISHOP] | self.w_piece_board[QUEEN]) & !self.orthogonal_pin;
-
-                //Orthogonally pinned rook moves.
-                let mut pinned_rooks = rooks & self.orthogonal_pin;
-                while pinned_rooks != 0 {
-                    let rook_pos: Square = bitboard_to_square(pinned_rooks & !(pinned_rooks - 1)); //getting the last bit
-                    pinned_rooks &= pinned_rooks - 1; //removing the last bit
-                    let moves = self.w_pinned_rook_moves(rook_pos);
-                    add_capture_moves_from_bitboard(
-                        rook_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                }
-
-                //Free rook moves.
-                let mut free_rooks = rooks & !self.orthogonal_pin;
-                while free_rooks != 0 {
-                    let rook_pos: Square = bitboard_to_square(free_rooks & !(free_rooks - 1)); //getting the last bit
-                    free_rooks &= free_rooks - 1; //removing the last bit
-                    let moves = self.w_rook_moves(rook_pos);
-                    add_capture_moves_from_bitboard(
-                        rook_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                }
-
-                //Diagonally pinned bishop moves.
-                let mut pinned_bishops = bishops & self.diagonal_pin;
-                while pinned_bishops != 0 {
-                    let bishop_pos: Square =
-                        bitboard_to_square(pinned_bishops & !(pinned_bishops - 1)); //getting the last bit
-                    pinned_bishops &= pinned_bishops - 1; //removing the last bit
-                    let moves = self.w_pinned_bishop_moves(bishop_pos);
-                    add_capture_moves_from_bitboard(
-                        bishop_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                }
-
-                //Free bishop moves.
-                let mut free_bishops = bishops & !self.diagonal_pin;
-                while free_bishops != 0 {
-                    let bishop_pos: Square = bitboard_to_square(free_bishops & !(free_bishops - 1)); //getting the last bit
-                    free_bishops &= free_bishops - 1; //removing the last bit
-                    let moves = self.w_bishop_moves(bishop_pos);
-                    add_capture_moves_from_bitboard(
-                        bishop_pos,
-                        moves & self.b_board,
-                        &mut legal_moves,
-                    );
-                }
-
-                let pawns = self.w_piece_board[PAWN];
-                //Orthogonally pinned pawns can never capture
-                let capturing_pawns = pawns & !self.orthogonal_pin;
-
-                //Diagonally pinned capturers
-                let mut pinned_capturers = capturing_pawns & self.diagonal_pin;
-                while pinned_capturers != 0 {
-                    let pawn_pos: Bitboard = pinned_capturers & !(pinned_capturers - 1); //getting the last bit
-                    pinned_capturers &= pinned_capturers - 1; //removing the last bit
-                    let moves = self.w_pinned_pawn_capture_mask(&pawn_pos);
-                    add_capture_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[7],
-                        &mut legal_moves,
-                    );
-                    add_capture_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[7],
-                        &mut legal_moves,
-                    );
-                }
-
-                //Free capturers
-                let mut capturers = capturing_pawns & !self.diagonal_pin;
-                while capturers != 0 {
-                    let pawn_pos: Bitboard = capturers & !(capturers - 1); //getting the last bit
-                    capturers &= capturers - 1; //removing the last bit
-                    let moves = self.w_pawn_capture_mask(&pawn_pos);
-                    add_capture_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[7],
-                        &mut legal_moves,
-                    );
-                    add_capture_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[7],
-                        &mut legal_moves,
-                    );
-                }
-                //The best way to find the candiates is to use the enemy capture mask from the en passant target square
-                let en_passant_pawns =
-                    capturing_pawns & b_pawn_capture_mask(&self.en_passant_target_square);
-
-                //Diagonally pinned en passant
-                let mut pinned_en_passant = en_passant_pawns & self.diagonal_pin;
-                while pinned_en_passant != 0 {
-                    let pawn_pos: Bitboard = pinned_en_passant & !(pinned_en_passant - 1); //getting the last bit
-                    pinned_en_passant &= pinned_en_passant - 1; //removing the last bit
-                    let moves = self.w_pinned_pawn_en_passant_mask(&pawn_pos);
-                    self.add_w_en_passant_moves(pawn_pos, moves, &mut legal_moves);
-                }
-
-                //En passant
-                let mut en_passant = en_passant_pawns & !self.diagonal_pin;
-                while en_passant != 0 {
-                    let pawn_pos: Bitboard = en_passant & !(en_passant - 1); //getting the last bit
-                    en_passant &= en_passant - 1; //removing the last bit
-                    let moves = self.w_pawn_en_passant_mask(&pawn_pos);
-                    self.add_w_en_passant_moves(pawn_pos, moves, &mut legal_moves);
-                }
-
-                //To get legal king moves we must know which squares are seen by the opponent
-                //Calculating this at once is faster compared to looking for attackers for every square
-                let seen_by_opponent = self.seen_by_black();
-                let king_pos = bitboard_to_square(self.w_piece_board[KING]);
-                let king_moves = self.w_king_move(king_pos) & !seen_by_opponent;
-                add_capture_moves_from_bitboard(
-                    king_pos,
-                    king_moves & self.b_board,
-                    &mut legal_moves,
-                );
-                if legal_moves.1 == 0 {
-                    self.result = if num_checks == 0 {
-                        Result::Draw
-                    } else {
-                        Result::Checkmate
-                    }
-                }
-                legal_moves
-            } else {
-                //Pinned knights can never move
-                let mut knights =
-                    self.b_piece_board[KNIGHT] & !(self.orthogonal_pin | self.diagonal_pin);
-                while knights != 0 {
-                    let knight_pos: Square = bitboard_to_square(knights & !(knights - 1)); //getting the last bit
-                    knights &= knights - 1; //removing the last bit
-                    let moves = self.b_knight_move(knight_pos);
-                    add_capture_moves_from_bitboard(
-                        knight_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                }
-                //Queens are handled like a rook and a bishop on the same square
-
-                //Diagonally pinned rooks can never move
-                let rooks =
-                    (self.b_piece_board[ROOK] | self.b_piece_board[QUEEN]) & !self.diagonal_pin;
-                //Orthogonally pinned bishops can never move
-                let bishops =
-                    (self.b_piece_board[BISHOP] | self.b_piece_board[QUEEN]) & !self.orthogonal_pin;
-
-                //Orthogonally pinned rook moves.
-                let mut pinned_rooks = rooks & self.orthogonal_pin;
-                while pinned_rooks != 0 {
-                    let rook_pos: Square = bitboard_to_square(pinned_rooks & !(pinned_rooks - 1)); //getting the last bit
-                    pinned_rooks &= pinned_rooks - 1; //removing the last bit
-                    let moves = self.b_pinned_rook_moves(rook_pos);
-                    add_capture_moves_from_bitboard(
-                        rook_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                }
-
-                //Free rook moves.
-                let mut free_rooks = rooks & !self.orthogonal_pin;
-                while free_rooks != 0 {
-                    let rook_pos: Square = bitboard_to_square(free_rooks & !(free_rooks - 1)); //getting the last bit
-                    free_rooks &= free_rooks - 1; //removing the last bit
-                    let moves = self.b_rook_moves(rook_pos);
-                    add_capture_moves_from_bitboard(
-                        rook_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                }
-
-                //Diagonally pinned bishop moves.
-                let mut pinned_bishops = bishops & self.diagonal_pin;
-                while pinned_bishops != 0 {
-                    let bishop_pos: Square =
-                        bitboard_to_square(pinned_bishops & !(pinned_bishops - 1)); //getting the last bit
-                    pinned_bishops &= pinned_bishops - 1; //removing the last bit
-                    let moves = self.b_pinned_bishop_moves(bishop_pos);
-                    add_capture_moves_from_bitboard(
-                        bishop_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                }
-
-                //Free bishop moves.
-                let mut free_bishops = bishops & !self.diagonal_pin;
-                while free_bishops != 0 {
-                    let bishop_pos: Square = bitboard_to_square(free_bishops & !(free_bishops - 1)); //getting the last bit
-                    free_bishops &= free_bishops - 1; //removing the last bit
-                    let moves = self.b_bishop_moves(bishop_pos);
-                    add_capture_moves_from_bitboard(
-                        bishop_pos,
-                        moves & self.w_board,
-                        &mut legal_moves,
-                    );
-                }
-
-                let pawns = self.b_piece_board[PAWN];
-                //Orthogonally pinned pawns can never capture
-                let capturing_pawns = pawns & !self.orthogonal_pin;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mve::algebraic_to_move, perft::TEST_FENS};
 
-                //Diagonally pinned capturers
-                let mut pinned_capturers = capturing_pawns & self.diagonal_pin;
-                while pinned_capturers != 0 {
-                    let pawn_pos: Bitboard = pinned_capturers & !(pinned_capturers - 1); //getting the last bit
-                    pinned_capturers &= pinned_capturers - 1; //removing the last bit
-                    let moves = self.b_pinned_pawn_capture_mask(&pawn_pos);
-                    add_capture_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[0],
-                        &mut legal_moves,
-                    );
-                    add_capture_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[0],
-                        &mut legal_moves,
-                    );
-                }
+    fn sorted(moves: &MoveList) -> Vec<Move> {
+        let mut moves = moves.to_vec();
+        moves.sort();
+        moves
+    }
 
-                //Free capturers
-                let mut capturers = capturing_pawns & !self.diagonal_pin;
-                while capturers != 0 {
-                    let pawn_pos: Bitboard = capturers & !(capturers - 1); //getting the last bit
-                    capturers &= capturers - 1; //removing the last bit
-                    let moves = self.b_pawn_capture_mask(&pawn_pos);
-                    add_capture_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & !RANK[0],
-                        &mut legal_moves,
-                    );
-                    add_capture_promotion_moves_from_bitboard(
-                        bitboard_to_square(pawn_pos),
-                        moves & RANK[0],
-                        &mut legal_moves,
-                    );
-                }
-                //The best way to find the candiates is to use the enemy capture mask from the en passant target square
-                let en_passant_pawns =
-                    capturing_pawns & w_pawn_capture_mask(&self.en_passant_target_square);
+    //checks the partition properties of the generators in every position of the tree
+    fn check_generators(pos: &mut Position, depth: u8) {
+        let moves = sorted(&pos.legal_moves());
+        let captures = sorted(&pos.legal_captures());
+        let quiets = sorted(&pos.legal_quiets());
+        let checks = sorted(&pos.legal_checks());
+        let evasions = sorted(&pos.legal_evasions());
+        let in_check = pos.update_check_and_pinned() != 0;
+
+        //captures and quiets are disjoint and together they are all legal moves
+        let mut union = [&captures[..], &quiets[..]].concat();
+        union.sort();
+        assert_eq!(moves, union, "{}", pos.fen());
+
+        //checks are exactly the quiet moves after which the opponent is in check
+        for mve in quiets.iter() {
+            let mut pos_clone = pos.clone();
+            pos_clone.make_move(*mve);
+            let gives_check = pos_clone.update_check_and_pinned() != 0;
+            assert_eq!(
+                gives_check,
+                checks.binary_search(mve).is_ok(),
+                "{} {}",
+                pos.fen(),
+                move_to_algebraic(*mve)
+            );
+        }
+        assert!(checks.iter().all(|mve| quiets.binary_search(mve).is_ok()));
 
-                //Diagonally pinned en passant
-                let mut pinned_en_passant = en_passant_pawns & self.diagonal_pin;
-                while pinned_en_passant != 0 {
-                    let pawn_pos: Bitboard = pinned_en_passant & !(pinned_en_passant - 1); //getting the last bit
-                    pinned_en_passant &= pinned_en_passant - 1; //removing the last bit
-                    let moves = self.b_pinned_pawn_en_passant_mask(&pawn_pos);
-                    self.add_b_en_passant_moves(pawn_pos, moves, &mut legal_moves);
-                }
+        //evasions are all legal moves in check and nothing otherwise
+        if in_check {
+            assert_eq!(moves, evasions, "{}", pos.fen());
+        } else {
+            assert!(evasions.is_empty(), "{}", pos.fen());
+        }
 
-                //En passant
-                let mut en_passant = en_passant_pawns & !self.diagonal_pin;
-                while en_passant != 0 {
-                    let pawn_pos: Bitboard = en_passant & !(en_passant - 1); //getting the last bit
-                    en_passant &= en_passant - 1; //removing the last bit
-                    let moves = self.b_pawn_en_passant_mask(&pawn_pos);
-                    self.add_b_en_passant_moves(pawn_pos, moves, &mut legal_moves);
-                }
+        if depth > 1 {
+            for mve in moves {
+                let mut pos_clone = pos.clone();
+                pos_clone.make_move(mve);
+                check_generators(&mut pos_clone, depth - 1);
+            }
+        }
+    }
 
-                //To get legal king moves we must know which squares are seen by the opponent
-                //Calculating this at once is faster compared to looking for attackers for every square
-                let seen_by_opponent = self.seen_by_white();
-                let king_pos = bitboard_to_square(self.b_piece_board[KING]);
-                let king_moves = self.b_king_move(king_pos) & !seen_by_opponent;
-                add_capture_moves_from_bitboard(
-                    king_pos,
-                    king_moves & self.w_board,
-                    &mut legal_moves,
-                );
+    #[test]
+    fn generators_partition_legal_moves() {
+        for fen in TEST_FENS {
+            let mut pos = Position::new();
+            pos.parse_fen(fen);
+            check_generators(&mut pos, 4);
+        }
 
-                if legal_moves.1 == 0 {
-                    self.result = if num_checks == 0 {
-                        Result::Draw
-                    } else {
-                        Result::Checkmate
-                    }
-                }
-                legal_moves
-            }
+        //castling with check and discovered checks by a knight
+        let mut pos = Position::new();
+        pos.parse_fen("5k2/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        assert!(sorted(&pos.legal_checks()).contains(&algebraic_to_move(&pos, "e1g1").unwrap()));
+        check_generators(&mut pos, 3);
+
+        let mut pos = Position::new();
+        pos.parse_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1");
+        assert_eq!(8, pos.legal_checks().len());
+        check_generators(&mut pos, 3);
+
+        //discovered checks by a pawn push and by a king move and promotions checking through the
+        //square the pawn leaves
+        for fen in [
+            "8/8/8/8/R2P3k/8/8/K7 w - - 0 1",
+            "4k3/8/8/8/4K3/8/8/4R3 w - - 0 1",
+            "8/6P1/8/8/8/6k1/8/K7 w - - 0 1",
+        ] {
+            let mut pos = Position::new();
+            pos.parse_fen(fen);
+            check_generators(&mut pos, 3);
         }
     }
 }
@@ -0,0 +1,31 @@
+#![allow(unused)]
+pub mod attacks;
+pub mod bitboard;
+pub mod board;
+pub mod cli;
+pub mod epd;
+pub mod eval;
+pub mod legalmoves;
+pub mod magic;
+pub mod makemove;
+pub mod masks;
+pub mod moveinfo;
+pub mod movelist;
+pub mod moveorder;
+pub mod movepicker;
+pub mod mve;
+pub mod options;
+pub mod perft;
+pub mod perftcompare;
+#[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+pub mod pext;
+pub mod pgn;
+pub mod piece;
+mod rand;
+pub mod san;
+pub mod search;
+pub mod searchbench;
+pub mod side;
+pub mod uci;
+pub mod ucigo;
+pub mod zobrist;
@@ -1,8 +1,8 @@
-use crate::board::{Bitboard, Square, FILE, RANK};
-use crate::masks::{
-    bishop_all_blockers_mask, bishop_mask, rook_all_blockers_mask, rook_mask,
-    BISHOP_ALL_BLOCKERS_MASK, ROOK_ALL_BLOCKERS_MASK,
-};
+use crate::bitboard::Bitboard;
+use crate::board::{Square, EMPTY};
+use crate::masks::{bishop_all_blockers_mask, bishop_mask, rook_all_blockers_mask, rook_mask};
+#[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+use crate::pext;
 use crate::rand::Wyrand;
 use Sliding::{Bishop, Rook};
 
@@ -14,115 +14,276 @@ pub enum Sliding {
     Bishop,
 }
 
-pub struct Magic {
-    pub rook_lookup: [[Bitboard; 4096]; 64],
-    pub rook_magic: [MagicNumber; 64],
-    pub bishop_lookup: [[Bitboard; 512]; 64],
-    pub bishop_magic: [MagicNumber; 64],
+//fancy magic bitboards, every square only uses as many lookup entries as it has blocker
+//configurations by shifting with its own amount of relevant blocker squares, all squares share
+//one lookup table and store where their part of the table starts
+//https://www.chessprogramming.org/Magic_Bitboards#Fancy
+#[derive(Copy, Clone, Debug)]
+pub struct MagicEntry {
+    pub mask: Bitboard,
+    pub magic: MagicNumber,
+    pub offset: u32,
+    pub shift: u8,
 }
 
-pub static mut MAGIC: Magic = Magic {
-    rook_lookup: [[0; 4096]; 64],
-    rook_magic: [0; 64],
-    bishop_lookup: [[0; 512]; 64],
-    bishop_magic: [0; 64],
-};
+impl MagicEntry {
+    //a magic index is the blocker board for the square multiplied with a magic number and then shifted by the amount of relevant blocker squares
+    //magic index = (blocker*magic number)>>(magic bitshift);
+    //move mask = lookup table [offset + magic index];
+    //https://www.chessprogramming.org/Magic_Bitboards
+    #[inline]
+    pub const fn index(&self, blocker_board: Bitboard) -> usize {
+        self.offset as usize
+            + ((blocker_board.0 & self.mask.0).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+//magic numbers found with find_magics, regenerate with "cargo run --release --bin find_magics"
+#[rustfmt::skip]
+pub const ROOK_MAGIC: [MagicNumber; 64] = [
+    0x0880014008601080, 0x0040001000200040, 0x108010000C802000, 0x018008001002800C,
+    0x0080020800440180, 0x0080018004000200, 0x01002F0002001084, 0x0180010000205880,
+    0x0000802080004000, 0x0000401000402000, 0x0040801001200A80, 0x0106004020100A00,
+    0x4002000821104600, 0x0082000200B00864, 0x0001000402000F00, 0x2042000492141045,
+    0x008008400241A000, 0x1802808040002000, 0x1401310040200100, 0x0088808008001002,
+    0x0000808004019800, 0x481881800A002400, 0x08040C0001104822, 0x8810020000804104,
+    0x0000802080004000, 0x8000A00440005001, 0x4000200380300281, 0x0000080080100080,
+    0x0044000480800800, 0x3002002200281410, 0x4001024400485009, 0x11CA040200038943,
+    0x00884004A0800081, 0x0811400082802001, 0x0010001180802000, 0xA002211001000900,
+    0x1000500501000800, 0x1000805400800201, 0x400A031004008802, 0x2004A400C2000091,
+    0x0004804006228000, 0x48108100C0050060, 0x81B1420083120024, 0x1A18008210028008,
+    0x0040510008010014, 0x0021000400790002, 0x0009011208640010, 0x1020828244020005,
+    0x4000208000400080, 0x0208806040050500, 0x1401310040200100, 0x0000080080100080,
+    0x0048000881140080, 0xD000402420300801, 0x012110A80D060C00, 0x0000840108804200,
+    0x0080004108201081, 0x4048400010208109, 0x080500200010C019, 0x280100C410002029,
+    0x8302011014200802, 0x084E001004055802, 0x0000100102080A84, 0x8100040080410022,
+];
+#[rustfmt::skip]
+pub const BISHOP_MAGIC: [MagicNumber; 64] = [
+    0x0803831004074024, 0x404410241C43800C, 0x10042B0C01021000, 0x0028208020105284,
+    0x000E211008014204, 0x830201100B281000, 0x0300411010101001, 0x0000808880904034,
+    0x0200102086041440, 0x0241841000AD0100, 0x0002420401002008, 0x9044082280200144,
+    0x1082040420000042, 0x0400010108400002, 0x0004204218A04802, 0x00190200840128B4,
+    0x8140042088494500, 0x411290E042040102, 0x0008000408001012, 0xA01800808200C000,
+    0x8500800400A00000, 0x2003040A00808400, 0x04034402080C4428, 0x20210009B4010401,
+    0x8002408810300A02, 0x14041100C2020801, 0x0104020004080814, 0x0040040008410020,
+    0x800900500100C000, 0x4280441002020200, 0xE001020000621004, 0xA000810042090CC0,
+    0x9008023000C92020, 0x02A2282090440940, 0x0000844040241400, 0x0200C048009C8200,
+    0x00200084000080A0, 0x0050014040020108, 0x14041100C2020801, 0x0088004500005900,
+    0x0408220814002080, 0x000E211008014204, 0x0801040022003401, 0x2000002018000300,
+    0x8800020822001010, 0x8602500200882202, 0x8602500200882202, 0x0004240052010040,
+    0x0300411010101001, 0x0241014110080840, 0x4201010284900106, 0xCA0D400520880842,
+    0x0210420843040002, 0x4280441002020200, 0x0210420843040002, 0x404410241C43800C,
+    0x0000808880904034, 0x00190200840128B4, 0x2101020440441002, 0x0811008000208823,
+    0x070010320D050400, 0x0000004090840EC0, 0x0200102086041440, 0x4060140108182088,
+];
+
+//the lookup tables are generated at compile time from the magic numbers above
+pub static ROOK_MAGICS: [MagicEntry; 64] = magic_entries(Rook);
+pub static BISHOP_MAGICS: [MagicEntry; 64] = magic_entries(Bishop);
+
+//the rook entries are stored first followed by the bishop entries
+pub const ROOK_LOOKUP_SIZE: usize = lookup_size(Rook);
+pub const LOOKUP_SIZE: usize = ROOK_LOOKUP_SIZE + lookup_size(Bishop);
+
+pub static SLIDER_LOOKUP: [Bitboard; LOOKUP_SIZE] = slider_lookup();
+
+//returns a bitboard with all squares of potential blockers, pieces on edges can never block
+pub const fn all_blockers_mask(piece: Sliding, square: Square) -> Bitboard {
+    match piece {
+        Rook => rook_all_blockers_mask(square),
+        Bishop => bishop_all_blockers_mask(square),
+    }
+}
+
+const fn slider_mask(piece: Sliding, square: Square, blocker_board: Bitboard) -> Bitboard {
+    match piece {
+        Rook => rook_mask(square.bitboard(), blocker_board),
+        Bishop => bishop_mask(square.bitboard(), blocker_board),
+    }
+}
+
+const fn lookup_size(piece: Sliding) -> usize {
+    let mut size = 0;
+    let mut square = 0;
+    while square < 64 {
+        size += 1 << all_blockers_mask(piece, Square::new(square)).popcount();
+        square += 1;
+    }
+    size
+}
+
+const fn magic_entries(piece: Sliding) -> [MagicEntry; 64] {
+    let (magics, mut offset) = match piece {
+        Rook => (ROOK_MAGIC, 0),
+        Bishop => (BISHOP_MAGIC, ROOK_LOOKUP_SIZE),
+    };
+    let mut entries = [MagicEntry {
+        mask: EMPTY,
+        magic: 0,
+        offset: 0,
+        shift: 0,
+    }; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mask = all_blockers_mask(piece, Square::new(square as u8));
+        let bits = mask.popcount();
+        entries[square] = MagicEntry {
+            mask,
+            magic: magics[square],
+            offset: offset as u32,
+            shift: 64 - bits as u8,
+        };
+        offset += 1 << bits;
+        square += 1;
+    }
+    entries
+}
+
+const fn slider_lookup() -> [Bitboard; LOOKUP_SIZE] {
+    let mut lookup = [EMPTY; LOOKUP_SIZE];
+    let pieces = [Rook, Bishop];
+    let mut i = 0;
+    while i < pieces.len() {
+        let entries = magic_entries(pieces[i]);
+        let mut square = 0;
+        while square < 64 {
+            let all_blockers_set = entries[square].mask;
+            let mut blocker_subset = EMPTY;
+            loop {
+                let index = entries[square].index(blocker_subset);
+                let move_mask = slider_mask(pieces[i], Square::new(square as u8), blocker_subset);
+                //a wrong magic number fails the build instead of creating wrong moves
+                if !lookup[index].is_empty() && lookup[index].0 != move_mask.0 {
+                    panic!("magic number collision");
+                }
+                lookup[index] = move_mask;
+                //Carry-Rippler
+                blocker_subset = blocker_subset.next_subset_of(all_blockers_set);
+                if blocker_subset.is_empty() {
+                    break;
+                }
+            }
+            square += 1;
+        }
+        i += 1;
+    }
+    lookup
+}
+
+//returns all squares seen by a rook on the square with the given blocker board
+#[inline]
+pub fn rook_attacks(square: Square, blocker_board: Bitboard) -> Bitboard {
+    #[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+    return pext::rook_attacks(square, blocker_board);
+    #[allow(unreachable_code)]
+    magic_rook_attacks(square, blocker_board)
+}
+
+//returns all squares seen by a bishop on the square with the given blocker board
+#[inline]
+pub fn bishop_attacks(square: Square, blocker_board: Bitboard) -> Bitboard {
+    #[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+    return pext::bishop_attacks(square, blocker_board);
+    #[allow(unreachable_code)]
+    magic_bishop_attacks(square, blocker_board)
+}
 
-pub const ROOK_MAGIC_SHIFT: Square = 52;
-pub const BISHOP_MAGIC_SHIFT: Square = 55;
+#[inline]
+pub fn magic_rook_attacks(square: Square, blocker_board: Bitboard) -> Bitboard {
+    SLIDER_LOOKUP[ROOK_MAGICS[square].index(blocker_board)]
+}
+
+#[inline]
+pub fn magic_bishop_attacks(square: Square, blocker_board: Bitboard) -> Bitboard {
+    SLIDER_LOOKUP[BISHOP_MAGICS[square].index(blocker_board)]
+}
+
+//the name of the slider lookup used, pext is only used when the "pext" feature is enabled
+//and the engine is compiled for cpus with bmi2
+pub fn slider_backend() -> &'static str {
+    if cfg!(all(
+        feature = "pext",
+        target_arch = "x86_64",
+        target_feature = "bmi2"
+    )) {
+        "pext"
+    } else {
+        "magic"
+    }
+}
 
-pub fn init_magic() {
-    for square in 0..64 as Square {
-        find_magic(Rook, square);
-        find_magic(Bishop, square);
+//searches for the rook and bishop magic numbers of every square
+//this is only used to generate the constants above, see src/bin/find_magics.rs
+pub fn find_magics() -> ([MagicNumber; 64], [MagicNumber; 64]) {
+    let mut rook_magic = [0; 64];
+    let mut bishop_magic = [0; 64];
+    for index in 0..64 {
+        let square = Square::new(index);
+        rook_magic[square] = find_magic(Rook, square);
+        bishop_magic[square] = find_magic(Bishop, square);
     }
+    (rook_magic, bishop_magic)
 }
 
-//finds a magic number for a square and updates MAGIC with the magic number and the lookup table for that square
-fn find_magic(piece: Sliding, square: Square) {
-    let mut rng = Wyrand(16113163697346267551); //best seed out of >100_000 random seeds (73292 magic candidates for 128 magics)
+//finds a magic number for a square
+pub fn find_magic(piece: Sliding, square: Square) -> MagicNumber {
+    let mut rng = Wyrand(16113163697346267551);
+    let all_blockers_set = all_blockers_mask(piece, square);
+    let mut lookup: Vec<Bitboard> = vec![EMPTY; 1 << all_blockers_set.popcount()];
 
     //looping through random numbers until a magic number is found
     loop {
         //rand & rand & rand to get a low amounts of 1s leads to better candidates
         let maybe_magic = rng.next() & rng.next() & rng.next();
-        let is_magic: bool = check_if_magic(piece, square, maybe_magic);
+        let is_magic: bool = check_if_magic(piece, square, maybe_magic, &mut lookup);
         if is_magic {
-            break;
+            return maybe_magic;
         }
     }
 }
 
 //checks if a number is magic by looking for hash conditions, magic number should have no
 //collisions as they should create a perfect hash function
-fn check_if_magic(piece: Sliding, square: Square, magic_candidate: MagicNumber) -> bool {
-    let all_blockers_set;
-    unsafe {
-        if piece == Rook {
-            MAGIC.rook_lookup[square as usize] = [0; 4096];
-        } else {
-            MAGIC.bishop_lookup[square as usize] = [0; 512];
-        }
-        all_blockers_set = if piece == Rook {
-            ROOK_ALL_BLOCKERS_MASK[square as usize]
-        } else {
-            BISHOP_ALL_BLOCKERS_MASK[square as usize]
-        };
-    }
+fn check_if_magic(
+    piece: Sliding,
+    square: Square,
+    magic_candidate: MagicNumber,
+    lookup: &mut [Bitboard],
+) -> bool {
+    lookup.fill(EMPTY);
+    let all_blockers_set = all_blockers_mask(piece, square);
+    let entry = MagicEntry {
+        mask: all_blockers_set,
+        magic: magic_candidate,
+        offset: 0,
+        shift: 64 - all_blockers_set.popcount() as u8,
+    };
 
-    let mut blocker_subset: Bitboard = 0;
+    let mut blocker_subset = EMPTY;
 
     //Carry-Rippler trick to enumerate all subsets in a set
     //https://www.chessprogramming.org/Traversing_Subsets_of_a_Set#All_Subsets_of_any_Set
     //the set is a mask containing all possible blocking squares
     //so the subsets will be all possible configurations of blocker boards
     loop {
-        let move_mask = if piece == Rook {
-            rook_mask(0b1u64 << square, blocker_subset)
-        } else {
-            bishop_mask(0b1u64 << square, blocker_subset)
-        };
-
-        //a magic index is the blocker board for the square multiplied with a magic number and then shifted by the amount of relevant blocker squares
-        //magic index = (blocker*magic number)>>(magic bitshift);
-        //move mask = lookup table [magic index];
-        //https://www.chessprogramming.org/Magic_Bitboards
-        //this is how the move mask later can be accessed from the lookup table
-        let magic_index = if piece == Rook {
-            blocker_subset.wrapping_mul(magic_candidate) >> ROOK_MAGIC_SHIFT
-        } else {
-            blocker_subset.wrapping_mul(magic_candidate) >> BISHOP_MAGIC_SHIFT
-        };
-        unsafe {
-            if piece == Rook {
-                if MAGIC.rook_lookup[square as usize][magic_index as usize] == 0 {
-                    MAGIC.rook_lookup[square as usize][magic_index as usize] = move_mask;
-                } else if MAGIC.rook_lookup[square as usize][magic_index as usize] != move_mask {
-                    //bad hash collision
-                    //this candidate is not magic!
-                    return false;
-                }
-            } else if MAGIC.bishop_lookup[square as usize][magic_index as usize] == 0 {
-                MAGIC.bishop_lookup[square as usize][magic_index as usize] = move_mask;
-            } else if MAGIC.bishop_lookup[square as usize][magic_index as usize] != move_mask {
-                //bad hash collision
-                //this candidate is not magic!
-                return false;
-            }
+        let move_mask = slider_mask(piece, square, blocker_subset);
+        let magic_index = entry.index(blocker_subset);
+        if lookup[magic_index] == EMPTY {
+            lookup[magic_index] = move_mask;
+        } else if lookup[magic_index] != move_mask {
+            //bad hash collision
+            //this candidate is not magic!
+            return false;
         }
 
         //Carry-Rippler
-        blocker_subset = blocker_subset.wrapping_sub(all_blockers_set) & all_blockers_set;
-        if blocker_subset == 0 {
+        blocker_subset = blocker_subset.next_subset_of(all_blockers_set);
+        if blocker_subset == EMPTY {
             break;
         }
     }
     //no bad hash collisions
     //this candidate is magic!
-    if piece == Rook {
-        unsafe { MAGIC.rook_magic[square as usize] = magic_candidate }
-    } else {
-        unsafe { MAGIC.bishop_magic[square as usize] = magic_candidate }
-    }
     true
 }
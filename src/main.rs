@@ -1,22 +1,8 @@
-#![allow(unused)]
-mod board;
-mod eval;
-mod init;
-mod legalmoves;
-mod magic;
-mod makemove;
-mod masks;
-mod moveorder;
-mod mve;
-mod perft;
-mod piece;
-mod rand;
-mod search;
-mod uci;
-mod ucigo;
+use std::{env, process::ExitCode};
 
-use uci::uci;
+use chessengine::cli::run;
 
-fn main() {
-    uci();
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    run(&args)
 }
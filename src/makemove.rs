@@ -1,79 +1,96 @@
 use crate::{
-    board::{square_to_bitboard, Position, BLACK, WHITE},
+    board::{square_to_bitboard, Color, Position, Square, BLACK, EMPTY, WHITE},
     mve::{
         move_to_algebraic, Move, BISHOP_PROMOTION, BISHOP_PROMOTION_CAPTURE, CAPTURE,
-        DOUBLE_PAWN_PUSH, EN_PASSANT_CAPTURE, KING_CASTLE, KNIGHT_PROMOTION,
-        KNIGHT_PROMOTION_CAPTURE, MOVE_FLAG, MOVE_FROM, MOVE_TO, PROMOTION, QUEEN_CASTLE,
-        QUEEN_PROMOTION, QUEEN_PROMOTION_CAPTURE, QUIET_MOVE, ROOK_PROMOTION,
-        ROOK_PROMOTION_CAPTURE,
+        DOUBLE_PAWN_PUSH, EN_PASSANT_CAPTURE, KNIGHT_PROMOTION, KNIGHT_PROMOTION_CAPTURE,
+        PROMOTION, QUEEN_CASTLE, QUEEN_PROMOTION, QUEEN_PROMOTION_CAPTURE, QUIET_MOVE,
+        ROOK_PROMOTION, ROOK_PROMOTION_CAPTURE,
     },
-    piece::{BISHOP, KING, KNIGHT, NONE, PAWN, QUEEN, ROOK},
+    piece::PieceKind,
+    zobrist::{piece_key, state_key, ZOBRIST},
 };
 
 impl Position {
     //plays a move on the current position
     pub fn make_move(&mut self, mve: Move) {
-        let from = mve & MOVE_FROM;
-        let to = (mve & MOVE_TO) >> 6;
-        let bit_from = square_to_bitboard(from as u8);
-        let bit_to = square_to_bitboard(to as u8);
+        let color = self.color_to_move;
+        //the castling rights and en passant square are hashed again after the move
+        let mut hash = self.hash ^ state_key(self) ^ ZOBRIST.black_to_move;
+        self.en_passant_target_square = EMPTY;
 
-        let move_flag = mve & MOVE_FLAG;
+        if mve.is_castling() {
+            hash ^= self.make_castling_move(color, mve);
+            self.half_move_clock += 1;
+        } else {
+            hash ^= self.make_piece_move(color, mve);
+        }
+        //full moves are incremented after black moves
+        if color == BLACK {
+            self.full_moves += 1;
+        }
+        self.blocker_board = self.w_board | self.b_board;
+        self.color_to_move = !self.color_to_move;
+        self.hash = hash ^ state_key(self);
+    }
+
+    //moves the piece of any move other than castling and returns the change of the hash
+    #[inline]
+    fn make_piece_move(&mut self, color: Color, mve: Move) -> u64 {
+        let from = mve.from();
+        let to = mve.to();
+        let bit_from = square_to_bitboard(from);
+        let bit_to = square_to_bitboard(to);
+
+        let move_flag = mve.flag();
 
         let bit_move = bit_from | bit_to;
 
         let mut do_not_reset_half_move: bool = true;
 
-        let piece;
-        let occupation;
-        unsafe {
-            let ptr = self.pieces.get_unchecked(from as usize);
-            piece = *ptr;
-            let ptr = self.pieces.get_unchecked(to as usize);
-            occupation = *ptr;
-        }
+        let piece = self.pieces[from].expect("no piece on the from square");
+        let occupation = self.pieces[to];
 
-        do_not_reset_half_move = piece != PAWN as u8;
+        do_not_reset_half_move = piece != PieceKind::Pawn;
 
-        self.en_passant_target_square = 0;
+        let mut hash = piece_key(color, piece, from) ^ piece_key(color, piece, to);
+        self.remove_piece_score(color, piece, from);
+        self.add_piece_score(color, piece, to);
 
-        if self.color_to_move == WHITE {
+        if color == WHITE {
             //updating castling rights
-            if piece == KING as u8 {
+            if piece == PieceKind::King {
                 (self.castling_rights[0], self.castling_rights[1]) = (false, false);
-            } else if piece == ROOK as u8 {
-                if from == 0 {
+            } else if piece == PieceKind::Rook {
+                if from == self.castling_rooks[0] {
                     self.castling_rights[0] = false;
-                } else if from == 7 {
+                } else if from == self.castling_rooks[1] {
                     self.castling_rights[1] = false;
                 }
             }
             //updating board
             self.w_board ^= bit_move;
-            unsafe {
-                let ptr = self.pieces.get_unchecked_mut(to as usize);
-                *ptr = piece;
-                let ptr = self.pieces.get_unchecked_mut(from as usize);
-                *ptr = NONE;
-                let ptr = self.w_piece_board.get_unchecked_mut(piece as usize);
-                *ptr ^= bit_move;
-            }
+            self.pieces[to] = Some(piece);
+            self.pieces[from] = None;
+            self.w_piece_board[piece] ^= bit_move;
 
-            if move_flag & CAPTURE == CAPTURE {
+            if mve.is_capture() {
                 do_not_reset_half_move = false;
                 if move_flag == EN_PASSANT_CAPTURE {
-                    self.b_board ^= bit_to >> 8;
-                    self.b_piece_board[PAWN] ^= bit_to >> 8;
-                    self.pieces[to as usize - 8] = NONE;
-                } else {
+                    //the captured pawn is behind the square the pawn moves to
+                    let victim = to.down();
+                    self.b_board ^= victim.bitboard();
+                    self.b_piece_board[PieceKind::Pawn] ^= victim.bitboard();
+                    self.pieces[victim] = None;
+                    hash ^= piece_key(BLACK, PieceKind::Pawn, victim);
+                    self.remove_piece_score(BLACK, PieceKind::Pawn, victim);
+                } else if let Some(captured) = occupation {
+                    hash ^= piece_key(BLACK, captured, to);
+                    self.remove_piece_score(BLACK, captured, to);
                     self.b_board ^= bit_to;
-                    unsafe {
-                        let ptr = self.b_piece_board.get_unchecked_mut(occupation as usize);
-                        *ptr ^= bit_to;
-                    }
-                    if to == 56 {
+                    self.b_piece_board[captured] ^= bit_to;
+                    if to == self.castling_rooks[2] {
                         self.castling_rights[2] = false;
-                    } else if to == 63 {
+                    } else if to == self.castling_rooks[3] {
                         self.castling_rights[3] = false;
                     }
                 }
@@ -81,73 +98,54 @@ impl Position {
             match move_flag {
                 QUIET_MOVE => {}
                 DOUBLE_PAWN_PUSH => {
-                    self.en_passant_target_square = bit_to >> 8;
-                }
-                KING_CASTLE => {
-                    self.w_board ^= 0b1u64 | (0b1u64 << 2);
-                    self.w_piece_board[ROOK] ^= 0b1u64 | (0b1u64 << 2);
-                    self.pieces[0] = NONE;
-                    self.pieces[2] = ROOK as u8;
-                }
-                QUEEN_CASTLE => {
-                    self.w_board ^= (0b1u64 << 4) | (0b1u64 << 7);
-                    self.w_piece_board[ROOK] ^= (0b1u64 << 4) | (0b1u64 << 7);
-                    self.pieces[7] = NONE;
-                    self.pieces[4] = ROOK as u8;
+                    self.en_passant_target_square = to.down().bitboard();
                 }
                 _ => {
-                    if move_flag & PROMOTION == PROMOTION {
-                        let promotion = ((move_flag >> 12) & 3) as usize;
-                        self.w_piece_board[PAWN] ^= bit_to;
-                        unsafe {
-                            let ptr = self.w_piece_board.get_unchecked_mut(promotion);
-                            *ptr |= bit_to;
-                            let ptr = self.pieces.get_unchecked_mut(to as usize);
-                            *ptr = promotion as u8;
-                        }
+                    if let Some(promotion) = mve.promotion() {
+                        self.w_piece_board[PieceKind::Pawn] ^= bit_to;
+                        self.w_piece_board[promotion] |= bit_to;
+                        self.pieces[to] = Some(promotion);
+                        hash ^=
+                            piece_key(WHITE, PieceKind::Pawn, to) ^ piece_key(WHITE, promotion, to);
+                        self.remove_piece_score(WHITE, PieceKind::Pawn, to);
+                        self.add_piece_score(WHITE, promotion, to);
                     }
                 }
             }
         } else {
-            //full moves are incremented after black moves
-            self.full_moves += 1;
-
             //updating castling rights
-            if piece == KING as u8 {
+            if piece == PieceKind::King {
                 (self.castling_rights[2], self.castling_rights[3]) = (false, false);
-            } else if piece == ROOK as u8 {
-                if from == 56 {
+            } else if piece == PieceKind::Rook {
+                if from == self.castling_rooks[2] {
                     self.castling_rights[2] = false;
-                } else if from == 63 {
+                } else if from == self.castling_rooks[3] {
                     self.castling_rights[3] = false;
                 }
             }
             //updating board
             self.b_board ^= bit_move;
-            unsafe {
-                let ptr = self.pieces.get_unchecked_mut(to as usize);
-                *ptr = piece;
-                let ptr = self.pieces.get_unchecked_mut(from as usize);
-                *ptr = NONE;
-                let ptr = self.b_piece_board.get_unchecked_mut(piece as usize);
-                *ptr ^= bit_move;
-            }
+            self.pieces[to] = Some(piece);
+            self.pieces[from] = None;
+            self.b_piece_board[piece] ^= bit_move;
 
-            if move_flag & CAPTURE == CAPTURE {
+            if mve.is_capture() {
                 do_not_reset_half_move = false;
                 if move_flag == EN_PASSANT_CAPTURE {
-                    self.w_board ^= bit_to << 8;
-                    self.w_piece_board[PAWN] ^= bit_to << 8;
-                    self.pieces[to as usize + 8] = NONE;
-                } else {
+                    let victim = to.up();
+                    self.w_board ^= victim.bitboard();
+                    self.w_piece_board[PieceKind::Pawn] ^= victim.bitboard();
+                    self.pieces[victim] = None;
+                    hash ^= piece_key(WHITE, PieceKind::Pawn, victim);
+                    self.remove_piece_score(WHITE, PieceKind::Pawn, victim);
+                } else if let Some(captured) = occupation {
+                    hash ^= piece_key(WHITE, captured, to);
+                    self.remove_piece_score(WHITE, captured, to);
                     self.w_board ^= bit_to;
-                    unsafe {
-                        let ptr = self.w_piece_board.get_unchecked_mut(occupation as usize);
-                        *ptr ^= bit_to;
-                    }
-                    if to == 0 {
+                    self.w_piece_board[captured] ^= bit_to;
+                    if to == self.castling_rooks[0] {
                         self.castling_rights[0] = false;
-                    } else if to == 7 {
+                    } else if to == self.castling_rooks[1] {
                         self.castling_rights[1] = false;
                     }
                 }
@@ -156,37 +154,57 @@ impl Position {
             match move_flag {
                 QUIET_MOVE => {}
                 DOUBLE_PAWN_PUSH => {
-                    self.en_passant_target_square = bit_to << 8;
-                }
-                KING_CASTLE => {
-                    self.b_board ^= (0b1u64 << 56) | (0b1u64 << 58);
-                    self.b_piece_board[ROOK] ^= (0b1u64 << 56) | (0b1u64 << 58);
-                    self.pieces[56] = NONE;
-                    self.pieces[58] = ROOK as u8;
-                }
-                QUEEN_CASTLE => {
-                    self.b_board ^= (0b1u64 << 60) | (0b1u64 << 63);
-                    self.b_piece_board[ROOK] ^= (0b1u64 << 60) | (0b1u64 << 63);
-                    self.pieces[63] = NONE;
-                    self.pieces[60] = ROOK as u8;
+                    self.en_passant_target_square = to.up().bitboard();
                 }
                 _ => {
-                    if move_flag & PROMOTION == PROMOTION {
-                        let promotion = ((move_flag >> 12) & 3) as usize;
-                        self.b_piece_board[PAWN] ^= bit_to;
-                        unsafe {
-                            let ptr = self.b_piece_board.get_unchecked_mut(promotion);
-                            *ptr |= bit_to;
-                            let ptr = self.pieces.get_unchecked_mut(to as usize);
-                            *ptr = promotion as u8;
-                        }
+                    if let Some(promotion) = mve.promotion() {
+                        self.b_piece_board[PieceKind::Pawn] ^= bit_to;
+                        self.b_piece_board[promotion] |= bit_to;
+                        self.pieces[to] = Some(promotion);
+                        hash ^=
+                            piece_key(BLACK, PieceKind::Pawn, to) ^ piece_key(BLACK, promotion, to);
+                        self.remove_piece_score(BLACK, PieceKind::Pawn, to);
+                        self.add_piece_score(BLACK, promotion, to);
                     }
                 }
             }
         }
         self.half_move_clock += 1;
         self.half_move_clock *= (do_not_reset_half_move as u32);
-        self.blocker_board = self.w_board | self.b_board;
-        self.color_to_move = !self.color_to_move;
+        hash
+    }
+
+    //castling moves the king onto the square of its rook, both are taken off the board before they
+    //are placed as in chess960 either of them can end on the start square of the other
+    fn make_castling_move(&mut self, color: Color, mve: Move) -> u64 {
+        let first_right = if color == WHITE { 0 } else { 2 };
+        let right = first_right + (mve.flag() == QUEEN_CASTLE) as usize;
+        let (king_from, rook_from) = (mve.from(), mve.to());
+        let (king_to, rook_to) = (self.castling_king_to[right], self.castling_rook_to[right]);
+        let (board, piece_board) = if color == WHITE {
+            (&mut self.w_board, &mut self.w_piece_board)
+        } else {
+            (&mut self.b_board, &mut self.b_piece_board)
+        };
+        *board = (*board & !(king_from.bitboard() | rook_from.bitboard()))
+            | king_to.bitboard()
+            | rook_to.bitboard();
+        piece_board[PieceKind::King] ^= king_from.bitboard() ^ king_to.bitboard();
+        piece_board[PieceKind::Rook] ^= rook_from.bitboard() ^ rook_to.bitboard();
+        self.pieces[king_from] = None;
+        self.pieces[rook_from] = None;
+        self.pieces[king_to] = Some(PieceKind::King);
+        self.pieces[rook_to] = Some(PieceKind::Rook);
+
+        self.remove_piece_score(color, PieceKind::King, king_from);
+        self.remove_piece_score(color, PieceKind::Rook, rook_from);
+        self.add_piece_score(color, PieceKind::King, king_to);
+        self.add_piece_score(color, PieceKind::Rook, rook_to);
+
+        self.castling_rights[first_right..first_right + 2].fill(false);
+        piece_key(color, PieceKind::King, king_from)
+            ^ piece_key(color, PieceKind::King, king_to)
+            ^ piece_key(color, PieceKind::Rook, rook_from)
+            ^ piece_key(color, PieceKind::Rook, rook_to)
     }
 }
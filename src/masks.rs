@@ -1,151 +1,170 @@
-use crate::board::{
-    Bitboard, Square, FILE, NOT_ON_AB_FILE, NOT_ON_A_FILE, NOT_ON_GH_FILE, NOT_ON_H_FILE, RANK,
+use crate::{
+    bitboard::Bitboard,
+    board::{
+        Square, EMPTY, FILE, NOT_ON_AB_FILE, NOT_ON_A_FILE, NOT_ON_GH_FILE, NOT_ON_H_FILE, RANK,
+    },
+    piece::PieceKind,
 };
 
-pub static mut KING_MASK: [Bitboard; 64] = [0; 64];
-pub static mut KNIGHT_MASK: [Bitboard; 64] = [0; 64];
-pub static mut ROOK_ALL_BLOCKERS_MASK: [Bitboard; 64] = [0; 64];
-pub static mut BISHOP_ALL_BLOCKERS_MASK: [Bitboard; 64] = [0; 64];
+//all masks are generated at compile time
+pub static KING_MASK: [Bitboard; 64] = mask_table(PieceKind::King);
+pub static KNIGHT_MASK: [Bitboard; 64] = mask_table(PieceKind::Knight);
+pub static ROOK_ALL_BLOCKERS_MASK: [Bitboard; 64] = mask_table(PieceKind::Rook);
+pub static BISHOP_ALL_BLOCKERS_MASK: [Bitboard; 64] = mask_table(PieceKind::Bishop);
 
-pub fn init_masks() {
-    for square in 0..64 {
-        let bit_square = 0b1u64 << square;
-        unsafe {
-            KING_MASK[square] = king_mask(bit_square);
-            KNIGHT_MASK[square] = knight_mask(bit_square);
-            ROOK_ALL_BLOCKERS_MASK[square] = rook_all_blockers_mask(square as Square);
-            BISHOP_ALL_BLOCKERS_MASK[square] = bishop_all_blockers_mask(square as Square)
-        }
+const fn mask_table(piece: PieceKind) -> [Bitboard; 64] {
+    let mut table = [EMPTY; 64];
+    let mut square = 0;
+    while square < 64 {
+        let bit_square = Square::new(square as u8).bitboard();
+        table[square] = match piece {
+            PieceKind::King => king_mask(bit_square),
+            PieceKind::Knight => knight_mask(bit_square),
+            PieceKind::Rook => rook_all_blockers_mask(Square::new(square as u8)),
+            _ => bishop_all_blockers_mask(Square::new(square as u8)),
+        };
+        square += 1;
     }
+    table
 }
 
 #[inline]
 pub fn w_pawn_capture_mask(bitboard_square: &Bitboard) -> Bitboard {
-    ((*bitboard_square & NOT_ON_H_FILE) << 7) | ((*bitboard_square & NOT_ON_A_FILE) << 9)
+    bitboard_square.north_east() | bitboard_square.north_west()
 }
 
 #[inline]
 pub fn b_pawn_capture_mask(bitboard_square: &Bitboard) -> Bitboard {
-    ((*bitboard_square & NOT_ON_H_FILE) >> 9) | ((*bitboard_square & NOT_ON_A_FILE) >> 7)
+    bitboard_square.south_east() | bitboard_square.south_west()
 }
 
-fn king_mask(bitboard_square: Bitboard) -> Bitboard {
-    ((bitboard_square & NOT_ON_H_FILE) << 7)
-        | ((bitboard_square & NOT_ON_H_FILE) >> 1)
-        | ((bitboard_square & NOT_ON_H_FILE) >> 9)
-        | (bitboard_square << 8)
-        | (bitboard_square >> 8)
-        | ((bitboard_square & NOT_ON_A_FILE) << 9)
-        | ((bitboard_square & NOT_ON_A_FILE) << 1)
-        | ((bitboard_square & NOT_ON_A_FILE) >> 7)
+const fn king_mask(bitboard_square: Bitboard) -> Bitboard {
+    Bitboard(
+        bitboard_square.north_east().0
+            | bitboard_square.east().0
+            | bitboard_square.south_east().0
+            | bitboard_square.north().0
+            | bitboard_square.south().0
+            | bitboard_square.north_west().0
+            | bitboard_square.west().0
+            | bitboard_square.south_west().0,
+    )
 }
 
-fn knight_mask(bitboard_square: Bitboard) -> Bitboard {
-    ((bitboard_square & NOT_ON_A_FILE) << 17)
-        | ((bitboard_square & NOT_ON_A_FILE) >> 15)
-        | ((bitboard_square & NOT_ON_H_FILE) << 15)
-        | ((bitboard_square & NOT_ON_H_FILE) >> 17)
-        | ((bitboard_square & NOT_ON_AB_FILE) << 10)
-        | ((bitboard_square & NOT_ON_AB_FILE) >> 6)
-        | ((bitboard_square & NOT_ON_GH_FILE) << 6)
-        | ((bitboard_square & NOT_ON_GH_FILE) >> 10)
+//const fns can not use the operator traits so the masks are built from the raw bits
+const fn knight_mask(bitboard_square: Bitboard) -> Bitboard {
+    let square = bitboard_square.0;
+    Bitboard(
+        ((square & NOT_ON_A_FILE.0) << 17)
+            | ((square & NOT_ON_A_FILE.0) >> 15)
+            | ((square & NOT_ON_H_FILE.0) << 15)
+            | ((square & NOT_ON_H_FILE.0) >> 17)
+            | ((square & NOT_ON_AB_FILE.0) << 10)
+            | ((square & NOT_ON_AB_FILE.0) >> 6)
+            | ((square & NOT_ON_GH_FILE.0) << 6)
+            | ((square & NOT_ON_GH_FILE.0) >> 10),
+    )
 }
 
 //returns a bitboard with all squares of potential blockers for rooks
 //pieces on edges can never block
-pub fn rook_all_blockers_mask(square: Square) -> Bitboard {
-    let file = 7 - (square % 8);
-    let rank = square / 8;
-    let not_on_ah = !(FILE[0] | FILE[7]);
-    let not_on_18 = !(RANK[0] | RANK[7]);
-    ((FILE[file as usize] & not_on_18) ^ (RANK[rank as usize] & not_on_ah)) & !(0b1u64 << square)
+pub const fn rook_all_blockers_mask(square: Square) -> Bitboard {
+    let not_on_ah = !(FILE[0].0 | FILE[7].0);
+    let not_on_18 = !(RANK[0].0 | RANK[7].0);
+    Bitboard(
+        ((Bitboard::file(square.file()).0 & not_on_18)
+            ^ (Bitboard::rank(square.rank()).0 & not_on_ah))
+            & !square.bitboard().0,
+    )
 }
 
-pub fn rook_mask(bitboard_square: Bitboard, blocker_board: Bitboard) -> Bitboard {
+pub const fn rook_mask(bitboard_square: Bitboard, blocker_board: Bitboard) -> Bitboard {
+    let blocker_board = blocker_board.0;
     let mut mask = 0b0u64;
-    let mut ptr = bitboard_square;
+    let mut ptr = bitboard_square.0;
     //up
-    while ptr & RANK[7] == 0 {
+    while ptr & RANK[7].0 == 0 {
         ptr <<= 8;
         mask |= ptr;
         if ptr & blocker_board != 0 {
             break;
         }
     }
-    ptr = bitboard_square;
+    ptr = bitboard_square.0;
     //down
-    while ptr & RANK[0] == 0 {
+    while ptr & RANK[0].0 == 0 {
         ptr >>= 8;
         mask |= ptr;
         if ptr & blocker_board != 0 {
             break;
         }
     }
-    ptr = bitboard_square;
+    ptr = bitboard_square.0;
     //left
-    while ptr & FILE[0] == 0 {
+    while ptr & FILE[0].0 == 0 {
         ptr <<= 1;
         mask |= ptr;
         if ptr & blocker_board != 0 {
             break;
         }
     }
-    ptr = bitboard_square;
+    ptr = bitboard_square.0;
     //right
-    while ptr & FILE[7] == 0 {
+    while ptr & FILE[7].0 == 0 {
         ptr >>= 1;
         mask |= ptr;
         if ptr & blocker_board != 0 {
             break;
         }
     }
-    mask
+    Bitboard(mask)
 }
 
 //returns a bitboard with all squares of potential blockers for bishops
 //pieces on edges can never block
-pub fn bishop_all_blockers_mask(square: Square) -> Bitboard {
-    let bitboard_edges = FILE[0] | FILE[7] | RANK[0] | RANK[7];
-    bishop_mask(0b1u64 << square, 0b0u64) & !bitboard_edges
+pub const fn bishop_all_blockers_mask(square: Square) -> Bitboard {
+    let bitboard_edges = FILE[0].0 | FILE[7].0 | RANK[0].0 | RANK[7].0;
+    Bitboard(bishop_mask(square.bitboard(), EMPTY).0 & !bitboard_edges)
 }
 
-pub fn bishop_mask(bitboard_square: Bitboard, blocker_board: Bitboard) -> Bitboard {
+pub const fn bishop_mask(bitboard_square: Bitboard, blocker_board: Bitboard) -> Bitboard {
+    let blocker_board = blocker_board.0;
     let mut mask = 0b0u64;
-    let mut ptr = bitboard_square;
+    let mut ptr = bitboard_square.0;
     //up right
-    while ptr & (RANK[7] | FILE[7]) == 0 {
+    while ptr & (RANK[7].0 | FILE[7].0) == 0 {
         ptr <<= 7;
         mask |= ptr;
         if ptr & blocker_board != 0 {
             break;
         }
     }
-    ptr = bitboard_square;
+    ptr = bitboard_square.0;
     //down right
-    while ptr & (RANK[0] | FILE[7]) == 0 {
+    while ptr & (RANK[0].0 | FILE[7].0) == 0 {
         ptr >>= 9;
         mask |= ptr;
         if ptr & blocker_board != 0 {
             break;
         }
     }
-    ptr = bitboard_square;
+    ptr = bitboard_square.0;
     //up left
-    while ptr & (RANK[7] | FILE[0]) == 0 {
+    while ptr & (RANK[7].0 | FILE[0].0) == 0 {
         ptr <<= 9;
         mask |= ptr;
         if ptr & blocker_board != 0 {
             break;
         }
     }
-    ptr = bitboard_square;
+    ptr = bitboard_square.0;
     //down left
-    while ptr & (RANK[0] | FILE[0]) == 0 {
+    while ptr & (RANK[0].0 | FILE[0].0) == 0 {
         ptr >>= 7;
         mask |= ptr;
         if ptr & blocker_board != 0 {
             break;
         }
     }
-    mask
+    Bitboard(mask)
 }
@@ -0,0 +1,254 @@
+use crate::{
+    attacks::attacks_from,
+    bitboard::Bitboard,
+    board::{Position, Square, EMPTY, WHITE},
+    mve::{
+        Move, CAPTURE, DOUBLE_PAWN_PUSH, EN_PASSANT_CAPTURE, KING_CASTLE, QUEEN_CASTLE, QUIET_MOVE,
+    },
+    piece::{Piece, PieceKind},
+    side::{Black, Side, White},
+};
+
+//questions about a move in the current position which can be answered without making the move
+impl Position {
+    pub fn moved_piece(&self, mve: Move) -> Option<Piece> {
+        self.piece_on(mve.from())
+    }
+
+    //the en passant victim is not on the square the pawn moves to
+    pub fn captured_piece(&self, mve: Move) -> Option<Piece> {
+        if mve.flag() == EN_PASSANT_CAPTURE {
+            Some(Piece::new(PieceKind::Pawn, !self.color_to_move))
+        } else if mve.is_capture() {
+            self.piece_on(mve.to())
+        } else {
+            None
+        }
+    }
+
+    //whether a legal move checks the enemy king directly or by uncovering a slider
+    pub fn gives_check(&self, mve: Move) -> bool {
+        if self.color_to_move == WHITE {
+            self.gives_check_for::<White>(mve)
+        } else {
+            self.gives_check_for::<Black>(mve)
+        }
+    }
+
+    //whether the move could have been generated in this position if pins and checks are ignored,
+    //moves from other positions like killer or transposition table moves can be checked with this
+    //before they are made
+    pub fn is_pseudo_legal(&self, mve: Move) -> bool {
+        if self.color_to_move == WHITE {
+            self.is_pseudo_legal_for::<White>(mve)
+        } else {
+            self.is_pseudo_legal_for::<Black>(mve)
+        }
+    }
+
+    //whether the move is one of the legal moves, without generating them
+    pub fn is_legal(&self, mve: Move) -> bool {
+        if self.color_to_move == WHITE {
+            self.is_legal_for::<White>(mve)
+        } else {
+            self.is_legal_for::<Black>(mve)
+        }
+    }
+
+    //in chess960 the castling rook can be the only piece between the destination of the king and an
+    //enemy rook or queen on the back rank, this is not possible if the rook starts in a corner so
+    //this only needs to be checked for other rooks
+    pub fn castling_uncovers_king<S: Side>(&self, king: Square, right: usize) -> bool {
+        let king_to = self.castling_king_to[right];
+        let occupancy =
+            (self.blocker_board & !king.bitboard() & !self.castling_rooks[right].bitboard())
+                | king_to.bitboard()
+                | self.castling_rook_to[right].bitboard();
+        let enemy = S::Opponent::piece_board(self);
+        self.seen_by_rook_custom_blocker(king_to, occupancy)
+            & (enemy[PieceKind::Rook] | enemy[PieceKind::Queen])
+            != EMPTY
+    }
+
+    fn is_pseudo_legal_for<S: Side>(&self, mve: Move) -> bool {
+        let from = mve.from();
+        let to = mve.to();
+        let flag = mve.flag();
+        let Some(piece) = self.piece_on(from) else {
+            return false;
+        };
+        if piece.color() != S::COLOR {
+            return false;
+        }
+
+        match flag {
+            //castling rights are removed when the king or the rook moves or the rook is captured
+            KING_CASTLE | QUEEN_CASTLE => {
+                let right = S::castling_right(mve);
+                return self.castling_rights[right]
+                    && piece.kind() == PieceKind::King
+                    && to == self.castling_rooks[right]
+                    && self.castling_empty[right] & self.blocker_board == EMPTY;
+            }
+            EN_PASSANT_CAPTURE => {
+                //the captured pawn stands behind the target square, make_move removes it from there
+                let victim = S::Opponent::forward(to.bitboard(), 1);
+                return piece.kind() == PieceKind::Pawn
+                    && to.bitboard() == self.en_passant_target_square
+                    && S::pawn_attacks(&from.bitboard()).contains(to)
+                    && victim & S::Opponent::piece_board(self)[PieceKind::Pawn] != EMPTY;
+            }
+            _ => {}
+        }
+
+        //captures must take an enemy piece other than the king, other moves need an empty square
+        let valid_target = if mve.is_capture() {
+            S::Opponent::board(self).contains(to) && self.pieces[to] != Some(PieceKind::King)
+        } else {
+            !self.blocker_board.contains(to)
+        };
+        if !valid_target {
+            return false;
+        }
+
+        if piece.kind() != PieceKind::Pawn {
+            return (flag == QUIET_MOVE || flag == CAPTURE)
+                && attacks_from(piece, from, self.blocker_board).contains(to);
+        }
+        let single_push = S::forward(from.bitboard(), 1);
+        let reachable = match flag {
+            QUIET_MOVE => single_push,
+            //the square between the two ranks must be empty as well
+            DOUBLE_PAWN_PUSH => {
+                S::forward(from.bitboard() & S::DOUBLE_PUSH_RANK, 2)
+                    & !S::forward(self.blocker_board, 1)
+            }
+            CAPTURE => S::pawn_attacks(&from.bitboard()),
+            _ if mve.is_promotion() && mve.is_capture() => S::pawn_attacks(&from.bitboard()),
+            _ if mve.is_promotion() => single_push,
+            _ => EMPTY,
+        };
+        //pawns reaching the last rank must promote and only those can promote
+        reachable.contains(to) && S::PROMOTION_RANK.contains(to) == mve.is_promotion()
+    }
+
+    fn is_legal_for<S: Side>(&self, mve: Move) -> bool {
+        if !self.is_pseudo_legal_for::<S>(mve) {
+            return false;
+        }
+        let from = mve.from();
+        let to = mve.to();
+        let flag = mve.flag();
+
+        //the king can not castle out of, through or into check
+        if mve.is_castling() {
+            let right = S::castling_right(mve);
+            return self.castling_king_path[right]
+                .into_iter()
+                .all(|square| !self.is_attacked(square, S::Opponent::COLOR))
+                && (matches!(to.file(), 0 | 7) || !self.castling_uncovers_king::<S>(from, right));
+        }
+
+        //the king must not be attacked on the board after the move, a captured piece no longer
+        //attacks and sliders can see through the square the piece moved from
+        let captured: Bitboard = if flag == EN_PASSANT_CAPTURE {
+            S::Opponent::forward(to.bitboard(), 1)
+        } else {
+            to.bitboard()
+        };
+        let occupancy = (self.blocker_board & !from.bitboard() & !captured) | to.bitboard();
+        let king = if self.pieces[from] == Some(PieceKind::King) {
+            to
+        } else {
+            S::piece_board(self)[PieceKind::King].lsb()
+        };
+        self.attackers_to_with_occupancy(king, S::Opponent::COLOR, occupancy) & !captured == EMPTY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::{Square, BLACK},
+        mve::BISHOP_PROMOTION,
+        perft::TEST_FENS,
+    };
+
+    fn square(name: &str) -> Square {
+        Square::from_algebraic(name).unwrap()
+    }
+
+    //every combination of squares and flags is legal exactly when the generator produces it,
+    //and a legal move gives check exactly when the king is in check after making it
+    #[test]
+    fn is_legal_matches_generator() {
+        for fen in TEST_FENS {
+            let mut pos = Position::from_fen(fen);
+            let legal_moves = pos.legal_moves();
+            for from in 0..64 {
+                for to in 0..64 {
+                    for flag in 0..16 {
+                        let mve = Move::new(Square::new(from), Square::new(to), flag << 12);
+                        assert_eq!(
+                            legal_moves.contains(&mve),
+                            pos.is_legal(mve),
+                            "{} {:?} {}",
+                            fen,
+                            mve,
+                            flag
+                        );
+                        if legal_moves.contains(&mve) {
+                            assert!(pos.is_pseudo_legal(mve));
+                            let mut pos_after = pos.clone();
+                            pos_after.make_move(mve);
+                            assert_eq!(
+                                pos_after.update_check_and_pinned() != 0,
+                                pos.gives_check(mve)
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn moved_and_captured_pieces() {
+        let pos = Position::from_fen("8/2p5/8/K1Pp3r/1R3p1k/8/4P1P1/8 w - d6 0 1");
+        let en_passant = Move::new(square("c5"), square("d6"), EN_PASSANT_CAPTURE);
+        let capture = Move::new(square("b4"), square("f4"), CAPTURE);
+        let push = Move::new(square("e2"), square("e4"), DOUBLE_PAWN_PUSH);
+        assert_eq!(
+            Some(Piece::new(PieceKind::Pawn, WHITE)),
+            pos.moved_piece(en_passant)
+        );
+        assert_eq!(
+            Some(Piece::new(PieceKind::Pawn, BLACK)),
+            pos.captured_piece(en_passant)
+        );
+        assert_eq!(
+            Some(Piece::new(PieceKind::Pawn, BLACK)),
+            pos.captured_piece(capture)
+        );
+        assert_eq!(None, pos.captured_piece(push));
+        assert_eq!(
+            None,
+            pos.moved_piece(Move::new(square("e4"), square("e5"), QUIET_MOVE))
+        );
+
+        //en passant would uncover the rook on the rank of the king
+        assert!(pos.is_pseudo_legal(en_passant) && !pos.is_legal(en_passant));
+        assert!(pos.is_legal(capture));
+        //killers from other positions with the wrong flag or a friendly piece on the target square
+        assert!(!pos.is_pseudo_legal(Move::new(square("b4"), square("f4"), QUIET_MOVE)));
+        assert!(!pos.is_pseudo_legal(Move::new(square("a5"), square("b4"), CAPTURE)));
+        assert!(!pos.is_pseudo_legal(Move::new(square("e2"), square("e3"), BISHOP_PROMOTION)));
+
+        //an en passant square without the pushed pawn in front of it, which only a corrupt state has
+        let mut pos = Position::from_fen("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1");
+        pos.en_passant_target_square = square("e6").bitboard();
+        let phantom = Move::new(square("d5"), square("e6"), EN_PASSANT_CAPTURE);
+        assert!(!pos.is_pseudo_legal(phantom) && !pos.is_legal(phantom));
+    }
+}
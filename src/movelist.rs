@@ -0,0 +1,119 @@
+use crate::mve::Move;
+use std::{
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+};
+
+//the maximum amount of legal moves in any chess position
+//https://www.chessprogramming.org/Chess_Position#Maximum_number_of_moves
+pub const MAX_NUMBER_OF_LEGAL_MOVES: usize = 218;
+
+//a list of moves stored on the stack, the moves can be accessed as a slice
+//so iterating, indexing, len, contains and sorting all work like on a slice
+//the array is not initialized when the list is created, only the first len moves are written
+#[derive(Clone, Copy)]
+pub struct MoveList {
+    moves: [MaybeUninit<Move>; MAX_NUMBER_OF_LEGAL_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    #[inline]
+    pub fn new() -> MoveList {
+        MoveList {
+            moves: [const { MaybeUninit::uninit() }; MAX_NUMBER_OF_LEGAL_MOVES],
+            len: 0,
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, mve: Move) {
+        //the index is checked, a position with more moves panics instead of writing past the list
+        self.moves[self.len].write(mve);
+        self.len += 1;
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    //sorts the moves from low to high score, moves with the same score keep their order
+    //insertion sort is used as the lists are short and often almost sorted already, the moves are
+    //scored while they are compared so no array of scores has to be filled for every list
+    pub fn sort_by_score(&mut self, mut score: impl FnMut(Move) -> i32) {
+        let moves = &mut self[..];
+        for i in 1..moves.len() {
+            let mve = moves[i];
+            let mve_score = score(mve);
+            let mut j = i;
+            while j > 0 && score(moves[j - 1]) > mve_score {
+                moves[j] = moves[j - 1];
+                j -= 1;
+            }
+            moves[j] = mve;
+        }
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    #[inline]
+    fn deref(&self) -> &[Move] {
+        //the first len moves have been written by push
+        unsafe { std::slice::from_raw_parts(self.moves.as_ptr() as *const Move, self.len) }
+    }
+}
+
+impl DerefMut for MoveList {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [Move] {
+        //the first len moves have been written by push
+        unsafe { std::slice::from_raw_parts_mut(self.moves.as_mut_ptr() as *mut Move, self.len) }
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl std::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|mve| crate::mve::move_to_algebraic(*mve)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::Square, mve::QUIET_MOVE};
+
+    #[test]
+    fn sort_by_score() {
+        let mve = |to| Move::new(Square::new(0), Square::new(to), QUIET_MOVE);
+        let mut moves = MoveList::new();
+        for to in [5, 3, 9, 1, 7] {
+            moves.push(mve(to));
+        }
+        assert_eq!(5, moves.len());
+        assert!(moves.contains(&mve(9)));
+        moves.sort_by_score(|mve| -(mve.to().index() as i32));
+        assert_eq!(&[mve(9), mve(7), mve(5), mve(3), mve(1)], &moves[..]);
+        moves.clear();
+        assert!(moves.is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{
+    bitboard::Bitboard,
+    board::{Position, Square, EMPTY, WHITE},
+    magic::{bishop_attacks, rook_attacks},
+    masks::{b_pawn_capture_mask, w_pawn_capture_mask, KING_MASK, KNIGHT_MASK},
+    movelist::{MoveList, MAX_NUMBER_OF_LEGAL_MOVES},
+    moveorder::{promising, value_on},
+    mve::{Move, EN_PASSANT_CAPTURE, NULL_MOVE},
+    piece::PieceKind,
+};
+
+//the depth is a u8 so the search can never be deeper than this
+pub const MAX_PLY: usize = u8::MAX as usize + 1;
+
+//quiet moves which caused beta cutoffs earlier in the search are likely to cause cutoffs again
+//https://www.chessprogramming.org/Killer_Heuristic
+//https://www.chessprogramming.org/History_Heuristic
+pub struct Heuristics {
+    //the two latest killer moves for every ply
+    pub killers: [[Move; 2]; MAX_PLY],
+    //indexed by color, from square and to square
+    pub history: [[[i32; 64]; 64]; 2],
+}
+
+impl Heuristics {
+    pub fn new() -> Heuristics {
+        Heuristics {
+            killers: [[NULL_MOVE; 2]; MAX_PLY],
+            history: [[[0; 64]; 64]; 2],
+        }
+    }
+
+    #[inline]
+    pub fn history(&self, pos: &Position, mve: Move) -> i32 {
+        self.history[pos.color_to_move.index()][mve.from()][mve.to()]
+    }
+
+    //should be called when a quiet move causes a beta cutoff
+    pub fn update(&mut self, pos: &Position, mve: Move, ply: usize, depth: u8) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != mve {
+            killers[1] = killers[0];
+            killers[0] = mve;
+        }
+        let history = &mut self.history[pos.color_to_move.index()][mve.from()][mve.to()];
+        //deeper cutoffs are more reliable than cutoffs close to the leaves
+        *history = history.saturating_add(depth as i32 * depth as i32);
+    }
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline]
+pub fn is_quiet(mve: Move) -> bool {
+    !mve.is_capture() && !mve.is_promotion()
+}
+
+//captures where the agressor is worth more than the victim lose material if the victim is defended,
+//these are searched last. en passant and capture promotions never lose material this way
+#[inline]
+fn is_bad_capture(pos: &Position, mve: Move) -> bool {
+    if mve.flag() == EN_PASSANT_CAPTURE || mve.is_promotion() {
+        return false;
+    }
+    let from = mve.from();
+    let to = mve.to();
+    if value_on(pos, from) <= value_on(pos, to) {
+        return false;
+    }
+    //the moving piece no longer blocks sliders behind it
+    is_defended(pos, to, pos.blocker_board ^ from.bitboard())
+}
+
+//whether the opponent of the side to move attacks the square
+#[inline]
+fn is_defended(pos: &Position, square: Square, blocker_board: Bitboard) -> bool {
+    let square_bitboard = square.bitboard();
+    //a pawn defends the square if a friendly pawn on the square could capture it
+    let (enemy_piece_board, pawn_defender_squares) = if pos.color_to_move == WHITE {
+        (&pos.b_piece_board, w_pawn_capture_mask(&square_bitboard))
+    } else {
+        (&pos.w_piece_board, b_pawn_capture_mask(&square_bitboard))
+    };
+    pawn_defender_squares & enemy_piece_board[PieceKind::Pawn] != EMPTY
+        || KNIGHT_MASK[square] & enemy_piece_board[PieceKind::Knight] != EMPTY
+        || KING_MASK[square] & enemy_piece_board[PieceKind::King] != EMPTY
+        || bishop_attacks(square, blocker_board)
+            & (enemy_piece_board[PieceKind::Bishop] | enemy_piece_board[PieceKind::Queen])
+            != EMPTY
+        || rook_attacks(square, blocker_board)
+            & (enemy_piece_board[PieceKind::Rook] | enemy_piece_board[PieceKind::Queen])
+            != EMPTY
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Stage {
+    TtMove,
+    GenerateCaptures,
+    GoodCaptures,
+    GenerateQuiets,
+    Promotions,
+    Killers,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+//returns the legal moves of a position one at a time in the order they should be searched
+//https://www.chessprogramming.org/Move_Generation#Staged_Move_Generation
+//the moves are generated and scored in stages, so if one of the first moves causes a beta cutoff
+//the quiet moves will never be generated
+pub struct MovePicker {
+    stage: Stage,
+    //the move which was best in an earlier search of this position, it is only searched if it is
+    //legal in this position so moves from hash collisions are skipped
+    tt_move: Move,
+    killers: [Move; 2],
+    killer_index: usize,
+    //captures followed by quiet moves, bad captures are moved to the front of the list while
+    //good captures are picked as every move before the current one has already been picked
+    moves: MoveList,
+    //the scores of the captures, only the first end scores are written in the capture stages
+    scores: [i32; MAX_NUMBER_OF_LEGAL_MOVES],
+    bad_captures: usize,
+    current: usize,
+    end: usize,
+}
+
+impl MovePicker {
+    pub fn new(tt_move: Move, killers: [Move; 2]) -> MovePicker {
+        MovePicker {
+            stage: Stage::TtMove,
+            tt_move,
+            killers,
+            killer_index: 0,
+            moves: MoveList::new(),
+            scores: [0; MAX_NUMBER_OF_LEGAL_MOVES],
+            bad_captures: 0,
+            current: 0,
+            end: 0,
+        }
+    }
+
+    //returns the next move to search or None when all legal moves have been returned
+    pub fn next(&mut self, pos: &mut Position, heuristics: &Heuristics) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::TtMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if self.tt_move != NULL_MOVE && pos.is_legal(self.tt_move) {
+                        return Some(self.tt_move);
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    self.moves = pos.legal_captures();
+                    for (score, mve) in self.scores.iter_mut().zip(self.moves.iter()) {
+                        *score = promising(pos, mve);
+                    }
+                    self.end = self.moves.len();
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => {
+                    if self.current == self.end {
+                        self.stage = Stage::GenerateQuiets;
+                        continue;
+                    }
+                    let mve = self.pick_best();
+                    if mve == self.tt_move {
+                        continue;
+                    }
+                    if is_bad_capture(pos, mve) {
+                        self.moves[self.bad_captures] = mve;
+                        self.bad_captures += 1;
+                        continue;
+                    }
+                    return Some(mve);
+                }
+                Stage::GenerateQuiets => {
+                    for &mve in &pos.legal_quiets() {
+                        self.moves.push(mve);
+                    }
+                    self.end = self.moves.len();
+                    self.stage = Stage::Promotions;
+                }
+                Stage::Promotions => match self.take(|mve| mve.is_promotion()) {
+                    Some(mve) => return Some(mve),
+                    None => self.stage = Stage::Killers,
+                },
+                Stage::Killers => {
+                    if self.killer_index == self.killers.len() {
+                        //the remaining moves are sorted once by how often they caused cutoffs
+                        self.moves[self.current..self.end]
+                            .sort_unstable_by_key(|mve| -heuristics.history(pos, *mve));
+                        self.stage = Stage::Quiets;
+                        continue;
+                    }
+                    let killer = self.killers[self.killer_index];
+                    self.killer_index += 1;
+                    //killers come from other positions, only those found among the legal moves are used
+                    if killer == NULL_MOVE {
+                        continue;
+                    }
+                    if let Some(mve) = self.take(|mve| mve == killer) {
+                        return Some(mve);
+                    }
+                }
+                Stage::Quiets => {
+                    if self.current == self.end {
+                        self.current = 0;
+                        self.stage = Stage::BadCaptures;
+                        continue;
+                    }
+                    let mve = self.moves[self.current];
+                    self.current += 1;
+                    if mve != self.tt_move {
+                        return Some(mve);
+                    }
+                }
+                Stage::BadCaptures => {
+                    if self.current == self.bad_captures {
+                        self.stage = Stage::Done;
+                        continue;
+                    }
+                    let mve = self.moves[self.current];
+                    self.current += 1;
+                    return Some(mve);
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    //selection sort step, the rest of the captures are only sorted if they are needed
+    fn pick_best(&mut self) -> Move {
+        let mut best = self.current;
+        for i in self.current + 1..self.end {
+            if self.scores[i] < self.scores[best] {
+                best = i;
+            }
+        }
+        self.moves.swap(self.current, best);
+        self.scores.swap(self.current, best);
+        self.current += 1;
+        self.moves[self.current - 1]
+    }
+
+    //picks the first remaining move matching the predicate which is not the tt move
+    fn take(&mut self, predicate: impl Fn(Move) -> bool) -> Option<Move> {
+        let tt_move = self.tt_move;
+        let index = (self.current..self.end)
+            .find(|i| self.moves[*i] != tt_move && predicate(self.moves[*i]))?;
+        self.moves.swap(self.current, index);
+        self.current += 1;
+        Some(self.moves[self.current - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mve::algebraic_to_move, perft::TEST_FENS};
+
+    fn picked_moves(pos: &mut Position, picker: &mut MovePicker) -> Vec<Move> {
+        let heuristics = Heuristics::new();
+        let mut moves = Vec::new();
+        while let Some(mve) = picker.next(pos, &heuristics) {
+            moves.push(mve);
+        }
+        moves
+    }
+
+    #[test]
+    fn picks_every_legal_move_once() {
+        for fen in TEST_FENS {
+            let mut pos = Position::new();
+            pos.parse_fen(fen);
+            let legal_moves = pos.legal_moves();
+            let mut expected = legal_moves.to_vec();
+            expected.sort();
+
+            let mut moves = picked_moves(&mut pos, &mut MovePicker::new(NULL_MOVE, [NULL_MOVE; 2]));
+            moves.sort();
+            assert_eq!(expected, moves, "{}", fen);
+
+            //the tt move and the legal killer are returned first and never returned twice
+            let tt_move = legal_moves[legal_moves.len() - 1];
+            let killer = expected
+                .iter()
+                .copied()
+                .find(|mve| is_quiet(*mve) && *mve != tt_move);
+            let killers = [
+                killer.unwrap_or(NULL_MOVE),
+                algebraic_to_move(&pos, "a1a8").unwrap(),
+            ];
+            let mut moves = picked_moves(&mut pos, &mut MovePicker::new(tt_move, killers));
+            assert_eq!(tt_move, moves[0]);
+            moves.sort();
+            assert_eq!(expected, moves, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn stage_order() {
+        let mut pos = Position::new();
+        //the queen can capture a defended pawn, the pawn on b7 can promote
+        pos.parse_fen("4k3/1P6/3p4/4p3/8/6Q1/8/4K3 w - - 0 1");
+        let moves = picked_moves(&mut pos, &mut MovePicker::new(NULL_MOVE, [NULL_MOVE; 2]));
+        let queen_takes = moves.iter().position(|mve| mve.is_capture()).unwrap();
+        assert_eq!(moves.len() - 1, queen_takes);
+        assert_eq!(algebraic_to_move(&pos, "b7b8q").unwrap(), moves[0]);
+    }
+}
@@ -1,19 +1,20 @@
 use std::fmt::format;
 
 use crate::{
-    board::{square_to_bitboard, Position, EMPTY},
-    piece::{KING, NONE, PAWN},
+    bitboard::Bitboard,
+    board::{square_to_bitboard, Position, Square, EMPTY, WHITE},
+    piece::PieceKind,
 };
 
-use std::cmp::{max, min};
-
-pub type Move = u16;
-
 //https://www.chessprogramming.org/Encoding_Moves
+//the from square is stored in the lowest 6 bits, the to square in the next 6 bits
+//and a flag describing the kind of move in the highest 4 bits
+//castling moves are stored as the king moving onto the square of its rook, so castling moves stay
+//distinct in chess960 where the king may already stand on its destination
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Move(u16);
 
-//4 bits are used as a flag
-
-pub const MOVE_FLAG: Move = 0b1111 << 12;
+pub type MoveFlag = u16;
 
 //bit	flag
 //1		promotion
@@ -37,91 +38,243 @@ pub const MOVE_FLAG: Move = 0b1111 << 12;
 // 14	1	1	1	0	rook-promo capture
 // 15	1	1	1	1	queen-promo capture
 
-pub const QUIET_MOVE: Move = 0 << 12;
-pub const DOUBLE_PAWN_PUSH: Move = 1 << 12;
-pub const KING_CASTLE: Move = 2 << 12;
-pub const QUEEN_CASTLE: Move = 3 << 12;
-pub const CAPTURE: Move = 4 << 12;
-pub const EN_PASSANT_CAPTURE: Move = 5 << 12;
-pub const KNIGHT_PROMOTION: Move = 8 << 12;
-pub const BISHOP_PROMOTION: Move = 9 << 12;
-pub const ROOK_PROMOTION: Move = 10 << 12;
-pub const QUEEN_PROMOTION: Move = 11 << 12;
-pub const KNIGHT_PROMOTION_CAPTURE: Move = 12 << 12;
-pub const BISHOP_PROMOTION_CAPTURE: Move = 13 << 12;
-pub const ROOK_PROMOTION_CAPTURE: Move = 14 << 12;
-pub const QUEEN_PROMOTION_CAPTURE: Move = 15 << 12;
-pub const PROMOTION: Move = 8 << 12;
-
-//12 bits are used to store from and to squares
-
-pub const MOVE_FROM: Move = 0b111111;
-pub const MOVE_TO: Move = 0b111111 << 6;
-
-pub const NULL_MOVE: Move = 0;
+pub const QUIET_MOVE: MoveFlag = 0 << 12;
+pub const DOUBLE_PAWN_PUSH: MoveFlag = 1 << 12;
+pub const KING_CASTLE: MoveFlag = 2 << 12;
+pub const QUEEN_CASTLE: MoveFlag = 3 << 12;
+pub const CAPTURE: MoveFlag = 4 << 12;
+pub const EN_PASSANT_CAPTURE: MoveFlag = 5 << 12;
+pub const KNIGHT_PROMOTION: MoveFlag = 8 << 12;
+pub const BISHOP_PROMOTION: MoveFlag = 9 << 12;
+pub const ROOK_PROMOTION: MoveFlag = 10 << 12;
+pub const QUEEN_PROMOTION: MoveFlag = 11 << 12;
+pub const KNIGHT_PROMOTION_CAPTURE: MoveFlag = 12 << 12;
+pub const BISHOP_PROMOTION_CAPTURE: MoveFlag = 13 << 12;
+pub const ROOK_PROMOTION_CAPTURE: MoveFlag = 14 << 12;
+pub const QUEEN_PROMOTION_CAPTURE: MoveFlag = 15 << 12;
+pub const PROMOTION: MoveFlag = 8 << 12;
+
+const MOVE_FROM: u16 = 0b111111;
+const MOVE_TO: u16 = 0b111111 << 6;
+const MOVE_FLAG: u16 = 0b1111 << 12;
+
+pub const NULL_MOVE: Move = Move(0);
+
+impl Move {
+    #[inline]
+    pub const fn new(from: Square, to: Square, flag: MoveFlag) -> Move {
+        Move(from.index() as u16 | ((to.index() as u16) << 6) | flag)
+    }
+
+    #[inline]
+    pub const fn from(self) -> Square {
+        Square::new((self.0 & MOVE_FROM) as u8)
+    }
+
+    #[inline]
+    pub const fn to(self) -> Square {
+        Square::new(((self.0 & MOVE_TO) >> 6) as u8)
+    }
+
+    #[inline]
+    pub const fn flag(self) -> MoveFlag {
+        self.0 & MOVE_FLAG
+    }
+
+    //en passant and capture promotions are also captures
+    #[inline]
+    pub const fn is_capture(self) -> bool {
+        self.0 & CAPTURE == CAPTURE
+    }
+
+    #[inline]
+    pub const fn is_promotion(self) -> bool {
+        self.0 & PROMOTION == PROMOTION
+    }
+
+    #[inline]
+    pub const fn is_castling(self) -> bool {
+        self.flag() == KING_CASTLE || self.flag() == QUEEN_CASTLE
+    }
+
+    //the king and the rook end on the same squares as in standard chess after castling
+    #[inline]
+    pub const fn castling_king_to(self) -> Square {
+        let file = if self.flag() == KING_CASTLE { 6 } else { 2 };
+        Square::from_file_rank(file, self.from().rank())
+    }
+
+    #[inline]
+    pub const fn castling_rook_to(self) -> Square {
+        let file = if self.flag() == KING_CASTLE { 5 } else { 3 };
+        Square::from_file_rank(file, self.from().rank())
+    }
+
+    //the target square in UCI notation, castling is written as the king moving two squares if the
+    //king and the rook start on their standard squares and as the king moving onto its rook
+    //otherwise, like in chess960 UCI notation
+    pub fn written_to(self) -> Square {
+        let standard_castling = self.from().file() == 4 && matches!(self.to().file(), 0 | 7);
+        if self.is_castling() && standard_castling {
+            self.castling_king_to()
+        } else {
+            self.to()
+        }
+    }
+
+    //the squares the king crosses when castling including its start and its destination,
+    //none of them may be attacked
+    pub fn castling_king_path(self) -> Bitboard {
+        squares_between(self.from(), self.castling_king_to())
+    }
+
+    //the squares the king and the rook cross when castling, they must be empty apart from the
+    //castling king and rook themselves
+    pub fn castling_empty_squares(self) -> Bitboard {
+        (squares_between(self.from(), self.castling_king_to())
+            | squares_between(self.to(), self.castling_rook_to()))
+            & !(self.from().bitboard() | self.to().bitboard())
+    }
+
+    //the piece a pawn promotes to, the two lowest flag bits are ordered like the piece kinds
+    #[inline]
+    pub const fn promotion(self) -> Option<PieceKind> {
+        if !self.is_promotion() {
+            return None;
+        }
+        match (self.0 >> 12) & 3 {
+            0 => Some(PieceKind::Knight),
+            1 => Some(PieceKind::Bishop),
+            2 => Some(PieceKind::Rook),
+            _ => Some(PieceKind::Queen),
+        }
+    }
+}
+
+//the squares of a rank from one square to another including both
+fn squares_between(from: Square, to: Square) -> Bitboard {
+    let (low, high) = (from.index().min(to.index()), from.index().max(to.index()));
+    Bitboard((u64::MAX << low) & (u64::MAX >> (63 - high)))
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.from(), self.written_to())?;
+        match self.promotion() {
+            Some(PieceKind::Knight) => write!(f, "n"),
+            Some(PieceKind::Bishop) => write!(f, "b"),
+            Some(PieceKind::Rook) => write!(f, "r"),
+            Some(_) => write!(f, "q"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Debug for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
 
 pub fn move_to_algebraic(mve: Move) -> String {
-    let from = mve & MOVE_FROM;
-    let from_row = ((from / 8) as u8 + b'1') as char;
-    let from_col = (7 - (from % 8) as u8 + b'a') as char;
-    let to = (mve & MOVE_TO) >> 6;
-    let to_row = ((to / 8) as u8 + b'1') as char;
-    let to_col = (7 - (to % 8) as u8 + b'a') as char;
-    let mut promotion = "";
-    //note that order for promotion matters here
-    if mve & QUEEN_PROMOTION == QUEEN_PROMOTION {
-        promotion = "q";
-    } else if mve & ROOK_PROMOTION == ROOK_PROMOTION {
-        promotion = "r";
-    } else if mve & BISHOP_PROMOTION == BISHOP_PROMOTION {
-        promotion = "b";
-    } else if mve & KNIGHT_PROMOTION == KNIGHT_PROMOTION {
-        promotion = "n";
-    }
-    format!("{}{}{}{}{}", from_col, from_row, to_col, to_row, promotion)
+    mve.to_string()
 }
 
 //as this engine makes use of move flags, the current position is
 //requiered to convert algebraic moves to create internal flags
-pub fn algebraic_to_move(pos: &Position, algebraic: &str) -> Move {
-    let from = &algebraic[..2];
-    let from_col = from.chars().next().unwrap() as u8 - b'a';
-    let from_row = from.chars().nth(1).unwrap() as u8 - b'1';
-    let to = &algebraic[2..];
-    let to_col = to.chars().next().unwrap() as u8 - b'a';
-    let to_row = to.chars().nth(1).unwrap() as u8 - b'1';
-
-    let from_square = (from_row) * 8 + (7 - from_col);
-    let to_square = (to_row) * 8 + (7 - to_col);
-
-    let mut mve: Move = from_square as u16 | ((to_square as u16) << 6);
-
-    if pos.pieces[to_square as usize] != NONE {
-        mve |= CAPTURE;
-    } else if pos.en_passant_target_square == square_to_bitboard(to_square) {
-        mve |= EN_PASSANT_CAPTURE;
-    }
-    if pos.pieces[from_square as usize] == PAWN as u8 {
-        if max(from_square, to_square) - min(from_square, to_square) == 16 {
-            mve |= DOUBLE_PAWN_PUSH;
-        }
-    } else if pos.pieces[from_square as usize] == KING as u8
-        && max(from_square, to_square) - min(from_square, to_square) == 2
-    {
-        if to_square == 1 || to_square == 57 {
-            mve |= KING_CASTLE;
-        } else if to_square == 5 || to_square == 61 {
-            mve |= QUEEN_CASTLE;
+//the move is not checked to be legal, None is returned if it is not written like "e7e8q"
+//castling can be written as the king moving two squares or as the king moving onto its rook
+pub fn algebraic_to_move(pos: &Position, algebraic: &str) -> Option<Move> {
+    if !matches!(algebraic.len(), 4 | 5) {
+        return None;
+    }
+    let from = Square::from_algebraic(algebraic.get(..2)?)?;
+    let to = Square::from_algebraic(algebraic.get(2..4)?)?;
+
+    if algebraic.len() == 4 && pos.pieces[from] == Some(PieceKind::King) {
+        if let Some(castling) = castling_move(pos, from, to) {
+            return Some(castling);
         }
     }
-    if to.len() == 3 {
-        match to.chars().nth(2).unwrap() {
-            'q' => mve |= QUEEN_PROMOTION,
-            'n' => mve |= KNIGHT_PROMOTION,
-            'r' => mve |= ROOK_PROMOTION,
-            'b' => mve |= BISHOP_PROMOTION,
-            _ => panic!(),
+    let mut flag = QUIET_MOVE;
+
+    if pos.pieces[to].is_some() {
+        flag |= CAPTURE;
+    } else if pos.en_passant_target_square == square_to_bitboard(to) {
+        flag |= EN_PASSANT_CAPTURE;
+    }
+    if pos.pieces[from] == Some(PieceKind::Pawn) && from.index().abs_diff(to.index()) == 16 {
+        flag |= DOUBLE_PAWN_PUSH;
+    }
+    if algebraic.len() == 5 {
+        match algebraic.as_bytes()[4] {
+            b'q' => flag |= QUEEN_PROMOTION,
+            b'n' => flag |= KNIGHT_PROMOTION,
+            b'r' => flag |= ROOK_PROMOTION,
+            b'b' => flag |= BISHOP_PROMOTION,
+            _ => return None,
         }
     }
-    mve
+    Some(Move::new(from, to, flag))
+}
+
+//a king move onto a friendly rook with a castling right or two squares along its rank towards it
+fn castling_move(pos: &Position, from: Square, to: Square) -> Option<Move> {
+    let first_right = if pos.color_to_move == WHITE { 0 } else { 2 };
+    (first_right..first_right + 2).find_map(|right| {
+        let rook = pos.castling_rooks[right];
+        let flag = if right % 2 == 0 {
+            KING_CASTLE
+        } else {
+            QUEEN_CASTLE
+        };
+        let mve = Move::new(from, rook, flag);
+        let standard = from.file() == 4 && from.index().abs_diff(to.index()) == 2;
+        (pos.castling_rights[right] && (to == rook || standard && to == mve.castling_king_to()))
+            .then_some(mve)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_encoding() {
+        let e7 = Square::from_algebraic("e7").unwrap();
+        let d8 = Square::from_algebraic("d8").unwrap();
+        assert_eq!((4, 6), (e7.file(), e7.rank()));
+        assert_eq!(Some(e7), Square::from_algebraic(&e7.to_string()));
+        assert_eq!(None, Square::from_algebraic("i1"));
+
+        let mve = Move::new(e7, d8, ROOK_PROMOTION_CAPTURE);
+        assert_eq!((e7, d8), (mve.from(), mve.to()));
+        assert!(mve.is_capture() && mve.is_promotion());
+        assert_eq!(Some(PieceKind::Rook), mve.promotion());
+        assert_eq!("e7d8r", mve.to_string());
+        assert_eq!(None, Move::new(e7, d8, EN_PASSANT_CAPTURE).promotion());
+
+        let pos = Position::from_fen("3r4/4P3/8/8/8/8/k7/7K w - - 0 1");
+        assert_eq!(Some(mve), algebraic_to_move(&pos, "e7d8r"));
+        assert_eq!(None, algebraic_to_move(&pos, "e7d8x"));
+        assert_eq!(None, algebraic_to_move(&pos, "e7"));
+        assert_eq!(None, algebraic_to_move(&pos, "e7d8qq"));
+        assert_eq!(None, algebraic_to_move(&pos, "é7d8"));
+
+        //castling is stored as the king moving onto its rook
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        let castling = algebraic_to_move(&pos, "e1g1").unwrap();
+        assert_eq!(Some(castling), algebraic_to_move(&pos, "e1h1"));
+        assert_eq!(
+            (KING_CASTLE, "h1"),
+            (castling.flag(), castling.to().to_string().as_str())
+        );
+        assert_eq!("e1g1", castling.to_string());
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/5KR1 w G - 0 1");
+        assert_eq!("f1g1", algebraic_to_move(&pos, "f1g1").unwrap().to_string());
+        assert_eq!(
+            None,
+            algebraic_to_move(&pos, "f1h1").filter(|mve| mve.is_castling())
+        );
+    }
 }
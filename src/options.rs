@@ -0,0 +1,308 @@
+//engine options that can be configured by the gui with "setoption"
+//https://page.mi.fu-berlin.de/block/uci.htm
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OptionKind {
+    Spin {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    Check {
+        default: bool,
+    },
+    Combo {
+        default: &'static str,
+        vars: &'static [&'static str],
+    },
+    Button,
+    String {
+        default: &'static str,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionValue {
+    Spin(i64),
+    Check(bool),
+    Combo(String),
+    Button,
+    String(String),
+}
+
+pub struct EngineOption {
+    pub name: &'static str,
+    pub kind: OptionKind,
+}
+
+pub const HASH: &str = "Hash";
+pub const THREADS: &str = "Threads";
+pub const MULTI_PV: &str = "MultiPV";
+pub const MOVE_OVERHEAD: &str = "Move Overhead";
+
+pub const DEFAULT_HASH_MB: usize = 16;
+pub const DEFAULT_THREADS: usize = 1;
+pub const DEFAULT_MULTI_PV: usize = 1;
+pub const DEFAULT_MOVE_OVERHEAD_MS: u64 = 10;
+
+//all options advertised in the "uci" reply
+//the search has no hash table and runs on one thread, Hash and Threads only configure the perft
+//table and threads of "go perft" and "bench"
+pub const ENGINE_OPTIONS: [EngineOption; 4] = [
+    EngineOption {
+        name: HASH,
+        kind: OptionKind::Spin {
+            default: DEFAULT_HASH_MB as i64,
+            min: 1,
+            max: 65536,
+        },
+    },
+    EngineOption {
+        name: THREADS,
+        kind: OptionKind::Spin {
+            default: DEFAULT_THREADS as i64,
+            min: 1,
+            max: 512,
+        },
+    },
+    EngineOption {
+        name: MULTI_PV,
+        kind: OptionKind::Spin {
+            default: DEFAULT_MULTI_PV as i64,
+            min: 1,
+            max: 218,
+        },
+    },
+    EngineOption {
+        name: MOVE_OVERHEAD,
+        kind: OptionKind::Spin {
+            default: DEFAULT_MOVE_OVERHEAD_MS as i64,
+            min: 0,
+            max: 5000,
+        },
+    },
+];
+
+impl EngineOption {
+    //the line describing the option in the "uci" reply
+    pub fn uci_string(&self) -> String {
+        let mut line = format!("option name {} type ", self.name);
+        match self.kind {
+            OptionKind::Spin { default, min, max } => {
+                line += &format!("spin default {} min {} max {}", default, min, max);
+            }
+            OptionKind::Check { default } => {
+                line += &format!("check default {}", default);
+            }
+            OptionKind::Combo { default, vars } => {
+                line += &format!("combo default {}", default);
+                for var in vars {
+                    line += &format!(" var {}", var);
+                }
+            }
+            OptionKind::Button => {
+                line += "button";
+            }
+            OptionKind::String { default } => {
+                //the uci protocol uses <empty> to send an empty string
+                let default = if default.is_empty() {
+                    "<empty>"
+                } else {
+                    default
+                };
+                line += &format!("string default {}", default);
+            }
+        }
+        line
+    }
+
+    //validates a value sent with "setoption" against the type and bounds of this option
+    pub fn parse_value(&self, value: Option<&str>) -> Result<OptionValue, String> {
+        match (self.kind, value) {
+            (OptionKind::Button, _) => Ok(OptionValue::Button),
+            (_, None) => Err(format!("option {} requires a value", self.name)),
+            (OptionKind::Spin { min, max, .. }, Some(value)) => match value.parse::<i64>() {
+                Ok(number) if (min..=max).contains(&number) => Ok(OptionValue::Spin(number)),
+                Ok(_) => Err(format!(
+                    "value for option {} must be between {} and {}",
+                    self.name, min, max
+                )),
+                Err(_) => Err(format!("value for option {} must be a number", self.name)),
+            },
+            (OptionKind::Check { .. }, Some(value)) => match value {
+                "true" => Ok(OptionValue::Check(true)),
+                "false" => Ok(OptionValue::Check(false)),
+                _ => Err(format!(
+                    "value for option {} must be true or false",
+                    self.name
+                )),
+            },
+            (OptionKind::Combo { vars, .. }, Some(value)) => vars
+                .iter()
+                .find(|var| var.eq_ignore_ascii_case(value))
+                .map(|var| OptionValue::Combo(var.to_string()))
+                .ok_or(format!(
+                    "value for option {} must be one of: {}",
+                    self.name,
+                    vars.join(" ")
+                )),
+            (OptionKind::String { .. }, Some(value)) => {
+                if value == "<empty>" {
+                    Ok(OptionValue::String(String::new()))
+                } else {
+                    Ok(OptionValue::String(value.to_string()))
+                }
+            }
+        }
+    }
+}
+
+//option names are case insensitive
+pub fn find_option(name: &str) -> Option<&'static EngineOption> {
+    ENGINE_OPTIONS
+        .iter()
+        .find(|option| option.name.eq_ignore_ascii_case(name))
+}
+
+//splits the arguments of "setoption name <id> [value <x>]" into name and value,
+//both the name and the value may contain spaces
+pub fn parse_setoption(args: &str) -> Option<(String, Option<String>)> {
+    let mut tokens = args.split_whitespace();
+    if tokens.next() != Some("name") {
+        return None;
+    }
+    let mut name: Vec<&str> = Vec::new();
+    let mut value: Option<Vec<&str>> = None;
+
+    for token in tokens {
+        match value.as_mut() {
+            Some(value) => value.push(token),
+            None if token == "value" => value = Some(Vec::new()),
+            None => name.push(token),
+        }
+    }
+    if name.is_empty() {
+        return None;
+    }
+    Some((name.join(" "), value.map(|value| value.join(" "))))
+}
+
+//the configuration used by the engine, updated through "setoption"
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineConfig {
+    pub hash_mb: usize,
+    pub threads: usize,
+    pub multi_pv: usize,
+    pub move_overhead_ms: u64,
+}
+
+impl EngineConfig {
+    pub fn new() -> EngineConfig {
+        EngineConfig {
+            hash_mb: DEFAULT_HASH_MB,
+            threads: DEFAULT_THREADS,
+            multi_pv: DEFAULT_MULTI_PV,
+            move_overhead_ms: DEFAULT_MOVE_OVERHEAD_MS,
+        }
+    }
+
+    //handles the arguments of a "setoption" command
+    pub fn set_option(&mut self, args: &str) -> Result<(), String> {
+        let (name, value) =
+            parse_setoption(args).ok_or("unexpected format use setoption name <id> [value <x>]")?;
+        let option = find_option(&name).ok_or(format!("unknown option {}", name))?;
+        let value = option.parse_value(value.as_deref())?;
+        self.apply(option.name, value);
+        Ok(())
+    }
+
+    fn apply(&mut self, name: &str, value: OptionValue) {
+        match (name, value) {
+            (HASH, OptionValue::Spin(mb)) => self.hash_mb = mb as usize,
+            (THREADS, OptionValue::Spin(threads)) => self.threads = threads as usize,
+            (MULTI_PV, OptionValue::Spin(lines)) => self.multi_pv = lines as usize,
+            (MOVE_OVERHEAD, OptionValue::Spin(ms)) => self.move_overhead_ms = ms as u64,
+            _ => {}
+        }
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setoption_updates_config() {
+        let mut config = EngineConfig::new();
+        config.set_option("name Hash value 256").unwrap();
+        config.set_option("name move overhead value 50").unwrap();
+        config.set_option("name MultiPV value 3").unwrap();
+        config.set_option("name Threads value 4").unwrap();
+
+        assert_eq!(256, config.hash_mb);
+        assert_eq!(50, config.move_overhead_ms);
+        assert_eq!(3, config.multi_pv);
+        assert_eq!(4, config.threads);
+
+        assert!(config.set_option("name Hash value 0").is_err());
+        assert!(config.set_option("name Hash value big").is_err());
+        assert!(config.set_option("name Unknown value 1").is_err());
+        assert_eq!(256, config.hash_mb);
+    }
+
+    #[test]
+    fn option_types() {
+        let check = EngineOption {
+            name: "Ponder",
+            kind: OptionKind::Check { default: false },
+        };
+        let combo = EngineOption {
+            name: "Style",
+            kind: OptionKind::Combo {
+                default: "Normal",
+                vars: &["Solid", "Normal", "Risky"],
+            },
+        };
+        let button = EngineOption {
+            name: "Clear Hash",
+            kind: OptionKind::Button,
+        };
+        let string = EngineOption {
+            name: "Book File",
+            kind: OptionKind::String { default: "" },
+        };
+
+        assert_eq!(
+            "option name Style type combo default Normal var Solid var Normal var Risky",
+            combo.uci_string()
+        );
+        assert_eq!(
+            "option name Book File type string default <empty>",
+            string.uci_string()
+        );
+        assert_eq!(
+            Ok(OptionValue::Check(true)),
+            check.parse_value(Some("true"))
+        );
+        assert_eq!(
+            Ok(OptionValue::Combo("Risky".to_string())),
+            combo.parse_value(Some("risky"))
+        );
+        assert!(combo.parse_value(Some("Wild")).is_err());
+        assert_eq!(Ok(OptionValue::Button), button.parse_value(None));
+        assert_eq!(
+            Ok(OptionValue::String("my book.bin".to_string())),
+            string.parse_value(Some("my book.bin"))
+        );
+        assert_eq!(
+            Some(("Clear Hash".to_string(), None)),
+            parse_setoption("name Clear Hash")
+        );
+    }
+}
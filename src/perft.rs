@@ -1,73 +1,382 @@
-use std::time::Instant;
+use std::{
+    alloc::{alloc_zeroed, Layout},
+    fs::File,
+    io::{BufRead, BufReader},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
 
 use crate::{
     board::Position,
-    mve::{move_to_algebraic, NULL_MOVE},
+    magic::slider_backend,
+    mve::{move_to_algebraic, Move, NULL_MOVE},
 };
 
 //counts the amount of leaf nodes at a certain depth
 pub fn perft(pos: &mut Position, depth: u8) -> usize {
+    if depth == 0 {
+        return 1;
+    }
     let legal_moves = pos.legal_moves();
     if depth == 1 {
-        return legal_moves.1;
+        return legal_moves.len();
     }
     let mut count = 0;
 
-    for i in 0..legal_moves.1 {
+    for mve in &legal_moves {
         let mut pos_clone = pos.clone();
-        pos_clone.make_move(legal_moves.0[i]);
+        pos_clone.make_move(*mve);
         count += perft(&mut pos_clone, depth - 1);
     }
     count
 }
 
-//performs a perft seach with printed results and meausers the speed of the move generation
-//the results from this can be used to compare correctness and speed with stockfish
-pub fn bench(pos: &mut Position, depth: u8) {
-    let start = Instant::now();
+//a table of perft results shared by all threads, an entry stores the count and the key xored
+//with the count so threads can read and write without locks and torn entries are not used
+//https://www.chessprogramming.org/Shared_Hash_Table#Lock-less
+pub struct PerftTable {
+    entries: Box<[[AtomicU64; 2]]>,
+    mask: usize,
+}
+
+impl PerftTable {
+    //the hash option allows sizes larger than the memory of most machines, so a failed
+    //allocation is reported instead of aborting the engine
+    //the table is allocated zeroed and not filled, writing every entry would commit all pages and
+    //get the process killed on systems which overcommit memory instead of failing the allocation
+    pub fn new(hash_mb: usize) -> Result<PerftTable, String> {
+        let error = || format!("not enough memory for a {} MB perft table", hash_mb);
+        let size =
+            hash_mb.max(1).saturating_mul(1024 * 1024) / std::mem::size_of::<[AtomicU64; 2]>();
+        //a power of two so the index is a mask of the key
+        let size = 1 << size.ilog2();
+        let layout = Layout::array::<[AtomicU64; 2]>(size).map_err(|_| error())?;
+        let entries = unsafe { alloc_zeroed(layout) } as *mut [AtomicU64; 2];
+        if entries.is_null() {
+            return Err(error());
+        }
+        //zeroed atomics are valid entries and the box frees the memory with the same layout
+        let entries = unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(entries, size)) };
+        Ok(PerftTable {
+            entries,
+            mask: size - 1,
+        })
+    }
+
+    //the same position has a different count at every depth
+    #[inline]
+    fn key(hash: u64, depth: u8) -> u64 {
+        hash ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    pub fn probe(&self, hash: u64, depth: u8) -> Option<usize> {
+        let key = PerftTable::key(hash, depth);
+        let entry = &self.entries[key as usize & self.mask];
+        let check = entry[0].load(Ordering::Relaxed);
+        let count = entry[1].load(Ordering::Relaxed);
+        (check ^ count == key).then_some(count as usize)
+    }
+
+    //always replaces the entry
+    pub fn store(&self, hash: u64, depth: u8, count: usize) {
+        let key = PerftTable::key(hash, depth);
+        let entry = &self.entries[key as usize & self.mask];
+        entry[0].store(key ^ count as u64, Ordering::Relaxed);
+        entry[1].store(count as u64, Ordering::Relaxed);
+    }
+}
+
+//perft with the counts of positions reached by transpositions looked up in the table
+pub fn hashed_perft(pos: &mut Position, depth: u8, table: &PerftTable) -> usize {
+    if depth == 0 {
+        return 1;
+    }
+    if depth > 1 {
+        if let Some(count) = table.probe(pos.hash, depth) {
+            return count;
+        }
+    }
+    let legal_moves = pos.legal_moves();
+    //bulk counting, the leaf nodes do not need to be played
+    if depth == 1 {
+        return legal_moves.len();
+    }
     let mut count = 0;
+
+    for mve in &legal_moves {
+        let mut pos_clone = pos.clone();
+        pos_clone.make_move(*mve);
+        count += hashed_perft(&mut pos_clone, depth - 1, table);
+    }
+    table.store(pos.hash, depth, count);
+    count
+}
+
+//the leaf node count after each legal move like divide, the root moves are split across the
+//threads which share one table of the given size
+//there is no move to divide at depth 0 so it is an error like a worker thread which panicked,
+//either is reported instead of crashing the uci loop
+pub fn hashed_divide(
+    pos: &mut Position,
+    depth: u8,
+    hash_mb: usize,
+    threads: usize,
+) -> Result<Vec<(Move, usize)>, String> {
+    if depth == 0 {
+        return Err("perft depth must be at least 1".to_string());
+    }
     let legal_moves = pos.legal_moves();
     if depth == 1 {
-        count += legal_moves.1;
-        for i in 0..legal_moves.1 {
-            println!("{} ", move_to_algebraic(legal_moves.0[i]));
+        return Ok(legal_moves.iter().map(|&mve| (mve, 1)).collect());
+    }
+    let pos = &*pos;
+    let table = PerftTable::new(hash_mb)?;
+    //every thread takes the next root move until all moves are counted
+    let next_move = AtomicUsize::new(0);
+    let mut counts = vec![0; legal_moves.len()];
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut counts = Vec::new();
+                    loop {
+                        let index = next_move.fetch_add(1, Ordering::Relaxed);
+                        let Some(&mve) = legal_moves.get(index) else {
+                            return counts;
+                        };
+                        let mut pos_clone = pos.clone();
+                        pos_clone.make_move(mve);
+                        counts.push((index, hashed_perft(&mut pos_clone, depth - 1, &table)));
+                    }
+                })
+            })
+            .collect();
+        //every worker is joined before an error is returned, the scope would panic again for a
+        //panicked thread which was not joined
+        let mut panicked = false;
+        for worker in workers {
+            match worker.join() {
+                Ok(worker_counts) => {
+                    for (index, count) in worker_counts {
+                        counts[index] = count;
+                    }
+                }
+                Err(_) => panicked = true,
+            }
+        }
+        if panicked {
+            return Err("a perft thread panicked".to_string());
         }
-    } else {
-        for i in 0..legal_moves.1 {
-            let mve = legal_moves.0[i];
+        Ok(())
+    })?;
+    Ok(legal_moves.iter().copied().zip(counts).collect())
+}
+
+pub const DEFAULT_BENCH_DEPTH: u8 = 6;
+
+//performs a hashed perft seach with printed results and meausers the speed of the move generation
+//the results from this can be used to compare correctness and speed with stockfish
+pub fn bench(pos: &mut Position, depth: u8, hash_mb: usize, threads: usize) -> Result<(), String> {
+    let start = Instant::now();
+    let counts = hashed_divide(pos, depth, hash_mb, threads)?;
+    print_bench(&counts, start.elapsed());
+    println!("Hash: {} MB", hash_mb);
+    println!("Threads: {}", threads);
+    println!("Slider Backend: {}", slider_backend());
+    Ok(())
+}
+
+//a plain perft on one thread without a table, unlike bench the speed only depends on the move
+//generation so it can be compared between two builds
+pub fn movegen_bench(pos: &mut Position, depth: u8) {
+    let start = Instant::now();
+    let counts = divide(pos, depth);
+    print_bench(&counts, start.elapsed());
+    println!("Hash: none");
+    println!("Threads: 1");
+    println!("Slider Backend: {}", slider_backend());
+}
+
+fn print_bench(counts: &[(Move, usize)], duration: Duration) {
+    let mut count = 0;
+    for &(mve, nodes) in counts {
+        count += nodes;
+        println!("{}: {}", move_to_algebraic(mve), nodes);
+    }
+    let nodes_per_second = count as u128 * 1_000_000 / duration.as_micros().max(1);
+
+    println!();
+    println!("Time Elapsed: {} ms", duration.as_millis());
+    println!("Total Nodes: {}", count);
+    println!("Nodes/Second: {}", nodes_per_second);
+}
+
+//the leaf node count after each legal move, the move where two move generators disagree can be
+//found by comparing the counts and repeating this after the move
+//no move is played at depth 0, so there are no counts to divide
+pub fn divide(pos: &mut Position, depth: u8) -> Vec<(Move, usize)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let legal_moves = pos.legal_moves();
+    legal_moves
+        .iter()
+        .map(|&mve| {
+            if depth == 1 {
+                return (mve, 1);
+            }
             let mut pos_clone = pos.clone();
             pos_clone.make_move(mve);
-            let res = perft(&mut pos_clone, depth - 1);
-            count += res;
-            println!("{}: {}", move_to_algebraic(mve), res)
+            (mve, perft(&mut pos_clone, depth - 1))
+        })
+        .collect()
+}
+
+//a position of a perft suite with the expected leaf node count of each depth, lines look like
+//rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400 ;D3 8902
+pub struct PerftSuiteEntry {
+    pub pos: Position,
+    pub expected: Vec<(u8, usize)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PerftMismatch {
+    pub depth: u8,
+    pub expected: usize,
+    pub found: usize,
+}
+
+impl PerftSuiteEntry {
+    pub fn parse(line: &str) -> Result<PerftSuiteEntry, String> {
+        let mut parts = line.split(';');
+        let fen = parts.next().unwrap_or_default().trim();
+        //chess960 positions can use Shredder-FEN or X-FEN castling rights
+        let pos = Position::try_from_fen(fen).ok_or_else(|| format!("invalid position {}", fen))?;
+
+        let mut expected = Vec::new();
+        for part in parts.filter(|part| !part.trim().is_empty()) {
+            let mut tokens = part.split_whitespace();
+            let (Some(depth), Some(count), None) = (tokens.next(), tokens.next(), tokens.next())
+            else {
+                return Err(format!("invalid depth {}", part.trim()));
+            };
+            let depth = depth.strip_prefix('D').and_then(|depth| depth.parse().ok());
+            match (depth, count.parse()) {
+                (Some(depth), Ok(count)) if depth > 0 => expected.push((depth, count)),
+                _ => return Err(format!("invalid depth {}", part.trim())),
+            }
         }
+        Ok(PerftSuiteEntry { pos, expected })
     }
-    let end = Instant::now();
-    let duration = end.duration_since(start);
 
-    let nodes_per_second = count as u128 * 1_000_000 / duration.as_micros();
+    //runs perft for every expected count up to the maximum depth, returns the sum of the leaf
+    //nodes or the first depth with a wrong count
+    pub fn check(&self, max_depth: u8) -> Result<usize, PerftMismatch> {
+        let mut nodes = 0;
+        for &(depth, expected) in &self.expected {
+            if depth > max_depth {
+                continue;
+            }
+            let found = perft(&mut self.pos.clone(), depth);
+            if found != expected {
+                return Err(PerftMismatch {
+                    depth,
+                    expected,
+                    found,
+                });
+            }
+            nodes += found;
+        }
+        Ok(nodes)
+    }
+}
+
+//checks every position of a perft suite file up to the depth, the divide output of positions
+//with a wrong count can be compared with the output of another engine
+pub fn run_perft_suite(path: &str, max_depth: u8) {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => {
+            println!("cannot open {}: {}", path, error);
+            return;
+        }
+    };
+    let start = Instant::now();
+    let mut passed = 0;
+    let mut total = 0;
+    let mut count = 0;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                println!("cannot read {}: {}", path, error);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = match PerftSuiteEntry::parse(&line) {
+            Ok(entry) => entry,
+            Err(error) => {
+                println!("line {}: {}", index + 1, error);
+                continue;
+            }
+        };
+        total += 1;
+        match entry.check(max_depth) {
+            Ok(nodes) => {
+                passed += 1;
+                count += nodes;
+                println!("ok {} {}", index + 1, entry.pos.fen());
+            }
+            Err(mismatch) => {
+                println!(
+                    "mismatch {} {} depth {} expected {} found {}",
+                    index + 1,
+                    entry.pos.fen(),
+                    mismatch.depth,
+                    mismatch.expected,
+                    mismatch.found
+                );
+                for (mve, nodes) in divide(&mut entry.pos.clone(), mismatch.depth) {
+                    println!("{}: {}", move_to_algebraic(mve), nodes);
+                }
+            }
+        }
+    }
+    let duration = start.elapsed();
+    let nodes_per_second = count as u128 * 1_000_000 / duration.as_micros().max(1);
 
     println!();
+    println!("Passed: {}/{}", passed, total);
     println!("Time Elapsed: {} ms", duration.as_millis());
     println!("Total Nodes: {}", count);
     println!("Nodes/Second: {}", nodes_per_second);
 }
 
+//the perft positions below and a position with an en passant capture, shared by the tests of
+//the move generator and everything built on it
 #[cfg(test)]
-mod tests {
-    use crate::init::initialize_engine;
+pub const TEST_FENS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+];
 
+#[cfg(test)]
+mod tests {
     use super::*;
     //https://www.chessprogramming.org/Perft_Results
 
-    //initialize_engine() is is not thread safe atm
-    //so running all tests at once on multiple theads will cause false negatives
-    //run the tests one by one or on one thread
-    //cargo test --release -- --test-threads=1
-
     #[test]
     fn perft_startpos() {
-        initialize_engine();
         let mut board = Position::new();
         board.parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
 
@@ -81,7 +390,6 @@ mod tests {
 
     #[test]
     fn perft_kiwipete() {
-        initialize_engine();
         let mut board = Position::new();
         board.parse_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
 
@@ -94,7 +402,6 @@ mod tests {
 
     #[test]
     fn perft_position3() {
-        initialize_engine();
         let mut board = Position::new();
         board.parse_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
 
@@ -109,7 +416,6 @@ mod tests {
 
     #[test]
     fn perft_position4_white() {
-        initialize_engine();
         let mut board = Position::new();
         board.parse_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
 
@@ -122,7 +428,6 @@ mod tests {
 
     #[test]
     fn perft_position4_black() {
-        initialize_engine();
         let mut board = Position::new();
         board.parse_fen("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1");
 
@@ -135,7 +440,6 @@ mod tests {
 
     #[test]
     fn perft_position5() {
-        initialize_engine();
         let mut board = Position::new();
         board.parse_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8");
 
@@ -148,7 +452,6 @@ mod tests {
 
     #[test]
     fn perft_position6() {
-        initialize_engine();
         let mut board = Position::new();
         board.parse_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10");
 
@@ -158,4 +461,86 @@ mod tests {
         assert_eq!(3_894_594, perft(&mut board, 4));
         assert_eq!(164_075_551, perft(&mut board, 5));
     }
+
+    #[test]
+    fn perft_suite_entries() {
+        let entry = PerftSuiteEntry::parse(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400 ;D3 8902",
+        )
+        .unwrap();
+        assert_eq!(vec![(1, 20), (2, 400), (3, 8902)], entry.expected);
+        assert_eq!(Ok(420), entry.check(2));
+        assert_eq!(Ok(9322), entry.check(3));
+
+        //the move counters can be left out like in EPD
+        let entry =
+            PerftSuiteEntry::parse("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - ;D1 14 ;D2 190").unwrap();
+        assert_eq!(
+            Err(PerftMismatch {
+                depth: 2,
+                expected: 190,
+                found: 191
+            }),
+            entry.check(5)
+        );
+        let divide = divide(&mut entry.pos.clone(), 2);
+        assert_eq!(14, divide.len());
+        assert_eq!(191, divide.iter().map(|(_, nodes)| nodes).sum::<usize>());
+
+        //chess960 positions in Shredder-FEN and X-FEN
+        for line in [
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9 ;D1 21 ;D2 528 ;D3 12189",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9 ;D1 21 ;D2 528 ;D3 12189",
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9 ;D1 21 ;D2 807 ;D3 18002",
+            "1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9 ;D1 29 ;D2 502 ;D3 14569",
+        ] {
+            let entry = PerftSuiteEntry::parse(line).unwrap();
+            assert!(entry.check(3).is_ok(), "{}", line);
+        }
+        //the file is only written when another rook is further out on that side of the king
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        assert_eq!(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9",
+            Position::from_fen(fen).fen()
+        );
+        assert_eq!(
+            "4k3/8/8/8/8/8/8/R2RK3 w D - 0 1",
+            Position::from_fen("4k3/8/8/8/8/8/8/R2RK3 w D - 0 1").fen()
+        );
+        assert!(PerftSuiteEntry::parse("4k3/8/8/8/8/8/8/4K3 w - - ;D1 x").is_err());
+        assert!(PerftSuiteEntry::parse("4k3/8/8/8/8/8/8/4K3 w K - ;D1 5").is_err());
+    }
+
+    #[test]
+    fn hashed_perft_counts() {
+        //a small table with many replaced entries and more threads than cores
+        let mut board = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        );
+        let counts = hashed_divide(&mut board, 5, 1, 3).unwrap();
+        assert_eq!(
+            divide(&mut board, 2),
+            hashed_divide(&mut board, 2, 1, 3).unwrap()
+        );
+        assert_eq!(48, counts.len());
+        assert_eq!(
+            193_690_690,
+            counts.iter().map(|(_, nodes)| nodes).sum::<usize>()
+        );
+
+        let mut board = Position::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+        let table = PerftTable::new(1).unwrap();
+        assert_eq!(178_633_661, hashed_perft(&mut board, 7, &table));
+        assert_eq!(14, hashed_perft(&mut board, 1, &table));
+        assert_eq!(1, hashed_perft(&mut board, 0, &table));
+
+        //depth 0 has no moves to divide
+        assert_eq!(1, perft(&mut board, 0));
+        assert!(divide(&mut board, 0).is_empty());
+        assert!(hashed_divide(&mut board, 0, 1, 1).is_err());
+
+        //an exabyte table can not be allocated
+        assert!(PerftTable::new(1 << 40).is_err());
+        assert!(PerftTable::new(usize::MAX).is_err());
+    }
 }
@@ -0,0 +1,252 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use crate::{board::Position, mve::Move, perft::divide};
+
+//anything which can count the leaf nodes after every legal move of a position
+pub trait PerftReference {
+    fn divide(&mut self, fen: &str, depth: u8) -> Result<Vec<(String, usize)>, String>;
+}
+
+//a uci engine which supports "go perft", like stockfish
+pub struct ReferenceEngine {
+    process: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl ReferenceEngine {
+    pub fn start(path: &str) -> Result<ReferenceEngine, String> {
+        let mut process = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|error| format!("cannot start {}: {}", path, error))?;
+        let input = process.stdin.take().unwrap();
+        let output = BufReader::new(process.stdout.take().unwrap());
+        let mut engine = ReferenceEngine {
+            process,
+            input,
+            output,
+        };
+        engine.send("uci")?;
+        while engine.read_line()?.trim() != "uciok" {}
+        Ok(engine)
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.input, "{}", command)
+            .and_then(|_| self.input.flush())
+            .map_err(|error| format!("cannot write to the reference engine: {}", error))
+    }
+
+    fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        match self.output.read_line(&mut line) {
+            Ok(0) => Err("the reference engine exited".to_string()),
+            Ok(_) => Ok(line),
+            Err(error) => Err(format!("cannot read from the reference engine: {}", error)),
+        }
+    }
+}
+
+impl PerftReference for ReferenceEngine {
+    //the engine prints a line like "e2e4: 13160" for every move and ends with "Nodes searched: N"
+    fn divide(&mut self, fen: &str, depth: u8) -> Result<Vec<(String, usize)>, String> {
+        self.send(&format!("position fen {}", fen))?;
+        self.send(&format!("go perft {}", depth))?;
+        let mut counts = Vec::new();
+        loop {
+            let line = self.read_line()?;
+            if line.starts_with("Nodes searched") {
+                return Ok(counts);
+            }
+            let Some((mve, count)) = line.split_once(':') else {
+                continue;
+            };
+            if let Ok(count) = count.trim().parse() {
+                counts.push((mve.trim().to_string(), count));
+            }
+        }
+    }
+}
+
+impl Drop for ReferenceEngine {
+    fn drop(&mut self) {
+        if self.send("quit").is_err() || self.process.wait().is_err() {
+            self.process.kill();
+        }
+    }
+}
+
+//the position and move where the move generators disagree
+#[derive(Debug, PartialEq)]
+pub enum Divergence {
+    //a move generated by this engine but not by the reference
+    ExtraMove { fen: String, mve: String },
+    //a move generated by the reference but not by this engine
+    MissingMove { fen: String, mve: String },
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Divergence::ExtraMove { fen, mve } => {
+                write!(f, "{} is only generated by this engine in {}", mve, fen)
+            }
+            Divergence::MissingMove { fen, mve } => {
+                write!(f, "{} is only generated by the reference in {}", mve, fen)
+            }
+        }
+    }
+}
+
+//compares the divide output of both move generators and follows a move with a different count
+//until a position is reached where one generator has a move the other one has not
+//returns None if all counts are the same
+pub fn find_divergence(
+    reference: &mut impl PerftReference,
+    pos: &Position,
+    depth: u8,
+) -> Result<Option<Divergence>, String> {
+    let mut pos = pos.clone();
+    for depth in (1..=depth).rev() {
+        let fen = pos.fen();
+        let ours: Vec<(Move, usize)> = divide(&mut pos.clone(), depth);
+        let theirs = reference.divide(&fen, depth)?;
+        let count = |counts: &[(String, usize)], mve: &str| {
+            counts
+                .iter()
+                .find(|(other, _)| other == mve)
+                .map(|(_, count)| *count)
+        };
+        let ours: Vec<(String, usize)> = ours
+            .into_iter()
+            .map(|(mve, count)| (mve.to_string(), count))
+            .collect();
+        println!(
+            "depth {} {} nodes {} reference {}",
+            depth,
+            fen,
+            ours.iter().map(|(_, count)| count).sum::<usize>(),
+            theirs.iter().map(|(_, count)| count).sum::<usize>()
+        );
+
+        if let Some((mve, _)) = ours.iter().find(|(mve, _)| count(&theirs, mve).is_none()) {
+            return Ok(Some(Divergence::ExtraMove {
+                fen,
+                mve: mve.clone(),
+            }));
+        }
+        if let Some((mve, _)) = theirs.iter().find(|(mve, _)| count(&ours, mve).is_none()) {
+            return Ok(Some(Divergence::MissingMove {
+                fen,
+                mve: mve.clone(),
+            }));
+        }
+        let Some((mve, nodes)) = ours
+            .iter()
+            .find(|(mve, nodes)| count(&theirs, mve) != Some(*nodes))
+        else {
+            return Ok(None);
+        };
+        println!(
+            "{} nodes {} reference {}",
+            mve,
+            nodes,
+            count(&theirs, mve).unwrap()
+        );
+        let mve = *pos
+            .legal_moves()
+            .iter()
+            .find(|legal_move| legal_move.to_string() == *mve)
+            .unwrap();
+        pos.make_move(mve);
+    }
+    Ok(None)
+}
+
+//compares perft of the position with a reference engine and prints where they diverge
+pub fn run_perft_compare(path: &str, pos: &Position, depth: u8) {
+    let result = ReferenceEngine::start(path)
+        .and_then(|mut reference| find_divergence(&mut reference, pos, depth));
+    match result {
+        Ok(Some(divergence)) => println!("divergence: {}", divergence),
+        Ok(None) => println!("no divergence up to depth {}", depth),
+        Err(error) => println!("{}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::BLACK,
+        mve::{move_to_algebraic, KING_CASTLE},
+    };
+
+    //a reference with a bug, black can never castle kingside
+    struct NoBlackKingCastle;
+
+    fn buggy_moves(pos: &mut Position) -> Vec<Move> {
+        let legal_moves = pos.legal_moves();
+        legal_moves
+            .iter()
+            .copied()
+            .filter(|mve| pos.color_to_move != BLACK || mve.flag() != KING_CASTLE)
+            .collect()
+    }
+
+    fn buggy_perft(pos: &mut Position, depth: u8) -> usize {
+        let moves = buggy_moves(pos);
+        if depth == 1 {
+            return moves.len();
+        }
+        moves
+            .iter()
+            .map(|&mve| {
+                let mut pos_clone = pos.clone();
+                pos_clone.make_move(mve);
+                buggy_perft(&mut pos_clone, depth - 1)
+            })
+            .sum()
+    }
+
+    impl PerftReference for NoBlackKingCastle {
+        fn divide(&mut self, fen: &str, depth: u8) -> Result<Vec<(String, usize)>, String> {
+            let mut pos = Position::from_fen(fen);
+            Ok(buggy_moves(&mut pos)
+                .into_iter()
+                .map(|mve| {
+                    let mut pos_clone = pos.clone();
+                    pos_clone.make_move(mve);
+                    let count = if depth == 1 {
+                        1
+                    } else {
+                        buggy_perft(&mut pos_clone, depth - 1)
+                    };
+                    (move_to_algebraic(mve), count)
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn isolate_divergence() {
+        let pos = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        );
+        let Ok(Some(Divergence::ExtraMove { fen, mve })) =
+            find_divergence(&mut NoBlackKingCastle, &pos, 3)
+        else {
+            panic!("the divergence was not found");
+        };
+        assert_eq!("e8g8", mve);
+        assert!(Position::from_fen(&fen).color_to_move == BLACK);
+
+        let pos = Position::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+        assert_eq!(Ok(None), find_divergence(&mut NoBlackKingCastle, &pos, 3));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{
+    bitboard::Bitboard,
+    board::{Square, EMPTY},
+    magic::{BISHOP_MAGICS, LOOKUP_SIZE, ROOK_MAGICS},
+    masks::{
+        bishop_all_blockers_mask, bishop_mask, rook_all_blockers_mask, rook_mask,
+        BISHOP_ALL_BLOCKERS_MASK, ROOK_ALL_BLOCKERS_MASK,
+    },
+};
+use std::arch::x86_64::_pext_u64;
+
+//on cpus with bmi2 the pext instruction can be used instead of magic numbers to create a perfect
+//hash of the blocker board, pext gathers the bits of the blocker board selected by the mask of all
+//potential blockers into the lowest bits, so the index is simply the blocker subset compressed
+//https://www.chessprogramming.org/BMI2#PEXTBitboards
+
+//the lookup table is generated at compile time and uses the same layout as the fancy magic
+//lookup, every square needs exactly as many entries for pext indices as for magic indices
+pub static PEXT_LOOKUP: [Bitboard; LOOKUP_SIZE] = pext_lookup();
+
+//the Carry-Rippler trick enumerates the subsets of a set in increasing order which is also the
+//order of their pext indices, so the nth subset is stored at index offset + n
+const fn pext_lookup() -> [Bitboard; LOOKUP_SIZE] {
+    let mut lookup = [EMPTY; LOOKUP_SIZE];
+    let mut offset = 0;
+    let mut i = 0;
+    while i < 128 {
+        let square = Square::new((i % 64) as u8);
+        let is_rook = i < 64;
+        let all_blockers_set = if is_rook {
+            rook_all_blockers_mask(square)
+        } else {
+            bishop_all_blockers_mask(square)
+        };
+        let mut blocker_subset = EMPTY;
+        loop {
+            lookup[offset] = if is_rook {
+                rook_mask(square.bitboard(), blocker_subset)
+            } else {
+                bishop_mask(square.bitboard(), blocker_subset)
+            };
+            offset += 1;
+            //Carry-Rippler
+            blocker_subset = blocker_subset.next_subset_of(all_blockers_set);
+            if blocker_subset.is_empty() {
+                break;
+            }
+        }
+        i += 1;
+    }
+    lookup
+}
+
+//the module is only compiled with bmi2 enabled (-C target-cpu=native or -C target-feature=+bmi2)
+//so the lookups can be inlined, a runtime check and a call to a function with its own target
+//features on every lookup is slower than the magic lookup
+
+#[inline]
+pub fn rook_attacks(square: Square, blockers: Bitboard) -> Bitboard {
+    let entry = &ROOK_MAGICS[square];
+    //safe as the module is only compiled with bmi2
+    let index = unsafe { _pext_u64(blockers.0, entry.mask.0) };
+    PEXT_LOOKUP[entry.offset as usize + index as usize]
+}
+
+#[inline]
+pub fn bishop_attacks(square: Square, blockers: Bitboard) -> Bitboard {
+    let entry = &BISHOP_MAGICS[square];
+    //safe as the module is only compiled with bmi2
+    let index = unsafe { _pext_u64(blockers.0, entry.mask.0) };
+    PEXT_LOOKUP[entry.offset as usize + index as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::magic::{magic_bishop_attacks, magic_rook_attacks};
+
+    #[test]
+    fn pext_matches_magic() {
+        for index in 0..64 {
+            let square = Square::new(index);
+            for (all_blockers_set, is_rook) in [
+                (ROOK_ALL_BLOCKERS_MASK[square], true),
+                (BISHOP_ALL_BLOCKERS_MASK[square], false),
+            ] {
+                let mut blocker_subset = EMPTY;
+                loop {
+                    //squares outside the blocker mask must not change the result
+                    let blockers = blocker_subset | !all_blockers_set;
+                    if is_rook {
+                        assert_eq!(
+                            magic_rook_attacks(square, blockers),
+                            rook_attacks(square, blockers)
+                        );
+                    } else {
+                        assert_eq!(
+                            magic_bishop_attacks(square, blockers),
+                            bishop_attacks(square, blockers)
+                        );
+                    }
+                    blocker_subset = blocker_subset.next_subset_of(all_blockers_set);
+                    if blocker_subset == EMPTY {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,954 @@
+use std::{io::BufRead, time::Duration};
+
+use crate::{
+    board::{Position, WHITE},
+    mve::{Move, NULL_MOVE},
+};
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+//a move of the game tree together with the annotations written after it
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameNode {
+    pub mve: Move,
+    pub parent: usize,
+    //the first child continues the line, the others are variations played instead of it
+    pub children: Vec<usize>,
+    pub nags: Vec<u8>,
+    //comment in front of the first move of a variation
+    pub starting_comment: Option<String>,
+    pub comment: Option<String>,
+    //the [%eval] and [%clk] commands of the comment
+    pub eval: Option<Eval>,
+    pub clock: Option<Duration>,
+}
+
+//an engine evaluation from the point of view of white, written as [%eval 0.25] or [%eval #-3]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Eval {
+    Centipawns(i32),
+    //moves until mate, negative if black mates
+    Mate(i32),
+}
+
+impl Eval {
+    fn parse(text: &str) -> Option<Eval> {
+        match text.strip_prefix('#') {
+            Some(moves) => moves.parse().ok().map(Eval::Mate),
+            None => {
+                let pawns: f64 = text.parse().ok()?;
+                pawns
+                    .is_finite()
+                    .then(|| Eval::Centipawns((pawns * 100.0).round() as i32))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Eval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Eval::Centipawns(centipawns) => {
+                let sign = if centipawns < 0 { "-" } else { "" };
+                let centipawns = centipawns.unsigned_abs();
+                write!(f, "{}{}.{:02}", sign, centipawns / 100, centipawns % 100)
+            }
+            Eval::Mate(moves) => write!(f, "#{}", moves),
+        }
+    }
+}
+
+//clock times are written as hours:minutes:seconds with optional fractions of a second
+fn parse_clock(text: &str) -> Option<Duration> {
+    let mut parts = text.split(':');
+    let (Some(hours), Some(minutes), Some(seconds), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    let hours: u64 = hours.parse().ok()?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    let millis = (hours * 3600 + minutes * 60) * 1000 + (seconds * 1000.0).round() as u64;
+    Some(Duration::from_millis(millis))
+}
+
+fn format_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    let mut text = format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    let millis = clock.subsec_millis();
+    if millis != 0 {
+        let fraction = format!(".{:03}", millis);
+        text.push_str(fraction.trim_end_matches('0'));
+    }
+    text
+}
+
+//a game with its tag pairs and a tree of moves, nodes[0] is the root which stands for the
+//starting position and holds the comment in front of the first move
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub nodes: Vec<GameNode>,
+    pub result: String,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game {
+    pub const ROOT: usize = 0;
+
+    pub fn new() -> Game {
+        Game {
+            tags: Vec::new(),
+            nodes: vec![GameNode {
+                mve: NULL_MOVE,
+                parent: Game::ROOT,
+                children: Vec::new(),
+                nags: Vec::new(),
+                starting_comment: None,
+                comment: None,
+                eval: None,
+                clock: None,
+            }],
+            result: "*".to_string(),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    //replaces the value of the tag or adds the tag at the end
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    //the position of the FEN tag or the standard starting position
+    pub fn start_position(&self) -> Result<Position, String> {
+        match self.tag("FEN") {
+            Some(fen) => Position::try_from_fen(fen).ok_or_else(|| format!("invalid FEN {}", fen)),
+            None => Ok(Position::from_fen(START_FEN)),
+        }
+    }
+
+    //adds the move after the node and returns the new node, the first move added after a node
+    //is its main line
+    pub fn add_move(&mut self, parent: usize, mve: Move) -> usize {
+        let node = self.nodes.len();
+        self.nodes.push(GameNode {
+            mve,
+            parent,
+            children: Vec::new(),
+            nags: Vec::new(),
+            starting_comment: None,
+            comment: None,
+            eval: None,
+            clock: None,
+        });
+        self.nodes[parent].children.push(node);
+        node
+    }
+
+    pub fn mainline(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut node = Game::ROOT;
+        while let Some(&child) = self.nodes[node].children.first() {
+            moves.push(self.nodes[child].mve);
+            node = child;
+        }
+        moves
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+//the line of moves the parser is in, the main line or a variation
+struct Line {
+    node: usize,
+    //the position before the move of the node, where a variation of the move starts
+    before: Position,
+    pos: Position,
+    //a variation without moves yet, comments belong to its first move
+    fresh: bool,
+}
+
+//collects one game from the tokens of the movetext
+struct GameBuilder {
+    game: Game,
+    lines: Vec<Line>,
+    //inside a brace comment which can span several lines, its text is collected in the buffer
+    in_comment: bool,
+    comment_buffer: String,
+    starting_comment: Option<String>,
+    has_result: bool,
+    //after an error the rest of the game is skipped
+    error: Option<PgnError>,
+}
+
+//adds the words of the text to the comment, line breaks and runs of whitespace become one space
+fn append_comment(comment: &mut Option<String>, text: &str) {
+    for word in text.split_whitespace() {
+        match comment {
+            Some(comment) => {
+                comment.push(' ');
+                comment.push_str(word);
+            }
+            None => *comment = Some(word.to_string()),
+        }
+    }
+}
+
+//moves the [%eval] and [%clk] commands of a comment into the node, other commands like
+//arrows stay in the comment text
+fn add_node_comment(node: &mut GameNode, text: &str) {
+    let mut rest = text;
+    while let Some(start) = rest.find("[%") {
+        let Some(length) = rest[start..].find(']') else {
+            break;
+        };
+        let command = &rest[start + 2..start + length];
+        let (name, value) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        let known = match name {
+            "eval" => Eval::parse(value.trim()).map(|eval| node.eval = Some(eval)),
+            "clk" => parse_clock(value.trim()).map(|clock| node.clock = Some(clock)),
+            _ => None,
+        };
+        let end = if known.is_some() {
+            start
+        } else {
+            start + length + 1
+        };
+        append_comment(&mut node.comment, &rest[..end]);
+        rest = &rest[start + length + 1..];
+    }
+    append_comment(&mut node.comment, rest);
+}
+
+//the value of a tag with the backslash escapes of quotes and backslashes removed
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut escaped = false;
+    for letter in value.chars() {
+        if letter == '\\' && !escaped {
+            escaped = true;
+        } else {
+            unescaped.push(letter);
+            escaped = false;
+        }
+    }
+    unescaped
+}
+
+//reads a tag pair like [Event "Casual game"] at the start of the text,
+//returns the name, the escaped value and the length of the tag
+fn parse_tag(text: &str) -> Option<(&str, &str, usize)> {
+    let inner = text.strip_prefix('[')?;
+    let name_end = inner.find(|c: char| c.is_whitespace() || c == '"')?;
+    let name = &inner[..name_end];
+    let after_name = inner[name_end..].trim_start();
+    let value_text = after_name.strip_prefix('"')?;
+    let mut escaped = false;
+    let value_end = value_text.char_indices().find_map(|(index, letter)| {
+        let end = letter == '"' && !escaped;
+        escaped = letter == '\\' && !escaped;
+        end.then_some(index)
+    })?;
+    let after_value = &value_text[value_end + 1..];
+    let close = after_value.find(']')?;
+    let length = text.len() - after_value.len() + close + 1;
+    Some((name, &value_text[..value_end], length))
+}
+
+//the numeric annotation glyph of a move suffix like "!?"
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+fn is_delimiter(letter: char) -> bool {
+    letter.is_whitespace() || "(){};[$".contains(letter)
+}
+
+impl GameBuilder {
+    fn new() -> GameBuilder {
+        GameBuilder {
+            game: Game::new(),
+            lines: Vec::new(),
+            in_comment: false,
+            comment_buffer: String::new(),
+            starting_comment: None,
+            has_result: false,
+            error: None,
+        }
+    }
+
+    fn started(&self) -> bool {
+        !self.game.tags.is_empty() || !self.lines.is_empty()
+    }
+
+    fn finish(mut self) -> Result<Game, PgnError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        if !self.has_result {
+            if let Some(result) = self.game.tag("Result") {
+                self.game.result = result.to_string();
+            }
+        }
+        Ok(self.game)
+    }
+
+    //the movetext starts at the first token after the tags
+    fn line(&mut self) -> Result<&mut Line, String> {
+        if self.lines.is_empty() {
+            let pos = self.game.start_position()?;
+            self.lines.push(Line {
+                node: Game::ROOT,
+                before: pos.clone(),
+                pos,
+                fresh: false,
+            });
+        }
+        Ok(self.lines.last_mut().unwrap())
+    }
+
+    fn add_comment(&mut self, text: &str) -> Result<(), String> {
+        let line = self.line()?;
+        let (node, fresh) = (line.node, line.fresh);
+        if fresh {
+            append_comment(&mut self.starting_comment, text);
+        } else {
+            add_node_comment(&mut self.game.nodes[node], text);
+        }
+        Ok(())
+    }
+
+    fn add_nag(&mut self, nag: u8) -> Result<(), String> {
+        let line = self.line()?;
+        if line.node == Game::ROOT || line.fresh {
+            return Err(format!("annotation ${} without a move", nag));
+        }
+        let node = line.node;
+        self.game.nodes[node].nags.push(nag);
+        Ok(())
+    }
+
+    fn add_move(&mut self, token: &str) -> Result<(), String> {
+        let split = token.trim_end_matches(['!', '?']).len();
+        let (san, suffix) = token.split_at(split);
+        let line = self.line()?;
+        let mve = line.pos.parse_san(san)?;
+        let parent = line.node;
+        let node = self.game.add_move(parent, mve);
+
+        let line = self.lines.last_mut().unwrap();
+        line.before = line.pos.clone();
+        line.pos.make_move(mve);
+        line.node = node;
+        line.fresh = false;
+        self.game.nodes[node].starting_comment = self.starting_comment.take();
+        self.game.nodes[node].nags.extend(suffix_nag(suffix));
+        Ok(())
+    }
+
+    //a variation replaces the last move of the current line
+    fn start_variation(&mut self) -> Result<(), String> {
+        let line = self.line()?;
+        if line.node == Game::ROOT || line.fresh {
+            return Err("variation without a move to replace".to_string());
+        }
+        let (node, before) = (line.node, line.before.clone());
+        self.lines.push(Line {
+            node: self.game.nodes[node].parent,
+            before: before.clone(),
+            pos: before,
+            fresh: true,
+        });
+        Ok(())
+    }
+
+    fn end_variation(&mut self) -> Result<(), String> {
+        if self.lines.len() < 2 {
+            return Err("unmatched )".to_string());
+        }
+        self.lines.pop();
+        self.starting_comment = None;
+        Ok(())
+    }
+
+    //reads the tokens of one line of text, returns the length of the text which belongs to this
+    //game when the game ends in the line
+    fn feed(&mut self, text: &str, line_number: usize) -> Option<usize> {
+        let mut rest = text;
+        loop {
+            if self.in_comment {
+                self.comment_buffer.push(' ');
+                match rest.find('}') {
+                    Some(end) => {
+                        self.comment_buffer.push_str(&rest[..end]);
+                        rest = &rest[end + 1..];
+                        self.in_comment = false;
+                        let comment = std::mem::take(&mut self.comment_buffer);
+                        let result = self.add_comment(&comment);
+                        self.comment_buffer = comment;
+                        self.check(result, line_number);
+                    }
+                    None => {
+                        self.comment_buffer.push_str(rest);
+                        return None;
+                    }
+                }
+                continue;
+            }
+
+            //a line starting with % is escaped from parsing
+            if rest.len() == text.len() && rest.starts_with('%') {
+                return None;
+            }
+            rest = rest.trim_start();
+            let first = rest.chars().next()?;
+            let consumed = text.len() - rest.len();
+            match first {
+                '[' => {
+                    //a tag after the movetext belongs to the next game
+                    if !self.lines.is_empty() {
+                        return Some(consumed);
+                    }
+                    match parse_tag(rest) {
+                        Some((name, value, length)) => {
+                            self.game.tags.push((name.to_string(), unescape(value)));
+                            rest = &rest[length..];
+                        }
+                        None => {
+                            let error = Err(format!("invalid tag {}", rest.trim_end()));
+                            self.check(error, line_number);
+                            return None;
+                        }
+                    }
+                }
+                '{' => {
+                    self.in_comment = true;
+                    self.comment_buffer.clear();
+                    rest = &rest[1..];
+                }
+                ';' => {
+                    let result = self.add_comment(&rest[1..]);
+                    self.check(result, line_number);
+                    return None;
+                }
+                _ => {
+                    let length = if first == '(' || first == ')' {
+                        1
+                    } else {
+                        rest[1..]
+                            .find(is_delimiter)
+                            .map_or(rest.len(), |end| end + 1)
+                    };
+                    let token = &rest[..length];
+                    rest = &rest[length..];
+                    if self.token(token, line_number) {
+                        return Some(text.len() - rest.len());
+                    }
+                }
+            }
+        }
+    }
+
+    //handles a token of the movetext, returns true when the token ends the game
+    fn token(&mut self, token: &str, line_number: usize) -> bool {
+        if matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+            if self.lines.len() <= 1 || self.error.is_some() {
+                self.game.result = token.to_string();
+                self.has_result = true;
+                return true;
+            }
+            return false;
+        }
+        if self.error.is_some() {
+            return false;
+        }
+        let result = match token {
+            "(" => self.start_variation(),
+            ")" => self.end_variation(),
+            "e.p." => Ok(()),
+            _ if token.starts_with('$') => match token[1..].parse() {
+                Ok(nag) => self.add_nag(nag),
+                Err(_) => Err(format!("invalid annotation {}", token)),
+            },
+            _ => {
+                //move numbers like "12." or "12..." can be followed by the move without a space
+                let number_end = token.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+                let san = match token[number_end..].strip_prefix('.') {
+                    Some(after_number) => after_number.trim_start_matches('.'),
+                    None => token,
+                };
+                if san.is_empty() {
+                    self.line().map(|_| ())
+                } else {
+                    self.add_move(san)
+                }
+            }
+        };
+        self.check(result, line_number);
+        false
+    }
+
+    //keeps the first error of the game together with its line
+    fn check(&mut self, result: Result<(), String>, line_number: usize) {
+        if let (Err(message), None) = (result, &self.error) {
+            self.error = Some(PgnError {
+                line: line_number,
+                message,
+            });
+        }
+    }
+}
+
+//reads the games of a PGN file one after another, lines are read into a reused buffer
+pub struct PgnReader<R: BufRead> {
+    input: R,
+    line: String,
+    //the part of the line which has already been read by earlier games
+    offset: usize,
+    line_number: usize,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(input: R) -> PgnReader<R> {
+        PgnReader {
+            input,
+            line: String::new(),
+            offset: 0,
+            line_number: 0,
+        }
+    }
+
+    //the next game or None at the end of the input, a game with an illegal move returns
+    //an error with the line of the move and the reader continues with the next game
+    pub fn next_game(&mut self) -> Option<Result<Game, PgnError>> {
+        let mut builder = GameBuilder::new();
+        loop {
+            if self.offset >= self.line.len() {
+                self.line.clear();
+                self.offset = 0;
+                match self.input.read_line(&mut self.line) {
+                    Ok(0) => break,
+                    Ok(_) => self.line_number += 1,
+                    Err(error) => {
+                        return Some(Err(PgnError {
+                            line: self.line_number + 1,
+                            message: error.to_string(),
+                        }))
+                    }
+                }
+                if self.line_number == 1 {
+                    let without_bom = self.line.trim_start_matches('\u{feff}').len();
+                    self.offset = self.line.len() - without_bom;
+                }
+            }
+            let text = &self.line[self.offset..];
+            match builder.feed(text, self.line_number) {
+                Some(length) => {
+                    self.offset += length;
+                    return Some(builder.finish());
+                }
+                None => self.offset = self.line.len(),
+            }
+        }
+        if builder.started() {
+            Some(builder.finish())
+        } else {
+            None
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_game()
+    }
+}
+
+//the tags every exported game starts with and the values used for missing tags
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+const MAX_LINE_LENGTH: usize = 80;
+
+//collects the tokens of the movetext into lines of at most 80 characters
+struct MovetextWriter {
+    text: String,
+    line_length: usize,
+    //an opening parenthesis waiting for the first token of its variation
+    open_variation: bool,
+}
+
+impl MovetextWriter {
+    fn token(&mut self, token: &str) {
+        let prefix = if self.open_variation { "(" } else { "" };
+        self.open_variation = false;
+        let length = prefix.len() + token.len();
+        if self.line_length == 0 {
+        } else if self.line_length + 1 + length > MAX_LINE_LENGTH {
+            self.text.push('\n');
+            self.line_length = 0;
+        } else {
+            self.text.push(' ');
+            self.line_length += 1;
+        }
+        self.text.push_str(prefix);
+        self.text.push_str(token);
+        self.line_length += length;
+    }
+
+    fn close_variation(&mut self) {
+        if self.line_length + 1 > MAX_LINE_LENGTH {
+            self.text.push('\n');
+            self.line_length = 0;
+        }
+        self.text.push(')');
+        self.line_length += 1;
+    }
+
+    //writes the comment word by word so long comments can be wrapped as well
+    //a closing brace would end the comment early, so it is left out
+    fn comment(&mut self, comment: &str) {
+        let comment = comment.replace('}', "");
+        let mut words = comment.split_whitespace().peekable();
+        let mut first = true;
+        while let Some(word) = words.next() {
+            let open = if first { "{" } else { "" };
+            let close = if words.peek().is_none() { "}" } else { "" };
+            self.token(&format!("{}{}{}", open, word, close));
+            first = false;
+        }
+    }
+
+    //writes the comment of the node with its commands, returns whether anything was written
+    fn node_comment(&mut self, node: &GameNode) -> bool {
+        let mut comment = String::new();
+        if let Some(eval) = node.eval {
+            comment.push_str(&format!("[%eval {}] ", eval));
+        }
+        if let Some(clock) = node.clock {
+            comment.push_str(&format!("[%clk {}] ", format_clock(clock)));
+        }
+        comment.push_str(node.comment.as_deref().unwrap_or_default());
+        self.comment(&comment);
+        !comment.trim().is_empty()
+    }
+}
+
+fn write_tag(pgn: &mut String, name: &str, value: &str) {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+}
+
+impl Game {
+    //the game in PGN export format, the tags of the Seven Tag Roster come first followed by the
+    //other tags, the movetext is written in SAN and wrapped at 80 columns
+    //the moves of the tree have to be legal, an invalid FEN tag is an error
+    pub fn to_pgn(&self) -> Result<String, String> {
+        let pos = self.start_position()?;
+        let mut pgn = String::new();
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = if name == "Result" {
+                &self.result
+            } else {
+                self.tag(name).unwrap_or(default)
+            };
+            write_tag(&mut pgn, name, value);
+        }
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER
+                .iter()
+                .any(|(roster_name, _)| roster_name == name)
+            {
+                write_tag(&mut pgn, name, value);
+            }
+        }
+        pgn.push('\n');
+
+        let mut writer = MovetextWriter {
+            text: String::new(),
+            line_length: 0,
+            open_variation: false,
+        };
+        writer.node_comment(&self.nodes[Game::ROOT]);
+        self.write_line(&mut writer, &pos, Game::ROOT, true);
+        writer.token(&self.result);
+        pgn.push_str(&writer.text);
+        pgn.push('\n');
+        Ok(pgn)
+    }
+
+    //writes the moves after the node with the variations of each move after the move,
+    //black moves get a move number at the start of a line and after comments and variations
+    fn write_line(
+        &self,
+        writer: &mut MovetextWriter,
+        pos: &Position,
+        node: usize,
+        force_number: bool,
+    ) {
+        let Some((&main, variations)) = self.nodes[node].children.split_first() else {
+            return;
+        };
+        let mut interrupted = self.write_move(writer, pos, main, force_number);
+        for &variation in variations {
+            writer.open_variation = true;
+            let variation_interrupted = self.write_move(writer, pos, variation, true);
+            let mut pos_after = pos.clone();
+            pos_after.make_move(self.nodes[variation].mve);
+            self.write_line(writer, &pos_after, variation, variation_interrupted);
+            writer.close_variation();
+            interrupted = true;
+        }
+        let mut pos_after = pos.clone();
+        pos_after.make_move(self.nodes[main].mve);
+        self.write_line(writer, &pos_after, main, interrupted);
+    }
+
+    //writes the move with its annotations, returns whether a comment follows the move
+    fn write_move(
+        &self,
+        writer: &mut MovetextWriter,
+        pos: &Position,
+        node: usize,
+        mut force_number: bool,
+    ) -> bool {
+        let node = &self.nodes[node];
+        if let Some(comment) = &node.starting_comment {
+            writer.comment(comment);
+            force_number = true;
+        }
+        //the move number is written in the same token as the move so a line never ends between them
+        let san = pos.to_san(node.mve);
+        if pos.color_to_move == WHITE {
+            writer.token(&format!("{}. {}", pos.full_moves, san));
+        } else if force_number {
+            writer.token(&format!("{}... {}", pos.full_moves, san));
+        } else {
+            writer.token(&san);
+        }
+        for nag in &node.nags {
+            writer.token(&format!("${}", nag));
+        }
+        writer.node_comment(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAMES: &str = r#"[Event "Casual \"blitz\" game"]
+[Site "?"]
+[Date "2024.01.02"]
+[Round "-"]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+{Opening comment} 1.e4 e5 2. Nf3 $1 Nc6 {A comment
+over two lines} (2... d6 3. d4 (3. Bc4 Be7) 3... exd4; the Philidor
+) 3. Bc4!? Nf6?? 4. Ng5 d5 5. exd5 Nxd5 6. Nxf7 Kxf7 7. Qf3+ Ke6 8. Nc3 1-0
+
+%this line is escaped
+[Event "From a FEN"]
+[SetUp "1"]
+[FEN "4k3/8/8/3pP3/8/8/8/4K2R w K d6 0 1"]
+
+1. exd6 e.p. Kd7 2. 0-0 *
+
+[Event "Illegal move"]
+
+1. e4 e5 2. Nf3 Nc6
+3. Bc4 Bc5 4. Qxf7 Nf6 0-1
+[Event "After the error"] 1. d4 d5 1/2-1/2
+"#;
+
+    #[test]
+    fn read_games() {
+        let mut reader = PgnReader::new(GAMES.as_bytes());
+
+        let game = reader.next_game().unwrap().unwrap();
+        assert_eq!(Some("Casual \"blitz\" game"), game.tag("Event"));
+        assert_eq!(7, game.tags.len());
+        assert_eq!("1-0", game.result);
+        let mainline = game.mainline();
+        assert_eq!(15, mainline.len());
+        let mut pos = game.start_position().unwrap();
+        for mve in mainline {
+            pos.make_move(mve);
+        }
+        assert_eq!(
+            "r1bq1b1r/ppp3pp/2n1k3/3np3/2B5/2N2Q2/PPPP1PPP/R1B1K2R b KQ - 3 8",
+            pos.fen()
+        );
+
+        let root = &game.nodes[Game::ROOT];
+        assert_eq!(Some("Opening comment".to_string()), root.comment);
+        let e4 = root.children[0];
+        let e5 = game.nodes[e4].children[0];
+        let nf3 = game.nodes[e5].children[0];
+        assert_eq!(vec![1], game.nodes[nf3].nags);
+        //the variation 2... d6 replaces 2... Nc6 and has a variation of its own
+        let [nc6, d6] = game.nodes[nf3].children[..] else {
+            panic!("{:?}", game.nodes[nf3].children);
+        };
+        assert_eq!(
+            Some("A comment over two lines".to_string()),
+            game.nodes[nc6].comment
+        );
+        let d4 = game.nodes[d6].children[0];
+        assert_eq!(2, game.nodes[d6].children.len());
+        let exd4 = game.nodes[d4].children[0];
+        assert_eq!(Some("the Philidor".to_string()), game.nodes[exd4].comment);
+        let bc4 = game.nodes[nc6].children[0];
+        assert_eq!(vec![5], game.nodes[bc4].nags);
+        assert_eq!(vec![4], game.nodes[game.nodes[bc4].children[0]].nags);
+
+        let game = reader.next_game().unwrap().unwrap();
+        assert_eq!("*", game.result);
+        assert_eq!(3, game.mainline().len());
+
+        let error = reader.next_game().unwrap().unwrap_err();
+        assert_eq!(23, error.line);
+        assert_eq!("illegal move Qxf7", error.message);
+
+        let game = reader.next_game().unwrap().unwrap();
+        assert_eq!(Some("After the error"), game.tag("Event"));
+        assert_eq!("1/2-1/2", game.result);
+        assert_eq!(2, game.mainline().len());
+        assert_eq!(None, reader.next_game());
+    }
+
+    #[test]
+    fn invalid_fen_tag() {
+        let pgn = "[FEN \"8/8/8 w - - 0 1\"]\n\n1. e4 *\n";
+        let error = PgnReader::new(pgn.as_bytes()).next_game().unwrap();
+        assert_eq!(
+            Err(PgnError {
+                line: 3,
+                message: "invalid FEN 8/8/8 w - - 0 1".to_string()
+            }),
+            error
+        );
+    }
+
+    #[test]
+    fn write_games() {
+        let pgn = "[White \"Engine \\\"A\\\"\"]\n[Annotator \"x\"]\n\n{Start} 1. e4 \
+                   {[%eval 0.25] [%clk 0:03:00] book} e5 (1... c5 $1 2. Nf3) \
+                   2. Nf3 {[%clk 0:02:59.5] [%csl Ge5]} Nc6 1-0";
+        let game = PgnReader::new(pgn.as_bytes()).next_game().unwrap().unwrap();
+        let e4 = &game.nodes[game.nodes[Game::ROOT].children[0]];
+        assert_eq!(Some(Eval::Centipawns(25)), e4.eval);
+        assert_eq!(Some(Duration::from_secs(180)), e4.clock);
+        assert_eq!(Some("book".to_string()), e4.comment);
+        assert_eq!(
+            "[Event \"?\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Engine \\\"A\\\"\"]\n\
+             [Black \"?\"]\n\
+             [Result \"1-0\"]\n\
+             [Annotator \"x\"]\n\
+             \n\
+             {Start} 1. e4 {[%eval 0.25] [%clk 0:03:00] book} 1... e5 (1... c5 $1 2. Nf3)\n\
+             2. Nf3 {[%clk 0:02:59.5] [%csl Ge5]} 2... Nc6 1-0\n",
+            game.to_pgn().unwrap()
+        );
+
+        //every game is read back into the same tree, the Seven Tag Roster is added when missing
+        for game in PgnReader::new(GAMES.as_bytes()).filter_map(|game| game.ok()) {
+            let pgn = game.to_pgn().unwrap();
+            assert!(pgn.lines().all(|line| line.len() <= 80), "{}", pgn);
+            let read_back = PgnReader::new(pgn.as_bytes()).next_game().unwrap().unwrap();
+            assert_eq!(game.nodes, read_back.nodes);
+            assert_eq!(game.result, read_back.result);
+            assert_eq!(game.tag("FEN"), read_back.tag("FEN"));
+            if game.tags.len() == 7 {
+                assert_eq!(game, read_back);
+            }
+        }
+
+        //a game built without the reader is read back the same, a closing brace can not be
+        //written inside a comment so it is left out
+        let mut game = Game::new();
+        let mut pos = Position::startpos();
+        let mut node = Game::ROOT;
+        for san in [
+            "e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O", "Be7",
+        ] {
+            let mve = pos.parse_san(san).unwrap();
+            node = game.add_move(node, mve);
+            pos.make_move(mve);
+            game.nodes[node].comment = Some(format!("the {{Ruy}} Lopez after {}", san));
+        }
+        game.result = "*".to_string();
+        let pgn = game.to_pgn().unwrap();
+        assert!(pgn.lines().all(|line| line.len() <= 80), "{}", pgn);
+        assert!(!pgn.lines().any(|line| line.ends_with('.')), "{}", pgn);
+        let read_back = PgnReader::new(pgn.as_bytes()).next_game().unwrap().unwrap();
+        assert_eq!(game.mainline(), read_back.mainline());
+        assert_eq!(
+            Some("the {Ruy Lopez after Be7".to_string()),
+            read_back.nodes[node].comment
+        );
+        assert_eq!(
+            Some(Eval::Mate(-3)),
+            Eval::parse(&Eval::Mate(-3).to_string())
+        );
+        assert_eq!("-0.05", Eval::Centipawns(-5).to_string());
+    }
+}
@@ -1,210 +1,217 @@
 use crate::{
-    board::{Bitboard, Color, Position, Square, NOT_ON_A_FILE, NOT_ON_H_FILE, RANK, WHITE},
-    magic::{BISHOP_MAGIC_SHIFT, MAGIC, ROOK_MAGIC_SHIFT},
-    masks::{BISHOP_ALL_BLOCKERS_MASK, KING_MASK, KNIGHT_MASK, ROOK_ALL_BLOCKERS_MASK},
+    bitboard::Bitboard,
+    board::{Color, Position, Square, BLACK, NOT_ON_A_FILE, NOT_ON_H_FILE, RANK, WHITE},
+    magic::{bishop_attacks, rook_attacks},
+    masks::{KING_MASK, KNIGHT_MASK},
     mve::Move,
+    side::Side,
 };
 
-pub const KNIGHT: usize = 0;
-pub const BISHOP: usize = 1;
-pub const ROOK: usize = 2;
-pub const QUEEN: usize = 3;
-pub const PAWN: usize = 4;
-pub const KING: usize = 5;
-pub const NONE: u8 = 6;
+//the kind of a piece without its color, Position::pieces stores the kind on every square
+//the order of the variants is the order of the piece boards and of all tables with one entry per kind
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PieceKind {
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    Pawn,
+    King,
+}
 
-impl Position {
-    //Pawn moves are handled by directly shifting bits (no lookup)
+impl PieceKind {
+    pub const ALL: [PieceKind; 6] = [
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+        PieceKind::Pawn,
+        PieceKind::King,
+    ];
+
+    //index into the piece boards and tables with one entry per kind
     #[inline]
-    pub fn w_pawn_forward_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        (*bitboard_square << 8) & !self.blocker_board & self.checked_squares
+    pub const fn index(self) -> usize {
+        self as usize
     }
+}
+
+//the piece boards and tables with one entry per kind are indexed by the kind
+impl<T> std::ops::Index<PieceKind> for [T; 6] {
+    type Output = T;
+
     #[inline]
-    pub fn w_pawn_doubleforward_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        ((*bitboard_square & (RANK[1])) << 16)
-            & !(self.blocker_board | (self.blocker_board << 8))
-            & self.checked_squares
+    fn index(&self, kind: PieceKind) -> &T {
+        &self[kind.index()]
     }
+}
+
+impl<T> std::ops::IndexMut<PieceKind> for [T; 6] {
     #[inline]
-    pub fn w_pawn_capture_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        (((*bitboard_square & NOT_ON_H_FILE) << 7) | ((*bitboard_square & NOT_ON_A_FILE) << 9))
-            & self.b_board
-            & self.checked_squares
+    fn index_mut(&mut self, kind: PieceKind) -> &mut T {
+        &mut self[kind.index()]
     }
+}
+
+//a piece of a color
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Piece {
+    kind: PieceKind,
+    color: Color,
+}
+
+impl Piece {
     #[inline]
-    pub fn w_pawn_en_passant_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        //subtle but really important detail is that the check mask must be shifted on this
-        //particular edge case to be able to remove checking pawns with en passant
-        ((((*bitboard_square & NOT_ON_H_FILE) << 7) | ((*bitboard_square & NOT_ON_A_FILE) << 9))
-            & self.en_passant_target_square)
-            & (self.checked_squares << 8)
+    pub const fn new(kind: PieceKind, color: Color) -> Piece {
+        Piece { kind, color }
     }
+
     #[inline]
-    pub fn b_pawn_forward_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        (*bitboard_square >> 8) & !self.blocker_board & self.checked_squares
+    pub const fn kind(self) -> PieceKind {
+        self.kind
     }
+
     #[inline]
-    pub fn b_pawn_doubleforward_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        ((*bitboard_square & (RANK[6])) >> 16)
-            & !(self.blocker_board | (self.blocker_board >> 8))
-            & self.checked_squares
+    pub const fn color(self) -> Color {
+        self.color
     }
-    #[inline]
-    pub fn b_pawn_capture_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        (((*bitboard_square & NOT_ON_H_FILE) >> 9) | ((*bitboard_square & NOT_ON_A_FILE) >> 7))
-            & self.w_board
-            & self.checked_squares
+
+    //the letter used in FEN, uppercase for white and lowercase for black
+    pub fn from_char(letter: char) -> Option<Piece> {
+        let kind = match letter.to_ascii_lowercase() {
+            'n' => PieceKind::Knight,
+            'b' => PieceKind::Bishop,
+            'r' => PieceKind::Rook,
+            'q' => PieceKind::Queen,
+            'p' => PieceKind::Pawn,
+            'k' => PieceKind::King,
+            _ => return None,
+        };
+        let color = if letter.is_ascii_uppercase() {
+            WHITE
+        } else {
+            BLACK
+        };
+        Some(Piece::new(kind, color))
     }
-    #[inline]
-    pub fn b_pawn_en_passant_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        //subtle but really important detail is that the check mask must be shifted on this
-        //particular edge case to be able to remove checking pawns with en passant
-        ((((*bitboard_square & NOT_ON_H_FILE) >> 9) | ((*bitboard_square & NOT_ON_A_FILE) >> 7))
-            & self.en_passant_target_square)
-            & (self.checked_squares >> 8)
+
+    pub fn to_char(self) -> char {
+        let letter = b"nbrqpk"[self.kind.index()] as char;
+        if self.color == WHITE {
+            letter.to_ascii_uppercase()
+        } else {
+            letter
+        }
+    }
+}
+
+impl std::fmt::Debug for Piece {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_char())
     }
+}
+
+impl Position {
+    pub fn piece_on(&self, square: Square) -> Option<Piece> {
+        let kind = self.pieces[square]?;
+        if self.w_board.contains(square) {
+            Some(Piece::new(kind, WHITE))
+        } else {
+            Some(Piece::new(kind, BLACK))
+        }
+    }
+
+    //Pawn moves are handled by directly shifting bits (no lookup)
     #[inline]
-    pub fn w_pinned_pawn_forward_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        self.w_pawn_forward_mask(bitboard_square) & self.orthogonal_pin
+    pub fn pawn_forward_mask<S: Side>(&self, bitboard_square: &Bitboard) -> Bitboard {
+        S::forward(*bitboard_square, 1) & !self.blocker_board & self.checked_squares
     }
     #[inline]
-    pub fn w_pinned_pawn_doubleforward_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        self.w_pawn_doubleforward_mask(bitboard_square) & self.orthogonal_pin
+    pub fn pawn_doubleforward_mask<S: Side>(&self, bitboard_square: &Bitboard) -> Bitboard {
+        S::forward(*bitboard_square & S::DOUBLE_PUSH_RANK, 2)
+            & !(self.blocker_board | S::forward(self.blocker_board, 1))
+            & self.checked_squares
     }
     #[inline]
-    pub fn w_pinned_pawn_capture_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        self.w_pawn_capture_mask(bitboard_square) & self.diagonal_pin
+    pub fn pawn_capture_mask<S: Side>(&self, bitboard_square: &Bitboard) -> Bitboard {
+        S::pawn_attacks(bitboard_square) & S::Opponent::board(self) & self.checked_squares
     }
     #[inline]
-    pub fn w_pinned_pawn_en_passant_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        self.w_pawn_en_passant_mask(bitboard_square) & self.diagonal_pin
+    pub fn pawn_en_passant_mask<S: Side>(&self, bitboard_square: &Bitboard) -> Bitboard {
+        //subtle but really important detail is that the check mask must be shifted on this
+        //particular edge case to be able to remove checking pawns with en passant
+        (S::pawn_attacks(bitboard_square) & self.en_passant_target_square)
+            & S::forward(self.checked_squares, 1)
     }
     #[inline]
-    pub fn b_pinned_pawn_forward_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        self.b_pawn_forward_mask(bitboard_square) & self.orthogonal_pin
+    pub fn pinned_pawn_forward_mask<S: Side>(&self, bitboard_square: &Bitboard) -> Bitboard {
+        self.pawn_forward_mask::<S>(bitboard_square) & self.orthogonal_pin
     }
     #[inline]
-    pub fn b_pinned_pawn_doubleforward_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        self.b_pawn_doubleforward_mask(bitboard_square) & self.orthogonal_pin
+    pub fn pinned_pawn_doubleforward_mask<S: Side>(&self, bitboard_square: &Bitboard) -> Bitboard {
+        self.pawn_doubleforward_mask::<S>(bitboard_square) & self.orthogonal_pin
     }
     #[inline]
-    pub fn b_pinned_pawn_capture_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        self.b_pawn_capture_mask(bitboard_square) & self.diagonal_pin
+    pub fn pinned_pawn_capture_mask<S: Side>(&self, bitboard_square: &Bitboard) -> Bitboard {
+        self.pawn_capture_mask::<S>(bitboard_square) & self.diagonal_pin
     }
     #[inline]
-    pub fn b_pinned_pawn_en_passant_mask(&self, bitboard_square: &Bitboard) -> Bitboard {
-        self.b_pawn_en_passant_mask(bitboard_square) & self.diagonal_pin
+    pub fn pinned_pawn_en_passant_mask<S: Side>(&self, bitboard_square: &Bitboard) -> Bitboard {
+        self.pawn_en_passant_mask::<S>(bitboard_square) & self.diagonal_pin
     }
 
     //when calculating all seen squares by pawns it is possible to calculate all attacks at once
     //by shifting the full pawn bitboard
     #[inline]
-    pub fn seen_by_w_pawns(&self) -> Bitboard {
-        ((self.w_piece_board[PAWN] & NOT_ON_H_FILE) << 7)
-            | ((self.w_piece_board[PAWN] & NOT_ON_A_FILE) << 9)
-    }
-    #[inline]
-    pub fn seen_by_b_pawns(&self) -> Bitboard {
-        ((self.b_piece_board[PAWN] & NOT_ON_H_FILE) >> 9)
-            | ((self.b_piece_board[PAWN] & NOT_ON_A_FILE) >> 7)
+    pub fn seen_by_pawns<S: Side>(&self) -> Bitboard {
+        S::pawn_attacks(&S::piece_board(self)[PieceKind::Pawn])
     }
 
     //All other pieces use lookups
 
     //Sliding pieces
     pub fn seen_by_rook(&self, square: Square) -> Bitboard {
-        unsafe {
-            let rook_blocker_board = self.blocker_board & ROOK_ALL_BLOCKERS_MASK[square as usize];
-            let magic_number = MAGIC.rook_magic[square as usize];
-            let lookup = &MAGIC.rook_lookup[square as usize];
-            let magic_index = rook_blocker_board.wrapping_mul(magic_number) >> ROOK_MAGIC_SHIFT;
-            lookup[magic_index as usize]
-        }
+        rook_attacks(square, self.blocker_board)
     }
 
     pub fn seen_by_rook_custom_blocker(&self, square: Square, blocker: Bitboard) -> Bitboard {
-        unsafe {
-            let rook_blocker_board = blocker & ROOK_ALL_BLOCKERS_MASK[square as usize];
-            let magic_number = MAGIC.rook_magic[square as usize];
-            let lookup = &MAGIC.rook_lookup[square as usize];
-            let magic_index = rook_blocker_board.wrapping_mul(magic_number) >> ROOK_MAGIC_SHIFT;
-            lookup[magic_index as usize]
-        }
-    }
-
-    #[inline]
-    pub fn w_rook_moves(&self, square: Square) -> Bitboard {
-        self.seen_by_rook(square) & !self.w_board & self.checked_squares
+        rook_attacks(square, blocker)
     }
 
     #[inline]
-    pub fn b_rook_moves(&self, square: Square) -> Bitboard {
-        self.seen_by_rook(square) & !self.b_board & self.checked_squares
+    pub fn rook_moves<S: Side>(&self, square: Square) -> Bitboard {
+        self.seen_by_rook(square) & !S::board(self) & self.checked_squares
     }
 
     pub fn seen_by_bishop(&self, square: Square) -> Bitboard {
-        unsafe {
-            let bishop_blocker_board =
-                self.blocker_board & BISHOP_ALL_BLOCKERS_MASK[square as usize];
-            let magic_number = MAGIC.bishop_magic[square as usize];
-            let lookup = &MAGIC.bishop_lookup[square as usize];
-            let magic_index = bishop_blocker_board.wrapping_mul(magic_number) >> BISHOP_MAGIC_SHIFT;
-            lookup[magic_index as usize]
-        }
+        bishop_attacks(square, self.blocker_board)
     }
     pub fn seen_by_bishop_custom_blocker(&self, square: Square, blocker: Bitboard) -> Bitboard {
-        unsafe {
-            let bishop_blocker_board = blocker & BISHOP_ALL_BLOCKERS_MASK[square as usize];
-            let magic_number = MAGIC.bishop_magic[square as usize];
-            let lookup = &MAGIC.bishop_lookup[square as usize];
-            let magic_index = bishop_blocker_board.wrapping_mul(magic_number) >> BISHOP_MAGIC_SHIFT;
-            lookup[magic_index as usize]
-        }
+        bishop_attacks(square, blocker)
     }
 
     #[inline]
-    pub fn w_bishop_moves(&self, square: Square) -> Bitboard {
-        self.seen_by_bishop(square) & !self.w_board & self.checked_squares
-    }
-
-    #[inline]
-    pub fn b_bishop_moves(&self, square: Square) -> Bitboard {
-        self.seen_by_bishop(square) & !self.b_board & self.checked_squares
+    pub fn bishop_moves<S: Side>(&self, square: Square) -> Bitboard {
+        self.seen_by_bishop(square) & !S::board(self) & self.checked_squares
     }
 
     //Sliding pieces pinned in the same direction as they move
     #[inline]
-    pub fn w_pinned_rook_moves(&self, square: Square) -> Bitboard {
-        self.w_rook_moves(square) & self.orthogonal_pin
+    pub fn pinned_rook_moves<S: Side>(&self, square: Square) -> Bitboard {
+        self.rook_moves::<S>(square) & self.orthogonal_pin
     }
     #[inline]
-    pub fn w_pinned_bishop_moves(&self, square: Square) -> Bitboard {
-        self.w_bishop_moves(square) & self.diagonal_pin
-    }
-    #[inline]
-    pub fn b_pinned_rook_moves(&self, square: Square) -> Bitboard {
-        self.b_rook_moves(square) & self.orthogonal_pin
-    }
-    #[inline]
-    pub fn b_pinned_bishop_moves(&self, square: Square) -> Bitboard {
-        self.b_bishop_moves(square) & self.diagonal_pin
+    pub fn pinned_bishop_moves<S: Side>(&self, square: Square) -> Bitboard {
+        self.bishop_moves::<S>(square) & self.diagonal_pin
     }
 
     //Non sliding pieces
     #[inline]
-    pub fn w_king_move(&self, square: Square) -> Bitboard {
-        unsafe { KING_MASK[square as usize] & !self.w_board }
-    }
-    #[inline]
-    pub fn w_knight_move(&self, square: Square) -> Bitboard {
-        unsafe { KNIGHT_MASK[square as usize] & !self.w_board & self.checked_squares }
-    }
-    #[inline]
-    pub fn b_king_move(&self, square: Square) -> Bitboard {
-        unsafe { KING_MASK[square as usize] & !self.b_board }
+    pub fn king_move<S: Side>(&self, square: Square) -> Bitboard {
+        KING_MASK[square] & !S::board(self)
     }
     #[inline]
-    pub fn b_knight_move(&self, square: Square) -> Bitboard {
-        unsafe { KNIGHT_MASK[square as usize] & !self.b_board & self.checked_squares }
+    pub fn knight_move<S: Side>(&self, square: Square) -> Bitboard {
+        KNIGHT_MASK[square] & !S::board(self) & self.checked_squares
     }
 }
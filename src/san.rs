@@ -0,0 +1,312 @@
+use crate::{
+    board::{Position, Square, EMPTY},
+    mve::{Move, KING_CASTLE, QUEEN_CASTLE},
+    piece::PieceKind,
+};
+
+const PIECE_LETTERS: [u8; 6] = *b"NBRQPK";
+
+//the piece kind of a letter used in SAN, pawns do not have a letter
+fn piece_kind(letter: u8) -> Option<PieceKind> {
+    match letter.to_ascii_uppercase() {
+        b'N' => Some(PieceKind::Knight),
+        b'B' => Some(PieceKind::Bishop),
+        b'R' => Some(PieceKind::Rook),
+        b'Q' => Some(PieceKind::Queen),
+        b'K' => Some(PieceKind::King),
+        _ => None,
+    }
+}
+
+//the squares a SAN move can name besides the target square, like the "b" in "Nbd7"
+#[derive(Clone, Copy)]
+struct Disambiguation {
+    file: Option<u8>,
+    rank: Option<u8>,
+}
+
+impl Disambiguation {
+    //reads the characters between the piece letter and the target square,
+    //capture and long algebraic separators are ignored
+    fn parse(text: &[u8]) -> Option<Disambiguation> {
+        let mut disambiguation = Disambiguation {
+            file: None,
+            rank: None,
+        };
+        for &letter in text {
+            match letter {
+                b'a'..=b'h' if disambiguation.file.is_none() => {
+                    disambiguation.file = Some(letter - b'a')
+                }
+                b'1'..=b'8' if disambiguation.rank.is_none() => {
+                    disambiguation.rank = Some(letter - b'1')
+                }
+                b'x' | b'X' | b':' | b'-' => {}
+                _ => return None,
+            }
+        }
+        Some(disambiguation)
+    }
+
+    fn matches(self, square: Square) -> bool {
+        self.file.is_none_or(|file| file == square.file())
+            && self.rank.is_none_or(|rank| rank == square.rank())
+    }
+}
+
+impl Position {
+    //the move in standard algebraic notation, like "Nbd7", "exd6", "O-O-O" or "e8=Q+"
+    //the move has to be legal in this position
+    pub fn to_san(&self, mve: Move) -> String {
+        let mut pos = self.clone();
+        let legal_moves = pos.legal_moves();
+        let from = mve.from();
+        let to = mve.to();
+        let piece = self.pieces[from].expect("no piece on the from square");
+
+        let mut san = String::new();
+        match mve.flag() {
+            KING_CASTLE => san.push_str("O-O"),
+            QUEEN_CASTLE => san.push_str("O-O-O"),
+            _ if piece == PieceKind::Pawn => {
+                if mve.is_capture() {
+                    san.push((b'a' + from.file()) as char);
+                    san.push('x');
+                }
+                san.push_str(&to.to_string());
+                if let Some(promotion) = mve.promotion() {
+                    san.push('=');
+                    san.push(PIECE_LETTERS[promotion.index()] as char);
+                }
+            }
+            _ => {
+                san.push(PIECE_LETTERS[piece.index()] as char);
+                //other pieces of the same kind which can move to the same square
+                let others = legal_moves.iter().filter(|other| {
+                    **other != mve && other.to() == to && self.pieces[other.from()] == Some(piece)
+                });
+                let mut ambiguous = false;
+                let mut same_file = false;
+                let mut same_rank = false;
+                for other in others {
+                    ambiguous = true;
+                    same_file |= other.from().file() == from.file();
+                    same_rank |= other.from().rank() == from.rank();
+                }
+                //the file is preferred, then the rank and only if both are shared the square
+                if ambiguous && (!same_file || same_rank) {
+                    san.push((b'a' + from.file()) as char);
+                }
+                if ambiguous && same_file {
+                    san.push((b'1' + from.rank()) as char);
+                }
+                if mve.is_capture() {
+                    san.push('x');
+                }
+                san.push_str(&to.to_string());
+            }
+        }
+
+        pos.make_move(mve);
+        if pos.checkers() != EMPTY {
+            if pos.legal_moves().is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+        san
+    }
+
+    //finds the legal move written in standard algebraic notation, common variants like "0-0",
+    //lowercase piece letters, missing or wrong check suffixes, annotations like "!?",
+    //"exd6 e.p." and long algebraic moves like "Ng1-f3" or "e2e4" are accepted
+    pub fn parse_san(&self, san: &str) -> Result<Move, String> {
+        let annotations: &[char] = &['+', '#', '!', '?'];
+        let mut text = san.trim().trim_end_matches(annotations);
+        if let Some(stripped) = text
+            .strip_suffix("e.p.")
+            .or_else(|| text.strip_suffix("ep"))
+        {
+            text = stripped.trim_end().trim_end_matches(annotations);
+        }
+
+        let mut pos = self.clone();
+        let legal_moves = pos.legal_moves();
+        let find = |is_match: &dyn Fn(Move) -> bool| -> Result<Option<Move>, String> {
+            let mut matches = legal_moves.iter().filter(|mve| is_match(**mve));
+            match (matches.next(), matches.next()) {
+                (Some(mve), None) => Ok(Some(*mve)),
+                (Some(_), Some(_)) => Err(format!("ambiguous move {}", san)),
+                (None, _) => Ok(None),
+            }
+        };
+        let illegal = || format!("illegal move {}", san);
+
+        //castling with letter O, digit zero and with or without dashes
+        let castle = text
+            .bytes()
+            .filter(|letter| *letter != b'-')
+            .try_fold(0, |count, letter| {
+                matches!(letter, b'O' | b'o' | b'0').then_some(count + 1)
+            });
+        if let Some(count @ (2 | 3)) = castle {
+            let flag = if count == 2 {
+                KING_CASTLE
+            } else {
+                QUEEN_CASTLE
+            };
+            return find(&|mve| mve.flag() == flag)?.ok_or_else(illegal);
+        }
+
+        let bytes = text.as_bytes();
+        //the promotion piece follows the target square with or without "="
+        let (bytes, promotion) = match bytes {
+            [rest @ .., b'=', letter] => (rest, Some(*letter)),
+            [rest @ .., b'1' | b'8', letter] if letter.is_ascii_alphabetic() => {
+                (&bytes[..rest.len() + 1], Some(*letter))
+            }
+            _ => (bytes, None),
+        };
+        let promotion = match promotion {
+            Some(letter) => match piece_kind(letter) {
+                Some(PieceKind::King) | None => {
+                    return Err(format!("invalid promotion in {}", san))
+                }
+                kind => kind,
+            },
+            None => None,
+        };
+
+        let Some((prefix, target)) = bytes.split_last_chunk::<2>() else {
+            return Err(format!("invalid move {}", san));
+        };
+        let to = std::str::from_utf8(target)
+            .ok()
+            .and_then(Square::from_algebraic)
+            .ok_or_else(|| format!("invalid target square in {}", san))?;
+
+        //a lowercase "b" can be the file of a pawn or a bishop, the pawn is tried first
+        let mut interpretations = Vec::with_capacity(2);
+        match prefix {
+            [letter, rest @ ..] if letter.is_ascii_uppercase() || *letter != b'b' => {
+                match piece_kind(*letter) {
+                    Some(kind) => interpretations.push((kind, rest)),
+                    None => interpretations.push((PieceKind::Pawn, prefix)),
+                }
+            }
+            [_, rest @ ..] => {
+                interpretations.push((PieceKind::Pawn, prefix));
+                interpretations.push((PieceKind::Bishop, rest));
+            }
+            [] => interpretations.push((PieceKind::Pawn, prefix)),
+        }
+
+        for (kind, rest) in interpretations {
+            let Some(disambiguation) = Disambiguation::parse(rest) else {
+                continue;
+            };
+            //castling written as a king move like "Kg1" is accepted as well
+            let found = find(&|mve| {
+                mve.written_to() == to
+                    && self.pieces[mve.from()] == Some(kind)
+                    && disambiguation.matches(mve.from())
+                    && mve.promotion() == promotion
+            })?;
+            if let Some(mve) = found {
+                return Ok(mve);
+            }
+        }
+        Err(illegal())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perft::TEST_FENS;
+
+    //every legal move has a different SAN which parses back to the move, also in the positions
+    //after each legal move
+    #[test]
+    fn san_round_trips_legal_moves() {
+        fn check(pos: &mut Position, depth: u8) {
+            let legal_moves = pos.legal_moves();
+            let mut names = Vec::new();
+            for mve in legal_moves.iter() {
+                let san = pos.to_san(*mve);
+                assert_eq!(Ok(*mve), pos.parse_san(&san), "{} {}", pos.fen(), san);
+                assert!(!names.contains(&san), "{} {}", pos.fen(), san);
+                names.push(san);
+                if depth > 1 {
+                    let mut pos_after = pos.clone();
+                    pos_after.make_move(*mve);
+                    check(&mut pos_after, depth - 1);
+                }
+            }
+        }
+        for fen in TEST_FENS {
+            check(&mut Position::from_fen(fen), 2);
+        }
+    }
+
+    #[test]
+    fn san_notation() {
+        let san = |fen: &str, uci: &str| {
+            let pos = Position::from_fen(fen);
+            let mve = *pos
+                .clone()
+                .legal_moves()
+                .iter()
+                .find(|mve| mve.to_string() == uci)
+                .unwrap();
+            (pos.to_san(mve), mve)
+        };
+        let knights = "rnbqkb1r/ppp1pppp/5n2/3p4/3P4/5N2/PPP1PPPP/RNBQKB1R b KQkq - 0 1";
+        let rooks = "4k3/8/8/8/8/R7/8/R3K3 w - - 0 1";
+        let queens = "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1";
+        let en_passant = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        let castling = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let promotion = "k7/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        let mate = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        let pawns = "4k3/8/8/8/8/2p5/1P1P4/4K3 w - - 0 1";
+
+        assert_eq!("Nbd7", san(knights, "b8d7").0);
+        assert_eq!("Nfd7", san(knights, "f6d7").0);
+        assert_eq!("R1a2", san(rooks, "a1a2").0);
+        assert_eq!("R3a2", san(rooks, "a3a2").0);
+        assert_eq!("Qa1b2", san(queens, "a1b2").0);
+        assert_eq!("Qcb2", san(queens, "c1b2").0);
+        assert_eq!("Q3b2", san(queens, "a3b2").0);
+        assert_eq!("exd6", san(en_passant, "e5d6").0);
+        assert_eq!("O-O-O", san(castling, "e1c1").0);
+        assert_eq!("O-O", san(castling, "e1g1").0);
+        assert_eq!("e8=Q+", san(promotion, "e7e8q").0);
+        assert_eq!("e8=N", san(promotion, "e7e8n").0);
+        assert_eq!("Qxf7#", san(mate, "h5f7").0);
+
+        let parse = |fen: &str, text: &str| Position::from_fen(fen).parse_san(text);
+        assert_eq!(Ok(san(castling, "e1c1").1), parse(castling, "0-0-0"));
+        assert_eq!(Ok(san(castling, "e1g1").1), parse(castling, "O-O+"));
+        assert_eq!(Ok(san(castling, "e1g1").1), parse(castling, "Kg1"));
+        assert_eq!(Ok(san(mate, "h5f7").1), parse(mate, "Qxf7"));
+        assert_eq!(Ok(san(mate, "h5f7").1), parse(mate, "qxf7!!"));
+        assert_eq!(
+            Ok(san(en_passant, "e5d6").1),
+            parse(en_passant, "exd6 e.p.")
+        );
+        assert_eq!(Ok(san(promotion, "e7e8q").1), parse(promotion, "e8Q"));
+        assert_eq!(Ok(san(knights, "b8d7").1), parse(knights, "nbd7"));
+        assert_eq!(Ok(san(knights, "f6d7").1), parse(knights, "Nf6-d7"));
+        //a lowercase b is read as a pawn if the pawn can make the move
+        assert_eq!(Ok(san(pawns, "b2c3").1), parse(pawns, "bxc3"));
+        assert_eq!(Ok(san(pawns, "b2c3").1), parse(pawns, "b2c3"));
+        assert_eq!(Ok(san(pawns, "d2c3").1), parse(pawns, "dxc3"));
+
+        assert_eq!(Err("ambiguous move Nd7".to_string()), parse(knights, "Nd7"));
+        assert_eq!(Err("illegal move e5".to_string()), parse(castling, "e5"));
+        assert!(parse(promotion, "e8").is_err());
+        assert!(parse(promotion, "e8=K").is_err());
+        assert!(parse(castling, "Zz9").is_err());
+    }
+}
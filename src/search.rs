@@ -1,34 +1,47 @@
 use crate::{
     board::{Position, Result},
     eval::evaluate,
-    moveorder::{mvv_lva, promising},
+    moveorder::mvv_lva,
+    movepicker::{is_quiet, Heuristics, MovePicker},
     mve::{move_to_algebraic, Move, NULL_MOVE},
 };
 use std::{
     cmp::max,
     sync::{
+        atomic::{AtomicBool, Ordering},
         mpsc::{channel, Receiver},
         Mutex, MutexGuard,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-//using a global mutable var is safe in this case
-//since the only race condition that can occur is not dangerous
-//using a mutex here would only introduce overhead
-static mut STOP_SEARCH: bool = false;
+//the stop flag of the uci search, set by the stop command and the search timer
+//a relaxed load compiles to a plain read so checking the flag at every node costs nothing
+//searches which can not be stopped, like the search benchmark, pass their own flag instead
+static STOP_SEARCH: AtomicBool = AtomicBool::new(false);
 
 pub static SEARCH_ONGOING: Mutex<bool> = Mutex::new(false);
 
-#[inline]
-fn stop_search() -> bool {
-    unsafe { STOP_SEARCH }
+//what every node of one search shares
+struct SearchContext<'a> {
+    heuristics: Heuristics,
+    nodes: u64,
+    stop: &'a AtomicBool,
 }
 
-fn start_search() {
-    unsafe {
-        STOP_SEARCH = false;
+impl SearchContext<'_> {
+    fn new(stop: &AtomicBool) -> SearchContext<'_> {
+        SearchContext {
+            heuristics: Heuristics::new(),
+            nodes: 0,
+            stop,
+        }
+    }
+
+    #[inline]
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
     }
 }
 
@@ -42,16 +55,12 @@ fn stop_search_after(search_time: Duration, stop_light: Receiver<SearchTimerSign
     match stop_light.try_recv() {
         Ok(stop_signal) => {} //if a stop light is recieved, the search is already manually cancelled
         //do nothing as cancelling may stop the next search unintentionally
-        _ => unsafe {
-            STOP_SEARCH = true;
-        },
+        _ => STOP_SEARCH.store(true, Ordering::Relaxed),
     }
 }
 
 pub fn stop_search_immediately() {
-    unsafe {
-        STOP_SEARCH = true;
-    }
+    STOP_SEARCH.store(true, Ordering::Relaxed);
 }
 
 pub fn search_is_ongoing() -> bool {
@@ -73,12 +82,46 @@ const EVAL_WONT_BE_USED: i32 = 0;
 //from the previous depth. If a forced checkmate is found the seach will immediatly finish as the position
 //will be hard solved, it will always find the shortest mate for the attacking player, and the longest delaying
 //sequence for the defending player
-pub fn search_iterative_deepening(pos: &mut Position, search_time: Option<Duration>) {
+//with multi pv the best multi_pv moves are reported at every depth
+//the search stops after the given depth if there is one
+pub fn search_iterative_deepening(
+    pos: &mut Position,
+    search_time: Option<Duration>,
+    max_depth: Option<u8>,
+    multi_pv: usize,
+) {
+    iterative_deepening(pos, search_time, max_depth, multi_pv, true);
+}
+
+//searches for a fixed time without printing anything and returns the best move,
+//used to run test suites
+pub fn search_best_move(pos: &mut Position, search_time: Duration) -> Move {
+    iterative_deepening(pos, Some(search_time), None, 1, false)
+}
+
+//the score of an info line, mates are given in moves
+fn uci_score(eval: i32, depth: u8) -> String {
+    match eval {
+        INF => format!("mate {}", depth / 2),
+        NEG_INF => format!("mate -{}", depth / 2),
+        _ => format!("cp {}", eval),
+    }
+}
+
+fn iterative_deepening(
+    pos: &mut Position,
+    search_time: Option<Duration>,
+    max_depth: Option<u8>,
+    multi_pv: usize,
+    print_info: bool,
+) -> Move {
     let mut depth = 1;
     let mut best_move = NULL_MOVE;
+    let start = Instant::now();
 
     let (sender, receiver) = channel::<SearchTimerSignal>();
-    start_search();
+    STOP_SEARCH.store(false, Ordering::Relaxed);
+    let mut context = SearchContext::new(&STOP_SEARCH);
     if let Some(time) = search_time {
         thread::spawn(move || {
             stop_search_after(time, receiver);
@@ -86,114 +129,168 @@ pub fn search_iterative_deepening(pos: &mut Position, search_time: Option<Durati
     }
 
     loop {
-        let (mve, eval) = search(pos, depth, best_move);
-        if stop_search() {
-            println!("bestmove {}", move_to_algebraic(mve));
-            break;
-        } else if eval == INF || eval == NEG_INF {
-            println!(
-                "info depth {} score mate {} pv {}",
-                depth,
-                if eval == INF {
-                    depth as i8 / 2
-                } else {
-                    -(depth as i8 / 2)
-                },
-                move_to_algebraic(mve),
-            );
-            println!("bestmove {}", move_to_algebraic(mve));
+        let best_moves = search(pos, depth, best_move, multi_pv, &mut context);
+        let (mve, eval) = best_moves[0];
+        let nps = context.nodes as u128 * 1_000_000 / start.elapsed().as_micros().max(1);
+        if context.stopped() {
+            best_move = mve;
             break;
         }
-
-        println!(
-            "info depth {} score cp {} pv {}",
-            depth,
-            eval,
-            move_to_algebraic(mve),
-        );
+        if print_info {
+            for (index, &(mve, eval)) in best_moves.iter().enumerate() {
+                println!(
+                    "info depth {} multipv {} score {} nodes {} nps {} pv {}",
+                    depth,
+                    index + 1,
+                    uci_score(eval, depth),
+                    context.nodes,
+                    nps,
+                    move_to_algebraic(mve),
+                );
+            }
+        }
         best_move = mve;
+        //the position is solved if a forced mate is found
+        if eval == INF || eval == NEG_INF || Some(depth) == max_depth {
+            break;
+        }
         depth += 1;
     }
+    //the search can be stopped before the first move at depth 1 is searched
+    if best_move == NULL_MOVE {
+        best_move = pos.legal_moves().first().copied().unwrap_or(NULL_MOVE);
+    }
+    if print_info {
+        println!("bestmove {}", move_to_algebraic(best_move));
+    }
     //incase the search is manually stopped we need to make sure to cancel the planned stop
     sender.send(SearchTimerSignal::StopLight);
     {
         let mut guard: MutexGuard<bool> = SEARCH_ONGOING.lock().unwrap();
         *guard = false;
     }
+    best_move
 }
 
-//negamax seach helper
-fn search(pos: &mut Position, depth: u8, prev_best_move: Move) -> (Move, i32) {
-    let mut legal_moves = pos.legal_moves();
-
-    let mut alpha = NEG_INF;
-    let mut best_move = prev_best_move;
-
-    if prev_best_move != NULL_MOVE {
-        let mut pos_clone = pos.clone();
-        pos_clone.make_move(prev_best_move);
-        alpha = -negamax_search(&mut pos_clone, depth - 1, NEG_INF, -alpha);
+//searches every depth up to the given depth without a time limit and without printing,
+//returns the best move and the amount of searched nodes which only depends on the position
+//and the depth, this makes the node count usable as a signature of the search behavior
+//the search has its own stop flag which is never set, so a uci stop or a running uci search can not
+//cut it short and it can not clear the stop flag of a running uci search
+pub fn search_fixed_depth(pos: &mut Position, depth: u8) -> (Move, u64) {
+    let mut best_move = NULL_MOVE;
+    let stop = AtomicBool::new(false);
+    let mut context = SearchContext::new(&stop);
+    for depth in 1..=depth {
+        let (mve, eval) = search(pos, depth, best_move, 1, &mut context)[0];
+        best_move = mve;
+        if eval == INF || eval == NEG_INF {
+            break;
+        }
     }
-    legal_moves.0[..legal_moves.1].sort_unstable_by_key(|x| promising(pos, x));
+    (best_move, context.nodes)
+}
 
-    for i in 0..legal_moves.1 {
-        let mve = legal_moves.0[i];
-        //prevent the prev best move form being searched twice
-        if mve == prev_best_move {
-            continue;
-        }
+//negamax seach helper, returns the best multi_pv moves with their evaluation from best to worst
+//the best move from the previous depth is searched first
+fn search(
+    pos: &mut Position,
+    depth: u8,
+    prev_best_move: Move,
+    multi_pv: usize,
+    context: &mut SearchContext,
+) -> Vec<(Move, i32)> {
+    let mut best_moves: Vec<(Move, i32)> = Vec::with_capacity(multi_pv + 1);
+
+    let mut move_picker = MovePicker::new(prev_best_move, context.heuristics.killers[0]);
+    while let Some(mve) = move_picker.next(pos, &context.heuristics) {
+        //only moves better than the worst of the best moves need an exact evaluation
+        let alpha = if best_moves.len() < multi_pv {
+            NEG_INF
+        } else {
+            best_moves[multi_pv - 1].1
+        };
         let mut pos_clone = pos.clone();
         pos_clone.make_move(mve);
-        let eval = -negamax_search(&mut pos_clone, depth - 1, NEG_INF, -alpha);
-        if stop_search() {
-            return (best_move, EVAL_WONT_BE_USED);
+        let eval = -negamax_search(&mut pos_clone, depth - 1, 1, NEG_INF, -alpha, context);
+        if context.stopped() {
+            let best_move = best_moves.first().map_or(prev_best_move, |&(mve, _)| mve);
+            return vec![(best_move, EVAL_WONT_BE_USED)];
         }
-        if eval > alpha || best_move == NULL_MOVE {
-            alpha = eval;
-            best_move = mve;
+        if best_moves.len() < multi_pv || eval > alpha {
+            let index = best_moves
+                .iter()
+                .position(|&(_, other)| other < eval)
+                .unwrap_or(best_moves.len());
+            best_moves.insert(index, (mve, eval));
+            best_moves.truncate(multi_pv);
         }
     }
-    (best_move, alpha)
+    if best_moves.is_empty() {
+        best_moves.push((prev_best_move, NEG_INF));
+    }
+    best_moves
 }
 
 //negamax with alpha-beta pruning
-fn negamax_search(pos: &mut Position, depth: u8, mut alpha: i32, beta: i32) -> i32 {
-    if stop_search() {
+fn negamax_search(
+    pos: &mut Position,
+    depth: u8,
+    ply: usize,
+    mut alpha: i32,
+    beta: i32,
+    context: &mut SearchContext,
+) -> i32 {
+    if context.stopped() {
         return EVAL_WONT_BE_USED;
     }
     if depth == 0 {
-        return quiescence_search(pos, alpha, beta);
+        return quiescence_search(pos, alpha, beta, context);
     }
+    context.nodes += 1;
 
-    let mut legal_moves = pos.legal_moves();
-
-    if legal_moves.1 == 0 {
-        if pos.result == Result::Checkmate {
-            return NEG_INF;
-        }
-        return 0;
-    }
-    legal_moves.0[..legal_moves.1].sort_unstable_by_key(|x| promising(pos, x));
+    //there is no transposition table so there is no tt move
+    let mut move_picker = MovePicker::new(NULL_MOVE, context.heuristics.killers[ply]);
+    let mut has_legal_move = false;
 
-    for i in 0..legal_moves.1 {
+    while let Some(mve) = move_picker.next(pos, &context.heuristics) {
+        has_legal_move = true;
         let mut pos_clone = pos.clone();
-        pos_clone.make_move(legal_moves.0[i]);
-        let eval = -negamax_search(&mut pos_clone, depth - 1, -beta, -alpha);
+        pos_clone.make_move(mve);
+        let eval = -negamax_search(&mut pos_clone, depth - 1, ply + 1, -beta, -alpha, context);
         if eval >= beta {
+            if is_quiet(mve) {
+                context.heuristics.update(pos, mve, ply, depth);
+            }
             return beta;
         }
         alpha = max(alpha, eval);
     }
+
+    if !has_legal_move {
+        //this only happens in checkmate or stalemate, generating all moves updates the result
+        pos.legal_moves();
+        if pos.result == Result::Checkmate {
+            return NEG_INF;
+        }
+        return 0;
+    }
     alpha
 }
 
 //evaluating a position when the depth is reached is dangerous
 //due to the horizon effect which can be reduced with a quiescence search
 //https://www.chessprogramming.org/Quiescence_Search
-fn quiescence_search(pos: &mut Position, mut alpha: i32, beta: i32) -> i32 {
-    if stop_search() {
+fn quiescence_search(
+    pos: &mut Position,
+    mut alpha: i32,
+    beta: i32,
+    context: &mut SearchContext,
+) -> i32 {
+    if context.stopped() {
         return EVAL_WONT_BE_USED;
     }
+    context.nodes += 1;
     let eval = evaluate(pos);
     if eval >= beta {
         return beta;
@@ -201,12 +298,12 @@ fn quiescence_search(pos: &mut Position, mut alpha: i32, beta: i32) -> i32 {
     alpha = max(alpha, eval);
 
     let mut legal_captures = pos.legal_captures();
-    legal_captures.0[..legal_captures.1].sort_unstable_by_key(|x| mvv_lva(pos, x));
+    legal_captures.sort_by_score(|mve| mvv_lva(pos, &mve));
 
-    for i in 0..legal_captures.1 {
+    for mve in &legal_captures {
         let mut pos_clone = pos.clone();
-        pos_clone.make_move(legal_captures.0[i]);
-        let eval = -quiescence_search(&mut pos_clone, -beta, -alpha);
+        pos_clone.make_move(*mve);
+        let eval = -quiescence_search(&mut pos_clone, -beta, -alpha, context);
         if eval >= beta {
             return beta;
         }
@@ -0,0 +1,70 @@
+use std::time::Instant;
+
+use crate::{board::Position, magic::slider_backend, search::search_fixed_depth};
+
+pub const DEFAULT_SEARCH_BENCH_DEPTH: u8 = 6;
+
+//openings, middlegames and endgames with checks, promotions and en passant captures
+pub const SEARCH_BENCH_POSITIONS: [&str; 12] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 10",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 11",
+    "4rrk1/pp1n3p/3q2pQ/2p1pb2/2PP4/2P3N1/P2B2PP/4RRK1 b - - 7 19",
+    "rq3rk1/ppp2ppp/1bnpb3/3N2B1/3NP3/7P/PPPQ1PP1/2KR3R w - - 7 14",
+    "r1bq1r1k/1pp1n1pp/1p1p4/4p2Q/4Pp2/1BNP4/PPP2PPP/3R1RK1 w - - 2 14",
+    "r3r1k1/2p2ppp/p1p1bn2/8/1q2P3/2NPQN2/PPP3PP/R4RK1 b - - 2 15",
+    "r1bbk1nr/pp3p1p/2n5/1N4p1/2Np1B2/8/PPP2PPP/2KR1B1R w kq - 0 13",
+    "3r1rk1/p5pp/bpp1pp2/8/q1PP1P2/b3P3/P2NQRPP/1R2B1K1 b - - 6 22",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "8/8/8/1P6/8/2k5/p7/1K6 w - - 0 58",
+    "6k1/6p1/6Pp/ppp5/3pn2P/1P3K2/1PP2P2/8 b - - 3 54",
+];
+
+//searches every embedded position to a fixed depth on a single thread
+//the total node count is a signature of the search which only changes when the search behavior
+//changes, the nodes per second can be used to compare the speed of two builds
+pub fn run_search_bench(depth: u8) -> u64 {
+    let start = Instant::now();
+    let mut nodes = 0;
+    for (index, fen) in SEARCH_BENCH_POSITIONS.iter().enumerate() {
+        let (best_move, position_nodes) = search_fixed_depth(&mut Position::from_fen(fen), depth);
+        nodes += position_nodes;
+        println!(
+            "position {} bestmove {} nodes {}",
+            index + 1,
+            best_move,
+            position_nodes
+        );
+    }
+    let duration = start.elapsed();
+    let nodes_per_second = nodes as u128 * 1_000_000 / duration.as_micros().max(1);
+
+    println!();
+    println!("Depth: {}", depth);
+    println!("Time Elapsed: {} ms", duration.as_millis());
+    println!("Nodes searched: {}", nodes);
+    println!("Nodes/Second: {}", nodes_per_second);
+    println!("Slider Backend: {}", slider_backend());
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::stop_search_immediately;
+
+    #[test]
+    fn deterministic_node_count() {
+        for fen in SEARCH_BENCH_POSITIONS {
+            assert!(Position::try_from_fen(fen).is_some(), "{}", fen);
+        }
+        //the signature only changes when the search behavior changes, update it together with
+        //the change which was meant to change it
+        assert_eq!(56891, run_search_bench(3));
+
+        //a stop for the uci search does not cut the benchmark short
+        stop_search_immediately();
+        let fen = SEARCH_BENCH_POSITIONS[1];
+        assert_eq!(22172, search_fixed_depth(&mut Position::from_fen(fen), 3).1);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{
+    bitboard::Bitboard,
+    board::{Color, Position, Square, BLACK, RANK, WHITE},
+    masks::{b_pawn_capture_mask, w_pawn_capture_mask},
+    mve::{Move, KING_CASTLE},
+};
+
+//everything that differs between white and black in move generation
+//code which is generic over a Side is monomorphized into one copy per color,
+//so there is no runtime cost compared to writing separate white and black functions
+pub trait Side {
+    type Opponent: Side;
+
+    const COLOR: Color;
+
+    //pawns can only double push from this rank
+    const DOUBLE_PUSH_RANK: Bitboard;
+    const PROMOTION_RANK: Bitboard;
+    //the rank pawns capture en passant from
+    const EN_PASSANT_RANK: Bitboard;
+
+    //indices into castling_rights
+    const KING_CASTLE_RIGHT: usize;
+    const QUEEN_CASTLE_RIGHT: usize;
+
+    //the castling right a castling move uses
+    #[inline]
+    fn castling_right(mve: Move) -> usize {
+        if mve.flag() == KING_CASTLE {
+            Self::KING_CASTLE_RIGHT
+        } else {
+            Self::QUEEN_CASTLE_RIGHT
+        }
+    }
+
+    fn piece_board(pos: &Position) -> &[Bitboard; 6];
+    fn board(pos: &Position) -> Bitboard;
+
+    //shifts a bitboard towards the opponent by a number of ranks
+    fn forward(bitboard: Bitboard, ranks: u32) -> Bitboard;
+    fn pawn_attacks(bitboard: &Bitboard) -> Bitboard;
+}
+
+pub struct White;
+pub struct Black;
+
+impl Side for White {
+    type Opponent = Black;
+
+    const COLOR: Color = WHITE;
+
+    const DOUBLE_PUSH_RANK: Bitboard = RANK[1];
+    const PROMOTION_RANK: Bitboard = RANK[7];
+    const EN_PASSANT_RANK: Bitboard = RANK[4];
+
+    const KING_CASTLE_RIGHT: usize = 0;
+    const QUEEN_CASTLE_RIGHT: usize = 1;
+
+    #[inline]
+    fn piece_board(pos: &Position) -> &[Bitboard; 6] {
+        &pos.w_piece_board
+    }
+    #[inline]
+    fn board(pos: &Position) -> Bitboard {
+        pos.w_board
+    }
+
+    #[inline]
+    fn forward(bitboard: Bitboard, ranks: u32) -> Bitboard {
+        bitboard << (8 * ranks)
+    }
+    #[inline]
+    fn pawn_attacks(bitboard: &Bitboard) -> Bitboard {
+        w_pawn_capture_mask(bitboard)
+    }
+}
+
+impl Side for Black {
+    type Opponent = White;
+
+    const COLOR: Color = BLACK;
+
+    const DOUBLE_PUSH_RANK: Bitboard = RANK[6];
+    const PROMOTION_RANK: Bitboard = RANK[0];
+    const EN_PASSANT_RANK: Bitboard = RANK[3];
+
+    const KING_CASTLE_RIGHT: usize = 2;
+    const QUEEN_CASTLE_RIGHT: usize = 3;
+
+    #[inline]
+    fn piece_board(pos: &Position) -> &[Bitboard; 6] {
+        &pos.b_piece_board
+    }
+    #[inline]
+    fn board(pos: &Position) -> Bitboard {
+        pos.b_board
+    }
+
+    #[inline]
+    fn forward(bitboard: Bitboard, ranks: u32) -> Bitboard {
+        bitboard >> (8 * ranks)
+    }
+    #[inline]
+    fn pawn_attacks(bitboard: &Bitboard) -> Bitboard {
+        b_pawn_capture_mask(bitboard)
+    }
+}
+
+//which kinds of moves a generator produces, also resolved at compile time
+pub trait GenType {
+    const QUIETS: bool;
+    const CAPTURES: bool;
+    //only the quiet moves which check the enemy king
+    const CHECKS: bool = false;
+    //only the moves out of check, no moves if the side to move is not in check
+    const EVASIONS: bool = false;
+}
+
+pub struct AllMoves;
+pub struct Captures;
+pub struct Quiets;
+pub struct QuietChecks;
+pub struct Evasions;
+
+impl GenType for AllMoves {
+    const QUIETS: bool = true;
+    const CAPTURES: bool = true;
+}
+
+impl GenType for Captures {
+    const QUIETS: bool = false;
+    const CAPTURES: bool = true;
+}
+
+impl GenType for Quiets {
+    const QUIETS: bool = true;
+    const CAPTURES: bool = false;
+}
+
+impl GenType for QuietChecks {
+    const QUIETS: bool = true;
+    const CAPTURES: bool = false;
+    const CHECKS: bool = true;
+}
+
+impl GenType for Evasions {
+    const QUIETS: bool = true;
+    const CAPTURES: bool = true;
+    const EVASIONS: bool = true;
+}
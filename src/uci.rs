@@ -1,68 +1,161 @@
 use crate::{
     board::{Position, Result},
-    init::initialize_engine,
-    perft::bench,
+    epd::run_test_suite,
+    mve::algebraic_to_move,
+    options::{EngineConfig, ENGINE_OPTIONS},
+    perft::{bench, movegen_bench, run_perft_suite},
+    perftcompare::run_perft_compare,
     search::stop_search_immediately,
-    ucigo::{search_position_from_time_info, ucigo},
+    searchbench::{run_search_bench, DEFAULT_SEARCH_BENCH_DEPTH},
+    ucigo::ucigo,
 };
-use std::io::{stdin, BufRead, Write};
+use std::{
+    io::{stdin, BufRead, Write},
+    time::Duration,
+};
+
+//all commands understood by the engine, the last ones are not part of the uci protocol
+const COMMANDS: [&str; 20] = [
+    "uci",
+    "debug",
+    "isready",
+    "setoption",
+    "register",
+    "ucinewgame",
+    "position",
+    "go",
+    "stop",
+    "ponderhit",
+    "quit",
+    "bench",
+    "searchbench",
+    "testsuite",
+    "perftsuite",
+    "perft-compare",
+    "fen",
+    "state",
+    "board",
+    "legal",
+];
+
+//splits a line into a command and its arguments
+//tokens can be separated by any amount of whitespace and unknown tokens before the command
+//are skipped as required by the uci protocol, "joho debug on" is parsed as "debug on"
+pub fn tokenize(line: &str) -> Option<(&str, Vec<&str>)> {
+    let mut tokens = line.split_whitespace();
+    let command = tokens.find(|token| COMMANDS.contains(token))?;
+    Some((command, tokens.collect()))
+}
+
+//diagnostic output which is only sent to the gui in debug mode
+fn info_string(debug: bool, message: &str) {
+    if debug {
+        println!("info string {}", message);
+    }
+}
 
 //handles all uci communication, the thread will block during all non thead safe operations and
 //all thread safe operations will be handled in a diffent thread
 //this ensures thread safety and allows the user to stop searches in a different thread as searches wont
 //block input handling
 pub fn uci() {
-    let mut initialized = false;
+    let mut debug = false;
 
     let mut pos = Position::startpos();
+    let mut config = EngineConfig::new();
 
     for line_res in stdin().lock().lines() {
         let line = line_res.unwrap();
 
-        if line == "uci" {
-            println!("id name chessengine\nid author linusg\nuciok");
-        } else if line == "isready" {
-            if !initialized {
-                initialize_engine();
-                initialized = true;
+        let Some((command, args)) = tokenize(&line) else {
+            if !line.trim().is_empty() {
+                println!("unknown command");
             }
-            println!("readyok");
-        } else if line == "position startpos" {
-            pos = Position::startpos();
-        } else if let Some(stripped) = line.strip_prefix("position startpos moves") {
-            pos = Position::from_move_string(stripped);
-        } else if let Some(stripped) = line.strip_prefix("position fen") {
-            if let Some(i) = line.find("moves") {
-                let fen = &line["position fen".len()..i].trim();
-                let moves = &line[i + "moves".len()..].trim();
-                pos = Position::from_fen_move_string(fen, moves);
-            } else {
-                pos = Position::from_fen(stripped.trim());
+            continue;
+        };
+
+        match command {
+            "uci" => {
+                println!("id name chessengine\nid author linusg");
+                for option in ENGINE_OPTIONS.iter() {
+                    println!("{}", option.uci_string());
+                }
+                println!("uciok");
             }
-        } else if let Some(stripped) = line.strip_prefix("go") {
-            if !initialized {
-                println!("Engine must be initialized before starting a search with \"isready\"");
-            } else {
-                ucigo(&pos, stripped);
+            "debug" => match args.first() {
+                Some(&"on") => debug = true,
+                Some(&"off") => debug = false,
+                _ => println!("unexpected format use debug [on | off]"),
+            },
+            "isready" => println!("readyok"),
+            "setoption" => match config.set_option(&args.join(" ")) {
+                Ok(()) => info_string(debug, &format!("{:?}", config)),
+                //guis only expect info strings for messages from the engine
+                Err(error) => println!("info string {}", error),
+            },
+            //the engine does not require registration
+            "register" => {}
+            "ucinewgame" => {
+                pos = Position::startpos();
+                info_string(debug, "new game");
             }
-        } else if let Some(stripped) = line.strip_prefix("bench") {
-            if !initialized {
-                println!("Engine must be initialized before starting a bench with \"isready\"");
-            } else if let Ok(depth) = stripped.trim().parse() {
-                bench(&mut pos, depth);
-            } else {
-                println!("unexpected format use bench <depth>");
+            "position" => {
+                if let Some(new_pos) = parse_position(&args) {
+                    pos = new_pos;
+                    info_string(debug, &format!("position {}", pos.fen()));
+                } else {
+                    println!("unexpected format use position [fen <fen> | startpos] moves <moves>");
+                }
             }
-        } else if line == "stop" {
-            stop_search_immediately()
-        } else if line == "board" {
-            pos.print();
-        } else if line == "fen" {
-            println!("{}", pos.fen());
-        } else if line == "state" {
-            if !initialized {
-                println!("Engine must be initialized before calculating state with \"isready\"");
-            } else {
+            "go" => ucigo(&pos, &args.join(" "), &config),
+            "bench" => match args[..] {
+                [depth] if depth.parse::<u8>().is_ok_and(|depth| depth > 0) => {
+                    let depth = depth.parse().unwrap();
+                    if let Err(error) = bench(&mut pos, depth, config.hash_mb, config.threads) {
+                        println!("info string {}", error);
+                    }
+                }
+                //unhashed and single threaded, used by compare_nps.py
+                [depth, "movegen"] if depth.parse::<u8>().is_ok_and(|depth| depth > 0) => {
+                    movegen_bench(&mut pos, depth.parse().unwrap());
+                }
+                _ => println!("unexpected format use bench <depth> [movegen]"),
+            },
+            "searchbench" => match args[..] {
+                [] => {
+                    run_search_bench(DEFAULT_SEARCH_BENCH_DEPTH);
+                }
+                [depth] if depth.parse::<u8>().is_ok_and(|depth| depth > 0) => {
+                    run_search_bench(depth.parse().unwrap());
+                }
+                _ => println!("unexpected format use searchbench [depth]"),
+            },
+            "testsuite" => match args[..] {
+                [path, "movetime", movetime]
+                    if movetime.parse::<u64>().is_ok_and(|movetime| movetime > 0) =>
+                {
+                    run_test_suite(path, Duration::from_millis(movetime.parse().unwrap()));
+                }
+                _ => println!("unexpected format use testsuite <file> movetime <ms>"),
+            },
+            "perftsuite" => match args[..] {
+                [path, depth] if depth.parse::<u8>().is_ok_and(|depth| depth > 0) => {
+                    run_perft_suite(path, depth.parse().unwrap());
+                }
+                _ => println!("unexpected format use perftsuite <file> <depth>"),
+            },
+            "perft-compare" => match args[..] {
+                [path, depth] if depth.parse::<u8>().is_ok_and(|depth| depth > 0) => {
+                    run_perft_compare(path, &pos, depth.parse().unwrap());
+                }
+                _ => println!("unexpected format use perft-compare <engine> <depth>"),
+            },
+            "stop" => stop_search_immediately(),
+            //pondering is not supported so there is nothing to do
+            "ponderhit" => {}
+            "board" => pos.print(),
+            "fen" => println!("{}", pos.fen()),
+            "state" => {
                 //legal moves must be calculated to update result
                 pos.legal_moves();
                 match pos.result {
@@ -71,16 +164,78 @@ pub fn uci() {
                     Result::Draw => println!("draw"),
                 }
             }
-        } else if line == "legal moves" {
-            if !initialized {
-                println!("Engine must be initialized before generating moves with \"isready\"");
-            } else {
-                pos.print_legal_moves();
-            }
-        } else if line == "quit" {
-            break;
-        } else {
-            println!("unknown command");
+            "legal" if args.first() == Some(&"moves") => pos.print_legal_moves(),
+            "quit" => break,
+            _ => println!("unknown command"),
+        }
+    }
+}
+
+//parses the arguments of "position [fen <fenstring> | startpos] moves <move1> ... <movei>"
+fn parse_position(args: &[&str]) -> Option<Position> {
+    let moves_index = args
+        .iter()
+        .position(|token| *token == "moves")
+        .unwrap_or(args.len());
+
+    let mut pos = match args.first() {
+        Some(&"startpos") => Position::startpos(),
+        Some(&"fen") if moves_index > 1 => Position::try_from_fen(&args[1..moves_index].join(" "))?,
+        _ => return None,
+    };
+    //a wrong move from the gui would corrupt the board, so every move must be legal
+    for algebraic in args.get(moves_index + 1..).unwrap_or_default() {
+        let mve = algebraic_to_move(&pos, algebraic)?;
+        if !pos.legal_moves().contains(&mve) {
+            return None;
         }
+        pos.make_move(mve);
+    }
+    Some(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_commands() {
+        assert_eq!(Some(("isready", vec![])), tokenize("  isready  "));
+        assert_eq!(Some(("debug", vec!["on"])), tokenize("joho debug on"));
+        assert_eq!(
+            Some(("position", vec!["startpos", "moves", "e2e4"])),
+            tokenize("position \t startpos   moves e2e4")
+        );
+        assert_eq!(None, tokenize("hello world"));
+        assert_eq!(None, tokenize(""));
+    }
+
+    #[test]
+    fn position_arguments() {
+        let pos = parse_position(&["startpos", "moves", "e2e4", "e7e5"]).unwrap();
+        assert_eq!(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            pos.fen()
+        );
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let tokens: Vec<&str> = fen.split_whitespace().collect();
+        let args = [&["fen"], &tokens[..]].concat();
+        assert_eq!(fen, parse_position(&args).unwrap().fen());
+        assert!(parse_position(&["fen"]).is_none());
+        //the move counters are optional
+        let pos = parse_position(&["fen", "4k3/8/8/8/8/8/8/4K3", "w", "-", "-", "moves", "e1d1"]);
+        assert_eq!("4k3/8/8/8/8/8/8/3K4 b - - 1 1", pos.unwrap().fen());
+        //eleven white queens are more than the promotions of eight pawns
+        let fen = "Q6Q/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1";
+        let tokens: Vec<&str> = fen.split_whitespace().collect();
+        assert!(parse_position(&[&["fen"], &tokens[..]].concat()).is_none());
+        let fen = "R6R/3Q4/1Q4Q1/4Q3/2Q4Q/Q4Q2/pp1Q4/kBNN1KB1 w - - 0 1";
+        let tokens: Vec<&str> = fen.split_whitespace().collect();
+        let mut pos = parse_position(&[&["fen"], &tokens[..]].concat()).unwrap();
+        assert_eq!(218, pos.legal_moves().len());
+        assert!(parse_position(&[]).is_none());
+        assert!(parse_position(&["startpos", "moves", "e2"]).is_none());
+        assert!(parse_position(&["startpos", "moves", "e2e5"]).is_none());
+        assert!(parse_position(&["startpos", "moves", "e2e4", "e7e5", "e1e2", "e8e7"]).is_some());
     }
 }
@@ -1,4 +1,5 @@
 use std::{
+    str::{FromStr, SplitWhitespace},
     sync::MutexGuard,
     thread,
     time::{self, Duration},
@@ -6,93 +7,125 @@ use std::{
 
 use crate::{
     board::{Position, BLACK, WHITE},
+    options::EngineConfig,
+    perft::hashed_divide,
     search::{search_is_ongoing, search_iterative_deepening, SEARCH_ONGOING},
 };
 
-//handles the supported uci flags and starts the corresponding search
-pub fn ucigo(pos: &Position, flags: &str) {
-    if flags.contains("infinite") {
-        search_position_infinite(pos);
-        return;
-    }
-    //supported flags
-    let mut wtime: Option<u64> = None;
-    let mut btime: Option<u64> = None;
-    let mut winc: u64 = 0;
-    let mut binc: u64 = 0;
+//the supported parameters of "go", the other parameters like ponder, searchmoves or nodes are
+//skipped together with any unknown token
+#[derive(Debug, Default, PartialEq)]
+pub struct GoParameters {
+    pub perft: Option<u8>,
+    pub infinite: bool,
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: u64,
+    pub binc: u64,
+    pub movetime: Option<u64>,
+    pub depth: Option<u8>,
+}
 
-    let flag_elements: Vec<&str> = flags.split_whitespace().collect();
-    let len = flag_elements.len();
+//the value after the parameter name
+fn go_value<T: FromStr>(tokens: &mut SplitWhitespace, name: &str) -> Result<T, String> {
+    tokens
+        .next()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("missing or invalid value for {}", name))
+}
 
-    //start an infinite search if no flags are provided
-    if len == 0 {
-        search_position_infinite(pos);
-        return;
+//the parameters are found by their name so they can be given in any order
+pub fn parse_go(flags: &str) -> Result<GoParameters, String> {
+    let mut parameters = GoParameters::default();
+    let mut tokens = flags.split_whitespace();
+    while let Some(token) = tokens.next() {
+        match token {
+            "perft" => parameters.perft = Some(go_value(&mut tokens, token)?),
+            "infinite" => parameters.infinite = true,
+            "wtime" => parameters.wtime = Some(go_value(&mut tokens, token)?),
+            "btime" => parameters.btime = Some(go_value(&mut tokens, token)?),
+            "winc" => parameters.winc = go_value(&mut tokens, token)?,
+            "binc" => parameters.binc = go_value(&mut tokens, token)?,
+            "movetime" => parameters.movetime = Some(go_value(&mut tokens, token)?),
+            "depth" => parameters.depth = Some(go_value(&mut tokens, token)?),
+            _ => {}
+        }
     }
+    if parameters.perft == Some(0) || parameters.depth == Some(0) {
+        return Err("the depth must be positive".to_string());
+    }
+    Ok(parameters)
+}
 
-    for i in (0..len).step_by(2) {
-        let flag = flag_elements[i];
-        if i + 1 >= flag_elements.len() {
-            break;
+//handles the supported uci flags and starts the corresponding search
+pub fn ucigo(pos: &Position, flags: &str, config: &EngineConfig) {
+    let parameters = match parse_go(flags) {
+        Ok(parameters) => parameters,
+        Err(error) => {
+            println!("{}", error);
+            unexpeced_ucigo_format();
+            return;
         }
-        let flag_value = flag_elements[i + 1];
-
-        match flag {
-            "wtime" => {
-                if let Ok(number) = flag_value.parse() {
-                    wtime = Some(number);
-                } else {
-                    unexpeced_ucigo_format();
-                    return;
-                }
-            }
-            "btime" => {
-                if let Ok(number) = flag_value.parse() {
-                    btime = Some(number);
-                } else {
-                    unexpeced_ucigo_format();
-                    return;
-                }
-            }
-            "winc" => {
-                if let Ok(number) = flag_value.parse() {
-                    winc = number;
-                } else {
-                    unexpeced_ucigo_format();
-                    return;
-                }
-            }
-            "binc" => {
-                if let Ok(number) = flag_value.parse() {
-                    binc = number;
-                } else {
-                    unexpeced_ucigo_format();
-                    return;
-                }
-            }
-            _ => {}
+    };
+    if let Some(depth) = parameters.perft {
+        if let Err(error) = go_perft(pos, depth, config) {
+            println!("info string {}", error);
         }
+        return;
     }
-    //to start a timed search both wtime and btime must be provided
-    if let (Some(wtime_ms), Some(btime_ms)) = (wtime, btime) {
-        search_position_from_time_info(pos, wtime_ms, btime_ms, winc, binc);
-    } else {
-        unexpeced_ucigo_format();
+
+    let time_control = match (parameters.wtime, parameters.btime) {
+        (Some(wtime), Some(btime)) => Some(match pos.color_to_move {
+            WHITE => (wtime / 50) + parameters.winc,
+            BLACK => (btime / 50) + parameters.binc,
+        }),
+        (None, None) => None,
+        //to start a timed search both wtime and btime must be provided
+        _ => {
+            unexpeced_ucigo_format();
+            return;
+        }
+    };
+    //without a time limit the search runs until "stop" or until the depth is reached
+    let search_time = match (parameters.infinite, parameters.movetime, time_control) {
+        (true, _, _) => None,
+        (false, Some(movetime), _) => Some(movetime),
+        (false, None, time_control) => time_control,
+    };
+    //leave time for the gui and communication latency
+    let search_time = search_time
+        .map(|time| Duration::from_millis(time.saturating_sub(config.move_overhead_ms).max(1)));
+    let depth = parameters.depth.filter(|_| !parameters.infinite);
+    search_position(pos, search_time, depth, config.multi_pv);
+}
+
+//prints the leaf node count after every legal move in the same format as stockfish
+pub fn go_perft(pos: &Position, depth: u8, config: &EngineConfig) -> Result<(), String> {
+    let counts = hashed_divide(&mut pos.clone(), depth, config.hash_mb, config.threads)?;
+    for (mve, count) in &counts {
+        println!("{}: {}", mve, count);
     }
+    println!();
+    println!(
+        "Nodes searched: {}",
+        counts.iter().map(|(_, count)| count).sum::<usize>()
+    );
+    Ok(())
 }
 
 fn unexpeced_ucigo_format() {
     println!("unexpected flag format");
-    println!("supported flags: infinite wtime <value> btime <value> winc <value> binc <value>");
+    println!(
+        "supported flags: infinite perft <depth> wtime <value> btime <value> winc <value> binc <value> movetime <value> depth <value>"
+    );
 }
 
-//search until "stop" or search timer is finshed
-pub fn search_position_from_time_info(
+//searches in a new thread until "stop", the search time is over or the depth is reached
+pub fn search_position(
     pos: &Position,
-    wtime_ms: u64,
-    btime_ms: u64,
-    winc_ms: u64,
-    binc_ms: u64,
+    search_time: Option<Duration>,
+    depth: Option<u8>,
+    multi_pv: usize,
 ) {
     if search_is_ongoing() {
         println!("cannot start two seaches at once, write \"stop\" to stop the ongoing search")
@@ -101,29 +134,43 @@ pub fn search_position_from_time_info(
             let mut guard: MutexGuard<bool> = SEARCH_ONGOING.lock().unwrap();
             *guard = true;
         }
-        let search_time = match pos.color_to_move {
-            WHITE => (wtime_ms / 50) + winc_ms,
-            BLACK => (btime_ms / 50) + binc_ms,
-        };
         let mut pos_clone = pos.clone();
         thread::spawn(move || {
-            search_iterative_deepening(&mut pos_clone, Some(Duration::from_millis(search_time)));
+            search_iterative_deepening(&mut pos_clone, search_time, depth, multi_pv);
         });
     }
 }
 
-//search until "stop"
-pub fn search_position_infinite(pos: &Position) {
-    if search_is_ongoing() {
-        println!("cannot start two seaches at once, write \"stop\" to stop the ongoing search")
-    } else {
-        {
-            let mut guard: MutexGuard<bool> = SEARCH_ONGOING.lock().unwrap();
-            *guard = true;
-        }
-        let mut pos_clone = pos.clone();
-        thread::spawn(move || {
-            search_iterative_deepening(&mut pos_clone, None);
-        });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn go_parameters() {
+        assert_eq!(Ok(GoParameters::default()), parse_go(""));
+        assert_eq!(
+            Ok(GoParameters {
+                wtime: Some(1000),
+                btime: Some(2000),
+                winc: 10,
+                ..GoParameters::default()
+            }),
+            parse_go("ponder wtime 1000 btime 2000 movestogo 20 winc 10")
+        );
+        assert_eq!(
+            Ok(GoParameters {
+                movetime: Some(100),
+                depth: Some(5),
+                ..GoParameters::default()
+            }),
+            parse_go("searchmoves e2e4 d2d4  movetime 100\tdepth 5")
+        );
+        //a token containing a parameter name is not the parameter
+        assert!(!parse_go("nodes 10 noninfinite").unwrap().infinite);
+        assert_eq!(Some(4), parse_go("perft 4").unwrap().perft);
+        assert!(parse_go("perft 0").is_err());
+        assert!(parse_go("depth").is_err());
+        assert!(parse_go("movetime -5").is_err());
+        assert!(parse_go("wtime x btime 100").is_err());
     }
 }
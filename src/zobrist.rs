@@ -0,0 +1,141 @@
+use crate::{
+    board::{Color, Position, Square, BLACK, EMPTY},
+    piece::PieceKind,
+    rand::Wyrand,
+};
+
+//random keys which are xored together to a hash of the position
+//https://www.chessprogramming.org/Zobrist_Hashing
+pub struct ZobristKeys {
+    //indexed by color, piece kind and square
+    pub pieces: [[[u64; 64]; 6]; 2],
+    pub black_to_move: u64,
+    //one key for every combination of castling rights, indexed like castling_index
+    pub castling: [u64; 16],
+    //indexed by the file of the en passant target square
+    pub en_passant: [u64; 8],
+}
+
+pub static ZOBRIST: ZobristKeys = zobrist_keys();
+
+const fn zobrist_keys() -> ZobristKeys {
+    let mut rng = Wyrand(0x2545_F491_4F6C_DD1D);
+    let mut pieces = [[[0; 64]; 6]; 2];
+    let mut color = 0;
+    while color < 2 {
+        let mut kind = 0;
+        while kind < 6 {
+            let mut square = 0;
+            while square < 64 {
+                pieces[color][kind][square] = rng.next();
+                square += 1;
+            }
+            kind += 1;
+        }
+        color += 1;
+    }
+    let black_to_move = rng.next();
+
+    //the key of a combination is the xor of the keys of its single rights
+    let mut rights = [0; 4];
+    let mut right = 0;
+    while right < 4 {
+        rights[right] = rng.next();
+        right += 1;
+    }
+    let mut castling = [0; 16];
+    let mut index = 0;
+    while index < 16 {
+        let mut right = 0;
+        while right < 4 {
+            if index & (1 << right) != 0 {
+                castling[index] ^= rights[right];
+            }
+            right += 1;
+        }
+        index += 1;
+    }
+
+    let mut en_passant = [0; 8];
+    let mut file = 0;
+    while file < 8 {
+        en_passant[file] = rng.next();
+        file += 1;
+    }
+    ZobristKeys {
+        pieces,
+        black_to_move,
+        castling,
+        en_passant,
+    }
+}
+
+#[inline]
+pub fn castling_index(castling_rights: &[bool; 4]) -> usize {
+    castling_rights[0] as usize
+        | (castling_rights[1] as usize) << 1
+        | (castling_rights[2] as usize) << 2
+        | (castling_rights[3] as usize) << 3
+}
+
+//the key of the castling rights and the en passant square, these are replaced on every move
+#[inline]
+pub fn state_key(pos: &Position) -> u64 {
+    let mut key = ZOBRIST.castling[castling_index(&pos.castling_rights)];
+    if pos.en_passant_target_square != EMPTY {
+        key ^= ZOBRIST.en_passant[pos.en_passant_target_square.lsb().file() as usize];
+    }
+    key
+}
+
+#[inline]
+pub fn piece_key(color: Color, kind: PieceKind, square: Square) -> u64 {
+    ZOBRIST.pieces[color.index()][kind][square]
+}
+
+impl Position {
+    //the hash of the position calculated from scratch, make_move updates the hash incrementally
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = state_key(self);
+        for square in 0..64 {
+            if let Some(piece) = self.piece_on(Square::new(square)) {
+                hash ^= piece_key(piece.color(), piece.kind(), Square::new(square));
+            }
+        }
+        if self.color_to_move == BLACK {
+            hash ^= ZOBRIST.black_to_move;
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perft::TEST_FENS;
+
+    #[test]
+    fn incremental_hash() {
+        let mut rng = Wyrand(7);
+        for fen in TEST_FENS {
+            for _ in 0..20 {
+                let mut pos = Position::from_fen(fen);
+                for _ in 0..40 {
+                    let legal_moves = pos.legal_moves();
+                    if legal_moves.is_empty() {
+                        break;
+                    }
+                    let mve = legal_moves[rng.next() as usize % legal_moves.len()];
+                    pos.make_move(mve);
+                    assert_eq!(pos.compute_hash(), pos.hash, "{}", pos.fen());
+                }
+            }
+        }
+
+        //transpositions have the same hash
+        let a = Position::from_move_string("g1f3 g8f6 b1c3 b8c6");
+        let b = Position::from_move_string("b1c3 b8c6 g1f3 g8f6");
+        assert_eq!(a.hash, b.hash);
+        assert_ne!(a.hash, Position::startpos().hash);
+    }
+}
@@ -0,0 +1,40 @@
+#this script compares the perft speed of two engine builds, for example before and after a change
+#it uses the unhashed single threaded "bench <depth> movegen" so only the move generation is measured
+#build the old version in a git worktree and pass both binaries
+#python3 compare_nps.py <old engine> <new engine> [depth] [rounds] [fen]
+import statistics
+import subprocess
+import sys
+
+old_engine = sys.argv[1]
+new_engine = sys.argv[2]
+depth = sys.argv[3] if len(sys.argv) > 3 else "6"
+rounds = int(sys.argv[4]) if len(sys.argv) > 4 else 5
+position = "position fen " + sys.argv[5] if len(sys.argv) > 5 else "position startpos"
+
+
+def nodes_per_second(engine):
+    commands = position + "\nbench " + depth + " movegen\nquit\n"
+    output = subprocess.run([engine], input=commands, capture_output=True, text=True).stdout
+    for line in output.splitlines():
+        if line.startswith("Nodes/Second:"):
+            return int(line.split(":")[1])
+    raise RuntimeError(engine + " did not report Nodes/Second")
+
+
+old_results = []
+new_results = []
+
+#the engines are run alternately so both are equally affected by noise on the machine
+for i in range(rounds):
+    old_results.append(nodes_per_second(old_engine))
+    new_results.append(nodes_per_second(new_engine))
+    print("round", i + 1, "old:", old_results[-1], "new:", new_results[-1])
+
+old_median = statistics.median(old_results)
+new_median = statistics.median(new_results)
+
+print()
+print("old median Nodes/Second:", int(old_median))
+print("new median Nodes/Second:", int(new_median))
+print("change: {:+.1f}%".format((new_median / old_median - 1) * 100))